@@ -0,0 +1,32 @@
+-- VAAs observed from the Spy and Wormholescan, one row per digest
+CREATE TABLE IF NOT EXISTS vaas (
+    id UUID PRIMARY KEY,
+    -- keccak256(keccak256(body)), hex-encoded
+    digest TEXT NOT NULL UNIQUE,
+    -- Canonical chain/emitter/sequence message ID
+    message_id TEXT NOT NULL,
+    emitter_chain INTEGER NOT NULL,
+    -- Hex-encoded 32-byte emitter address, without leading 0x
+    emitter_address TEXT NOT NULL,
+    sequence BIGINT NOT NULL,
+    guardian_set_index BIGINT NOT NULL,
+    consistency_level INTEGER NOT NULL,
+    signature_count INTEGER NOT NULL,
+    -- Type of the decoded payload, e.g. token_transfer, NULL when unrecognized
+    payload_type TEXT,
+    -- Timestamp of the VAA body
+    vaa_timestamp TIMESTAMPTZ NOT NULL,
+    -- When the observer first stored the VAA
+    observed_at TIMESTAMPTZ NOT NULL,
+    raw_bytes BYTEA NOT NULL,
+    -- Another digest was already stored for the same message ID
+    conflicting BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+-- A message ID maps to a single VAA, later bodies for it are kept as conflicts
+CREATE UNIQUE INDEX IF NOT EXISTS vaas_message_id_key ON vaas (message_id) WHERE NOT conflicting;
+
+CREATE INDEX IF NOT EXISTS vaas_emitter_sequence_idx ON vaas (emitter_chain, emitter_address, sequence);
+CREATE INDEX IF NOT EXISTS vaas_vaa_timestamp_idx ON vaas (vaa_timestamp);
+CREATE INDEX IF NOT EXISTS vaas_guardian_set_index_idx ON vaas (guardian_set_index);
+CREATE INDEX IF NOT EXISTS vaas_payload_type_idx ON vaas (payload_type);
@@ -0,0 +1,25 @@
+-- Append-only log of everything the observer saw, read models are projected from it
+CREATE TABLE IF NOT EXISTS events (
+    position BIGSERIAL PRIMARY KEY,
+    -- When the observer recorded the event
+    recorded_at TIMESTAMPTZ NOT NULL,
+    -- Event type, e.g. vaa_observed
+    kind TEXT NOT NULL,
+    -- Entity the event is about, e.g. a VAA digest or an emitter
+    stream_id TEXT NOT NULL,
+    payload JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS events_stream_id_idx ON events (stream_id, position);
+CREATE INDEX IF NOT EXISTS events_kind_idx ON events (kind, position);
+
+-- Recorded events are never rewritten, so they can be relied on as an audit trail
+CREATE OR REPLACE FUNCTION reject_event_changes() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'events are append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER events_append_only
+    BEFORE UPDATE OR DELETE ON events
+    FOR EACH ROW EXECUTE FUNCTION reject_event_changes();
@@ -0,0 +1,25 @@
+-- Same layout as migrations/postgres, with the payload stored as JSON text
+CREATE TABLE IF NOT EXISTS events (
+    position INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- When the observer recorded the event
+    recorded_at TEXT NOT NULL,
+    -- Event type, e.g. vaa_observed
+    kind TEXT NOT NULL,
+    -- Entity the event is about, e.g. a VAA digest or an emitter
+    stream_id TEXT NOT NULL,
+    payload TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS events_stream_id_idx ON events (stream_id, position);
+CREATE INDEX IF NOT EXISTS events_kind_idx ON events (kind, position);
+
+-- Recorded events are never rewritten, so they can be relied on as an audit trail
+CREATE TRIGGER IF NOT EXISTS events_no_update BEFORE UPDATE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'events are append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS events_no_delete BEFORE DELETE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'events are append-only');
+END;
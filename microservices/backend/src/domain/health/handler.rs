@@ -12,6 +12,7 @@ use axum::{
 use serde::Serialize;
 use schemars::JsonSchema;
 use std::sync::Arc;
+use tracing::warn;
 
 use crate::AppState;
 
@@ -104,9 +105,18 @@ async fn check_database(_state: &AppState) -> bool {
     true
 }
 
-async fn check_cache(_state: &AppState) -> bool {
-    // TODO: Implement actual cache check
-    true
+// Pings Redis when it is configured
+async fn check_cache(state: &AppState) -> bool {
+    match state.cache() {
+        Some(cache) => match cache.ping().await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Cache check failed: {}", e);
+                false
+            }
+        },
+        None => true,
+    }
 }
 
 async fn check_message_queue(_state: &Arc<AppState>) -> bool {
@@ -0,0 +1,502 @@
+// Backfill of the open sequence gaps from the guardians' public RPC
+
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::AppState;
+use crate::library::errors::Error;
+use super::chains;
+use super::events::{record as record_events, ObserverEvent};
+use super::grpc::ingest::ingest_backfilled_vaa;
+use super::grpc::public_rpc::PublicRpcClient;
+use super::grpc::vaa::OpenGap;
+use super::grpc::SubscribeSignedVaaResponse;
+use super::models::SequenceGap;
+use super::vaa::Vaa;
+
+/// Sequences requested per backfill round, the rest wait for the next one
+pub const MAX_BACKFILL_SEQUENCES: u64 = 1_000;
+// Gaps whose latest outcome is kept in the status
+const MAX_RECORDED_GAPS: usize = 200;
+
+/// How a backfill round left a gap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GapBackfillStatus {
+    /// Every missing sequence was recovered
+    Filled,
+    /// The guardians answered NotFound for the sequences not recovered
+    ConfirmedMissing,
+    /// Some sequences were left to the next round, e.g. held by the Governor
+    Pending,
+    /// Some sequences could not be requested from any guardian, retried on the next round
+    Errored,
+}
+
+/// Outcome of the latest backfill round for a gap.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GapBackfill {
+    pub emitter_chain: u16,
+    pub emitter_chain_name: Option<String>,
+    pub emitter_address: String,
+    /// The gap as it was open when the round started
+    pub gap: SequenceGap,
+    pub outage: bool,
+    pub status: GapBackfillStatus,
+    /// Sequences recovered from the guardians
+    pub recovered: Vec<u64>,
+    /// Sequences the guardians answered NotFound for during this round
+    pub not_found: Vec<u64>,
+    /// Sequences no guardian could be asked for
+    pub errored: Vec<u64>,
+    /// Sequences left to the next round, those held by the Governor included
+    pub pending: u64,
+    #[schemars(with = "String")]
+    pub attempted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct BackfillStatus {
+    pub running: bool,
+    pub rounds: u64,
+    #[schemars(with = "Option<String>")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[schemars(with = "Option<String>")]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// VAAs recovered since startup
+    pub recovered: u64,
+    /// Sequences confirmed missing since startup, they are not requested again
+    pub confirmed_missing: u64,
+    /// Failed sequence requests since startup
+    pub errored: u64,
+    /// Latest outcome of the gaps attempted, most recent last
+    pub gaps: Vec<GapBackfill>,
+}
+
+/// Backfill rounds run since startup.
+#[derive(Debug, Default)]
+pub struct Backfills {
+    status: Mutex<BackfillStatus>,
+    // (chain, emitter, sequence) the guardians answered NotFound for
+    confirmed_missing: Mutex<HashSet<(u16, String, u64)>>,
+}
+
+impl Backfills {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn status(&self) -> BackfillStatus {
+        self.status.lock().await.clone()
+    }
+
+    async fn begin(&self) -> Result<BackfillStatus, Error> {
+        let mut status = self.status.lock().await;
+        if status.running {
+            return Err(Error::Request("A backfill round is already running".to_string()));
+        }
+        status.running = true;
+        status.started_at = Some(Utc::now());
+        status.finished_at = None;
+        Ok(status.clone())
+    }
+
+    async fn finish(&self, outcomes: &[GapBackfill]) {
+        let mut status = self.status.lock().await;
+        status.running = false;
+        status.finished_at = Some(Utc::now());
+        status.rounds += 1;
+        for outcome in outcomes {
+            status.recovered += outcome.recovered.len() as u64;
+            status.confirmed_missing += outcome.not_found.len() as u64;
+            status.errored += outcome.errored.len() as u64;
+            // Only the latest outcome of a gap is kept
+            status.gaps.retain(|gap| {
+                (gap.emitter_chain, &gap.emitter_address, &gap.gap) != (outcome.emitter_chain, &outcome.emitter_address, &outcome.gap)
+            });
+            status.gaps.push(outcome.clone());
+        }
+        let excess = status.gaps.len().saturating_sub(MAX_RECORDED_GAPS);
+        status.gaps.drain(..excess);
+    }
+}
+
+/// Where missing VAAs are requested from, a guardian's public RPC in production.
+#[async_trait]
+pub trait SignedVaaSource: Send {
+    /// Address recorded along with the VAAs recovered from this source
+    fn addr(&self) -> &str;
+
+    /// The signed VAA of the message ID, `None` when the source doesn't have it
+    async fn signed_vaa(&mut self, emitter_chain: u16, emitter_address: &str, sequence: u64) -> Result<Option<Vec<u8>>, Error>;
+}
+
+/// A guardian public RPC endpoint, connected on first use. An endpoint that
+/// can't be reached is not dialed again by the same instance.
+pub struct GuardianRpc {
+    addr: String,
+    client: Option<Result<PublicRpcClient, String>>,
+}
+
+impl GuardianRpc {
+    pub fn new(addr: String) -> Self {
+        Self { addr, client: None }
+    }
+}
+
+#[async_trait]
+impl SignedVaaSource for GuardianRpc {
+    fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    async fn signed_vaa(&mut self, emitter_chain: u16, emitter_address: &str, sequence: u64) -> Result<Option<Vec<u8>>, Error> {
+        if self.client.is_none() {
+            let client = PublicRpcClient::connect(self.addr.clone()).await.map_err(|e| e.to_string());
+            self.client = Some(client);
+        }
+        match self.client.as_mut() {
+            Some(Ok(client)) => client.get_signed_vaa(emitter_chain, emitter_address, sequence).await,
+            Some(Err(e)) => Err(Error::Connection(e.clone())),
+            None => unreachable!(),
+        }
+    }
+}
+
+/// A source per configured guardian public RPC endpoint, asked in order.
+pub fn guardian_sources(rpc_addrs: &[String]) -> Vec<Box<dyn SignedVaaSource>> {
+    rpc_addrs
+        .iter()
+        .map(|addr| Box::new(GuardianRpc::new(addr.clone())) as Box<dyn SignedVaaSource>)
+        .collect()
+}
+
+enum Fetched {
+    Recovered { vaa_bytes: Vec<u8>, addr: String },
+    NotFound,
+    Errored(String),
+}
+
+// Asks each source in turn until one has the VAA. A NotFound from any of them
+// confirms the sequence is missing, unless another source has it.
+async fn fetch(sources: &mut [Box<dyn SignedVaaSource>], emitter_chain: u16, emitter_address: &str, sequence: u64) -> Fetched {
+    let mut not_found = false;
+    let mut error = None;
+    for source in sources.iter_mut() {
+        let fetched = source
+            .signed_vaa(emitter_chain, emitter_address, sequence)
+            .await
+            .and_then(|vaa_bytes| vaa_bytes.map(|vaa_bytes| check(vaa_bytes, emitter_chain, emitter_address, sequence)).transpose());
+        match fetched {
+            Ok(Some(vaa_bytes)) => return Fetched::Recovered { vaa_bytes, addr: source.addr().to_string() },
+            Ok(None) => not_found = true,
+            Err(e) => error = Some(format!("{}: {}", source.addr(), e)),
+        }
+    }
+    match (not_found, error) {
+        (true, _) => Fetched::NotFound,
+        (false, Some(error)) => Fetched::Errored(error),
+        (false, None) => Fetched::Errored("No guardian public RPC endpoint configured".to_string()),
+    }
+}
+
+// Makes sure a source answered with the VAA that was asked for
+fn check(vaa_bytes: Vec<u8>, emitter_chain: u16, emitter_address: &str, sequence: u64) -> Result<Vec<u8>, Error> {
+    let vaa = Vaa::parse(&vaa_bytes)?;
+    if (vaa.emitter_chain, hex::encode(vaa.emitter_address), vaa.sequence) != (emitter_chain, emitter_address.to_string(), sequence) {
+        return Err(Error::External(format!(
+            "Asked for {}/{}/{}, got {}",
+            emitter_chain, emitter_address, sequence, vaa.message_id()
+        )));
+    }
+    Ok(vaa_bytes)
+}
+
+/// Runs a backfill round: every sequence of the gaps still open in the Spy
+/// ingestion is requested from `sources`, up to `MAX_BACKFILL_SEQUENCES`.
+/// Recovered VAAs are ingested, filling their gap, and each gap attempted is
+/// recorded as a `gap_backfilled` event. Sequences confirmed missing are not
+/// requested again, errored ones and those held by the Governor are retried
+/// on the next round.
+pub async fn backfill_gaps(state: &AppState, sources: &mut [Box<dyn SignedVaaSource>]) -> Result<Vec<GapBackfill>, Error> {
+    state.backfills().begin().await?;
+    let outcomes = run_round(state, sources).await;
+    state.backfills().finish(&outcomes).await;
+    Ok(outcomes)
+}
+
+/// Starts a backfill round in the background, from the guardian public RPC
+/// endpoints at `rpc_addrs`. Fails if a round is already running.
+pub async fn start_backfill(state: Arc<AppState>, rpc_addrs: Vec<String>) -> Result<BackfillStatus, Error> {
+    let started = state.backfills().begin().await?;
+    tokio::spawn(async move {
+        let mut sources = guardian_sources(&rpc_addrs);
+        let outcomes = run_round(&state, &mut sources).await;
+        state.backfills().finish(&outcomes).await;
+    });
+    Ok(started)
+}
+
+/// Runs a backfill round every `interval`, the first one right away.
+pub fn spawn_gap_backfill(
+    state: Arc<AppState>,
+    rpc_addrs: Vec<String>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let mut sources = guardian_sources(&rpc_addrs);
+            if let Err(e) = backfill_gaps(&state, &mut sources).await {
+                warn!("Skipping backfill round: {}", e);
+            }
+        }
+    })
+}
+
+async fn run_round(state: &AppState, sources: &mut [Box<dyn SignedVaaSource>]) -> Vec<GapBackfill> {
+    let mut budget = MAX_BACKFILL_SEQUENCES;
+    let mut outcomes = Vec::new();
+
+    for open in state.spy_ingestion().open_gaps().await {
+        if budget == 0 {
+            break;
+        }
+        if let Some(outcome) = backfill_gap(state, sources, &open, &mut budget).await {
+            outcomes.push(outcome);
+        }
+    }
+
+    if !outcomes.is_empty() {
+        let count = |sequences: fn(&GapBackfill) -> usize| outcomes.iter().map(sequences).sum::<usize>();
+        info!(
+            "Backfilled {} gaps: {} VAAs recovered, {} confirmed missing, {} errored",
+            outcomes.len(),
+            count(|outcome| outcome.recovered.len()),
+            count(|outcome| outcome.not_found.len()),
+            count(|outcome| outcome.errored.len()),
+        );
+    }
+    outcomes
+}
+
+// Requests the gap's sequences not confirmed missing yet. Returns `None` when
+// there was nothing left to request.
+async fn backfill_gap(
+    state: &AppState,
+    sources: &mut [Box<dyn SignedVaaSource>],
+    open: &OpenGap,
+    budget: &mut u64,
+) -> Option<GapBackfill> {
+    let backfills = state.backfills();
+    let mut outcome = GapBackfill {
+        emitter_chain: open.emitter_chain,
+        emitter_chain_name: chains::name(open.emitter_chain),
+        emitter_address: open.emitter_address.clone(),
+        gap: open.gap.clone(),
+        outage: open.outage,
+        status: GapBackfillStatus::Filled,
+        recovered: Vec::new(),
+        not_found: Vec::new(),
+        errored: Vec::new(),
+        pending: 0,
+        attempted_at: Utc::now(),
+    };
+    let mut known_missing = 0;
+    // Enqueued VAAs are not signed yet, the guardians would answer NotFound
+    let governed = state.governor().governed(open.emitter_chain, &open.emitter_address).await;
+
+    for sequence in open.gap.from..=open.gap.to {
+        let key = (open.emitter_chain, open.emitter_address.clone(), sequence);
+        if backfills.confirmed_missing.lock().await.contains(&key) {
+            known_missing += 1;
+            continue;
+        }
+        if governed.contains_key(&sequence) {
+            outcome.pending += 1;
+            continue;
+        }
+        if *budget == 0 {
+            outcome.pending += open.gap.to - sequence + 1;
+            break;
+        }
+        *budget -= 1;
+
+        match fetch(sources, open.emitter_chain, &open.emitter_address, sequence).await {
+            Fetched::Recovered { vaa_bytes, addr } => {
+                ingest_backfilled_vaa(state, SubscribeSignedVaaResponse { vaa_bytes }, &addr).await;
+                outcome.recovered.push(sequence);
+            }
+            Fetched::NotFound => {
+                backfills.confirmed_missing.lock().await.insert(key);
+                outcome.not_found.push(sequence);
+            }
+            Fetched::Errored(e) => {
+                warn!("Failed to backfill {}/{}/{}: {}", open.emitter_chain, open.emitter_address, sequence, e);
+                outcome.errored.push(sequence);
+            }
+        }
+    }
+
+    if outcome.recovered.is_empty() && outcome.not_found.is_empty() && outcome.errored.is_empty() {
+        return None;
+    }
+    outcome.status = if !outcome.errored.is_empty() {
+        GapBackfillStatus::Errored
+    } else if outcome.pending > 0 {
+        GapBackfillStatus::Pending
+    } else if known_missing > 0 || !outcome.not_found.is_empty() {
+        GapBackfillStatus::ConfirmedMissing
+    } else {
+        GapBackfillStatus::Filled
+    };
+
+    record_events(state, [ObserverEvent::GapBackfilled {
+        emitter_chain: outcome.emitter_chain,
+        emitter_address: outcome.emitter_address.clone(),
+        gap: outcome.gap.clone(),
+        outage: outcome.outage,
+        status: outcome.status,
+        recovered: outcome.recovered.clone(),
+        not_found: outcome.not_found.clone(),
+        errored: outcome.errored.clone(),
+        pending: outcome.pending,
+    }]).await;
+    Some(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::wormhole::builder::test_vaa_bytes;
+    use std::collections::HashMap;
+    use crate::state::Repositories;
+    use crate::storage::{Repository, VaaRepository, events::{Event, EventStore, MemoryEventStore}, memory::MemoryRepository};
+    use crate::domain::wormhole::events::observed;
+    use crate::domain::wormhole::governor::EnqueuedVaa;
+    use crate::domain::wormhole::grpc::ingest::ingest_vaa;
+    use crate::domain::wormhole::guardians::GuardianSetRegistry;
+    use crate::domain::wormhole::models::VaaRecord;
+
+    // Answers from a fixed table, errors for the sequences it doesn't list
+    struct FakeSource {
+        addr: String,
+        answers: HashMap<u64, Option<Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl SignedVaaSource for FakeSource {
+        fn addr(&self) -> &str {
+            &self.addr
+        }
+
+        async fn signed_vaa(&mut self, _: u16, _: &str, sequence: u64) -> Result<Option<Vec<u8>>, Error> {
+            self.answers
+                .get(&sequence)
+                .cloned()
+                .ok_or_else(|| Error::External("unavailable".to_string()))
+        }
+    }
+
+    fn source(addr: &str, answers: &[(u64, Option<Vec<u8>>)]) -> Box<dyn SignedVaaSource> {
+        Box::new(FakeSource { addr: addr.to_string(), answers: answers.iter().cloned().collect() })
+    }
+
+    async fn test_state() -> AppState {
+        let repositories = Repositories::new(
+            Repository::new(MemoryRepository::new()),
+            VaaRepository::new(MemoryRepository::new()),
+            EventStore::new(MemoryEventStore::new()),
+        );
+        let guardian_sets = Arc::new(GuardianSetRegistry::bundled("devnet").unwrap());
+        AppState::new(repositories, guardian_sets).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backfill_resolves_gaps() {
+        let state = test_state().await;
+        for sequence in [1, 6] {
+            ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(sequence) }).await;
+        }
+
+        // 2 and 4 are recovered from the second guardian, which doesn't have 3
+        // and fails for 5 like the first one
+        let mut sources = vec![
+            source("down:7070", &[]),
+            source("guardian:7070", &[(2, Some(test_vaa_bytes(2))), (3, None), (4, Some(test_vaa_bytes(4)))]),
+        ];
+        let outcomes = backfill_gaps(&state, &mut sources).await.unwrap();
+        assert_eq!(outcomes.len(), 1);
+        let outcome = &outcomes[0];
+        assert_eq!(outcome.status, GapBackfillStatus::Errored);
+        assert_eq!((outcome.recovered.clone(), outcome.not_found.clone(), outcome.errored.clone()), (vec![2, 4], vec![3], vec![5]));
+
+        let gaps: Vec<(u64, u64)> = state.spy_ingestion().open_gaps().await.iter().map(|open| (open.gap.from, open.gap.to)).collect();
+        assert_eq!(gaps, vec![(3, 3), (5, 5)]);
+        assert_eq!(state.vaas_repository().list().await.len(), 4);
+        // Backfilled VAAs were never delivered by the Spy
+        let recovered = VaaRecord::from_bytes(test_vaa_bytes(2), "mainnet").unwrap();
+        assert!(!observed(state.events(), &recovered.digest).await.unwrap());
+
+        let emitter = &state.projections().emitters().await[0];
+        assert_eq!((emitter.backfilled, emitter.confirmed_missing, emitter.missing_sequences), (2, 1, 2));
+
+        // 3 is confirmed missing and not requested again, 5 is retried
+        let mut sources = vec![source("guardian:7070", &[(5, Some(test_vaa_bytes(5)))])];
+        let outcomes = backfill_gaps(&state, &mut sources).await.unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!((outcomes[0].gap.from, outcomes[0].status), (5, GapBackfillStatus::Filled));
+
+        // Only the gap confirmed missing is left, nothing to request anymore
+        assert!(backfill_gaps(&state, &mut sources).await.unwrap().is_empty());
+
+        let status = state.backfills().status().await;
+        assert_eq!((status.rounds, status.recovered, status.confirmed_missing, status.errored), (3, 3, 1, 1));
+        assert_eq!(status.gaps.len(), 2);
+
+        let events = state.events().read(&Default::default()).await.unwrap();
+        let kinds: Vec<&str> = events.iter().map(|stored| stored.event.kind()).filter(|kind| kind.contains("backfilled")).collect();
+        assert_eq!(kinds, vec!["vaa_backfilled", "vaa_backfilled", "gap_backfilled", "vaa_backfilled", "gap_backfilled"]);
+    }
+
+    #[tokio::test]
+    async fn test_backfill_leaves_governed_sequences() {
+        let state = test_state().await;
+        for sequence in [1, 4] {
+            ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(sequence) }).await;
+        }
+        state.governor().update("guardian:7070".to_string(), vec![EnqueuedVaa {
+            emitter_chain: 2,
+            emitter_chain_name: None,
+            emitter_address: "11".repeat(32),
+            sequence: 2,
+            release_time: Utc::now(),
+            notional_value: 1_000_000,
+            tx_hash: "0xabc".to_string(),
+        }]).await;
+
+        let mut sources = vec![source("guardian:7070", &[(2, None), (3, Some(test_vaa_bytes(3)))])];
+        let outcomes = backfill_gaps(&state, &mut sources).await.unwrap();
+        assert_eq!((outcomes[0].status, outcomes[0].pending), (GapBackfillStatus::Pending, 1));
+        assert_eq!((outcomes[0].recovered.clone(), outcomes[0].not_found.clone()), (vec![3], vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_rejects_other_vaas() {
+        let state = test_state().await;
+        for sequence in [1, 3] {
+            ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(sequence) }).await;
+        }
+
+        let mut sources = vec![source("guardian:7070", &[(2, Some(test_vaa_bytes(7)))])];
+        let outcomes = backfill_gaps(&state, &mut sources).await.unwrap();
+        assert_eq!((outcomes[0].status, outcomes[0].errored.clone()), (GapBackfillStatus::Errored, vec![2]));
+        assert_eq!(state.vaas_repository().list().await.len(), 2);
+    }
+}
@@ -0,0 +1,241 @@
+// Assembles and signs VAAs, the inverse of `Vaa::parse`
+
+use k256::ecdsa::SigningKey;
+
+use crate::library::errors::Error;
+use super::signatures::{sign_digest, vaa_digest};
+use super::vaa::{Vaa, SIGNATURE_LEN, VAA_VERSION};
+
+/// Private keys of the devnet (Tilt) guardians, in guardian set 0 order
+pub const DEVNET_GUARDIAN_KEYS: &[&str] = &[
+    "cfb12303a19cde580bb4dd771639b0d26bc68353645571a8cff516ab2ee113a0",
+];
+
+/// Builds a VAA field by field. Signers sign the body as it is when the VAA is
+/// built, so fields can be set in any order.
+#[derive(Debug, Clone)]
+pub struct VaaBuilder {
+    version: u8,
+    guardian_set_index: u32,
+    signatures: Vec<(u8, [u8; 65])>,
+    signers: Vec<(u8, SigningKey)>,
+    timestamp: u32,
+    nonce: u32,
+    emitter_chain: u16,
+    emitter_address: [u8; 32],
+    sequence: u64,
+    consistency_level: u8,
+    payload: Vec<u8>,
+}
+
+impl VaaBuilder {
+    pub fn new(emitter_chain: u16, emitter_address: [u8; 32], sequence: u64) -> Self {
+        Self {
+            version: 1,
+            guardian_set_index: 0,
+            signatures: Vec::new(),
+            signers: Vec::new(),
+            timestamp: 0,
+            nonce: 0,
+            emitter_chain,
+            emitter_address,
+            sequence,
+            consistency_level: 1,
+            payload: Vec::new(),
+        }
+    }
+
+    /// Starts from an existing VAA, keeping its signatures as they are.
+    pub fn from_vaa(vaa: &Vaa) -> Self {
+        Self {
+            version: vaa.version,
+            guardian_set_index: vaa.guardian_set_index,
+            signatures: vaa.signatures
+                .iter()
+                .map(|signature| (signature.guardian_index, signature.signature))
+                .collect(),
+            signers: Vec::new(),
+            timestamp: vaa.timestamp,
+            nonce: vaa.nonce,
+            emitter_chain: vaa.emitter_chain,
+            emitter_address: vaa.emitter_address,
+            sequence: vaa.sequence,
+            consistency_level: vaa.consistency_level,
+            payload: vaa.payload.clone(),
+        }
+    }
+
+    /// Only version 1 VAAs are built, `to_bytes` rejects other versions.
+    pub fn version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn guardian_set_index(mut self, guardian_set_index: u32) -> Self {
+        self.guardian_set_index = guardian_set_index;
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: u32) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u32) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    pub fn emitter(mut self, emitter_chain: u16, emitter_address: [u8; 32]) -> Self {
+        self.emitter_chain = emitter_chain;
+        self.emitter_address = emitter_address;
+        self
+    }
+
+    pub fn sequence(mut self, sequence: u64) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    pub fn consistency_level(mut self, consistency_level: u8) -> Self {
+        self.consistency_level = consistency_level;
+        self
+    }
+
+    pub fn payload(mut self, payload: impl Into<Vec<u8>>) -> Self {
+        self.payload = payload.into();
+        self
+    }
+
+    /// Adds a signature as is, valid or not, e.g. one taken from another VAA.
+    pub fn signature(mut self, guardian_index: u8, signature: [u8; 65]) -> Self {
+        self.signatures.push((guardian_index, signature));
+        self
+    }
+
+    /// Signs the body with the key of the guardian at `guardian_index`.
+    pub fn signer(mut self, guardian_index: u8, key: SigningKey) -> Self {
+        self.signers.push((guardian_index, key));
+        self
+    }
+
+    /// Signs the body with every devnet guardian key, reaching quorum for the
+    /// devnet guardian set 0.
+    pub fn devnet_signers(mut self) -> Self {
+        for (guardian_index, key) in devnet_guardian_keys().into_iter().enumerate() {
+            self.signers.push((guardian_index as u8, key));
+        }
+        self
+    }
+
+    /// Timestamp, nonce, emitter, sequence, consistency level and payload, the part guardians sign.
+    pub fn body(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(51 + self.payload.len());
+        body.extend_from_slice(&self.timestamp.to_be_bytes());
+        body.extend_from_slice(&self.nonce.to_be_bytes());
+        body.extend_from_slice(&self.emitter_chain.to_be_bytes());
+        body.extend_from_slice(&self.emitter_address);
+        body.extend_from_slice(&self.sequence.to_be_bytes());
+        body.push(self.consistency_level);
+        body.extend_from_slice(&self.payload);
+        body
+    }
+
+    /// Signatures are sorted by guardian index, as the Core Bridge requires.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        // Other versions have another layout, e.g. batches, see `ParsedVaa`
+        if self.version != VAA_VERSION {
+            return Err(Error::Request(format!(
+                "Unsupported VAA version {}, only version {} VAAs can be built", self.version, VAA_VERSION
+            )));
+        }
+        let body = self.body();
+        let digest = vaa_digest(&body);
+
+        let mut signatures = self.signatures.clone();
+        for (guardian_index, key) in &self.signers {
+            signatures.push((*guardian_index, sign_digest(key, &digest)?));
+        }
+        signatures.sort_by_key(|(guardian_index, _)| *guardian_index);
+        let count = u8::try_from(signatures.len())
+            .map_err(|_| Error::Request(format!("Too many signatures: {}, at most 255", signatures.len())))?;
+
+        let mut bytes = Vec::with_capacity(6 + signatures.len() * SIGNATURE_LEN + body.len());
+        bytes.push(self.version);
+        bytes.extend_from_slice(&self.guardian_set_index.to_be_bytes());
+        bytes.push(count);
+        for (guardian_index, signature) in &signatures {
+            bytes.push(*guardian_index);
+            bytes.extend_from_slice(signature);
+        }
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    pub fn build(&self) -> Result<Vaa, Error> {
+        Ok(Vaa::parse(&self.to_bytes()?)?)
+    }
+}
+
+pub fn devnet_guardian_keys() -> Vec<SigningKey> {
+    DEVNET_GUARDIAN_KEYS
+        .iter()
+        .map(|key| {
+            let bytes = hex::decode(key).expect("Invalid devnet guardian key");
+            SigningKey::from_slice(&bytes).expect("Invalid devnet guardian key")
+        })
+        .collect()
+}
+
+/// Unsigned VAA from emitter `0x11..11` on Ethereum, the fixture the
+/// storage, ingest, crawl and backfill tests share.
+#[cfg(test)]
+pub fn test_vaa(sequence: u64) -> VaaBuilder {
+    VaaBuilder::new(2, [0x11; 32], sequence).timestamp(1_700_000_000)
+}
+
+#[cfg(test)]
+pub fn test_vaa_bytes(sequence: u64) -> Vec<u8> {
+    test_vaa(sequence).to_bytes().expect("Invalid test VAA")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use crate::domain::wormhole::guardians::GuardianSetRegistry;
+    use crate::domain::wormhole::rest::vaa::decode_vaa;
+
+    #[test]
+    fn test_round_trip_with_decode_vaa() {
+        let registry = GuardianSetRegistry::bundled("devnet").unwrap();
+        let builder = VaaBuilder::new(2, [0x11; 32], 42)
+            .timestamp(1_700_000_000)
+            .nonce(7)
+            .consistency_level(200)
+            .payload(b"hello".to_vec())
+            .devnet_signers();
+        let bytes = builder.to_bytes().unwrap();
+
+        let decoded = decode_vaa(&STANDARD.encode(&bytes), None, false, &registry).unwrap();
+        assert_eq!(decoded.emitter_chain, 2);
+        assert_eq!(decoded.emitter_address, format!("0x{}", "11".repeat(32)));
+        assert_eq!((decoded.sequence, decoded.timestamp, decoded.nonce, decoded.consistency_level), (42, 1_700_000_000, 7, 200));
+        assert_eq!(decoded.payload.as_deref(), Some(hex::encode(b"hello").as_str()));
+        assert!(decoded.verification.quorum_reached);
+
+        // Signatures carried over as is encode to the same bytes
+        let vaa = Vaa::parse(&bytes).unwrap();
+        assert_eq!(VaaBuilder::from_vaa(&vaa).to_bytes().unwrap(), bytes);
+        assert_eq!(vaa.to_bytes(), bytes);
+
+        // Changing the body after the fact voids the copied signatures
+        let tampered = VaaBuilder::from_vaa(&vaa).sequence(43).to_bytes().unwrap();
+        let decoded = decode_vaa(&hex::encode(&tampered), None, false, &registry).unwrap();
+        assert!(!decoded.verification.quorum_reached);
+
+        // The v1 layout is only written for v1 VAAs, a v2 would decode as a batch
+        assert!(VaaBuilder::from_vaa(&vaa).version(2).to_bytes().is_err());
+    }
+}
@@ -0,0 +1,203 @@
+// Wormhole chain registry, keyed by the generated `publicrpc.v1.ChainID` enum
+
+use serde::Serialize;
+use schemars::JsonSchema;
+
+use crate::library::errors::Error;
+use super::grpc::proto::publicrpc::v1::ChainId;
+
+/// How a chain's native addresses map to 32-byte Wormhole addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFormat {
+    /// 20-byte address, left-padded with zeros
+    Evm,
+    /// 32-byte public key or program address, base58 natively
+    Base58,
+    /// 32-byte account address, 0x-prefixed hex natively (Move chains)
+    Hex,
+    /// 32-byte contract address, bech32 natively (Cosmos chains)
+    Bech32,
+    /// Hash of the native address, which can't be recovered from it
+    Opaque,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChainInfo {
+    pub id: u16,
+    /// Human-readable name
+    pub name: &'static str,
+    pub mainnet: bool,
+    pub testnet: bool,
+    pub address_format: AddressFormat,
+    #[serde(skip)]
+    chain_id: ChainId,
+}
+
+impl ChainInfo {
+    /// Enum name in the protobuf definitions, e.g. `CHAIN_ID_BASE`
+    pub fn proto_name(&self) -> &'static str {
+        self.chain_id.as_str_name()
+    }
+
+    pub fn chain_id(&self) -> ChainId {
+        self.chain_id
+    }
+
+    /// Whether the chain is connected to Wormhole on `network`. Devnets run every chain.
+    pub fn is_on(&self, network: &str) -> bool {
+        match network {
+            "mainnet" => self.mainnet,
+            "testnet" => self.testnet,
+            _ => true,
+        }
+    }
+
+    /// Checks that a 32-byte emitter address is well-formed for this chain.
+    pub fn validate_emitter(&self, emitter: &[u8; 32]) -> Result<(), Error> {
+        if self.address_format == AddressFormat::Evm && emitter[..12].iter().any(|byte| *byte != 0) {
+            return Err(Error::Request(format!(
+                "Invalid {} emitter address: EVM addresses must be left-padded with 12 zero bytes",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+const MAINNET: (bool, bool) = (true, true);
+const TESTNET: (bool, bool) = (false, true);
+
+const fn chain(chain_id: ChainId, name: &'static str, networks: (bool, bool), address_format: AddressFormat) -> ChainInfo {
+    ChainInfo {
+        id: chain_id as u16,
+        name,
+        mainnet: networks.0,
+        testnet: networks.1,
+        address_format,
+        chain_id,
+    }
+}
+
+use AddressFormat::{Base58, Bech32, Evm, Hex, Opaque};
+
+const CHAINS: &[ChainInfo] = &[
+    chain(ChainId::Solana, "Solana", MAINNET, Base58),
+    chain(ChainId::Ethereum, "Ethereum", MAINNET, Evm),
+    chain(ChainId::Terra, "Terra Classic", MAINNET, Bech32),
+    chain(ChainId::Bsc, "BNB Smart Chain", MAINNET, Evm),
+    chain(ChainId::Polygon, "Polygon", MAINNET, Evm),
+    chain(ChainId::Avalanche, "Avalanche", MAINNET, Evm),
+    chain(ChainId::Oasis, "Oasis", MAINNET, Evm),
+    chain(ChainId::Algorand, "Algorand", MAINNET, Opaque),
+    chain(ChainId::Aurora, "Aurora", MAINNET, Evm),
+    chain(ChainId::Fantom, "Fantom", MAINNET, Evm),
+    chain(ChainId::Karura, "Karura", MAINNET, Evm),
+    chain(ChainId::Acala, "Acala", MAINNET, Evm),
+    chain(ChainId::Klaytn, "Klaytn", MAINNET, Evm),
+    chain(ChainId::Celo, "Celo", MAINNET, Evm),
+    chain(ChainId::Near, "NEAR", MAINNET, Opaque),
+    chain(ChainId::Moonbeam, "Moonbeam", MAINNET, Evm),
+    chain(ChainId::Terra2, "Terra", MAINNET, Bech32),
+    chain(ChainId::Injective, "Injective", MAINNET, Bech32),
+    chain(ChainId::Osmosis, "Osmosis", MAINNET, Bech32),
+    chain(ChainId::Sui, "Sui", MAINNET, Hex),
+    chain(ChainId::Aptos, "Aptos", MAINNET, Hex),
+    chain(ChainId::Arbitrum, "Arbitrum", MAINNET, Evm),
+    chain(ChainId::Optimism, "Optimism", MAINNET, Evm),
+    chain(ChainId::Gnosis, "Gnosis", MAINNET, Evm),
+    chain(ChainId::Pythnet, "Pythnet", MAINNET, Base58),
+    chain(ChainId::Xpla, "XPLA", MAINNET, Bech32),
+    chain(ChainId::Btc, "Bitcoin", TESTNET, Opaque),
+    chain(ChainId::Base, "Base", MAINNET, Evm),
+    chain(ChainId::Filecoin, "Filecoin", TESTNET, Evm),
+    chain(ChainId::Sei, "Sei", MAINNET, Bech32),
+    chain(ChainId::Rootstock, "Rootstock", TESTNET, Evm),
+    chain(ChainId::Scroll, "Scroll", MAINNET, Evm),
+    chain(ChainId::Mantle, "Mantle", MAINNET, Evm),
+    chain(ChainId::Blast, "Blast", MAINNET, Evm),
+    chain(ChainId::Xlayer, "X Layer", MAINNET, Evm),
+    chain(ChainId::Linea, "Linea", MAINNET, Evm),
+    chain(ChainId::Berachain, "Berachain", MAINNET, Evm),
+    chain(ChainId::Seievm, "Sei EVM", MAINNET, Evm),
+    chain(ChainId::Eclipse, "Eclipse", TESTNET, Base58),
+    chain(ChainId::Bob, "BOB", TESTNET, Evm),
+    chain(ChainId::Snaxchain, "SnaxChain", MAINNET, Evm),
+    chain(ChainId::Unichain, "Unichain", MAINNET, Evm),
+    chain(ChainId::Worldchain, "World Chain", MAINNET, Evm),
+    chain(ChainId::Ink, "Ink", MAINNET, Evm),
+    chain(ChainId::HyperEvm, "HyperEVM", MAINNET, Evm),
+    chain(ChainId::Monad, "Monad", MAINNET, Evm),
+    chain(ChainId::Movement, "Movement", MAINNET, Hex),
+    chain(ChainId::Wormchain, "Wormchain", MAINNET, Bech32),
+    chain(ChainId::Cosmoshub, "Cosmos Hub", MAINNET, Bech32),
+    chain(ChainId::Evmos, "Evmos", MAINNET, Bech32),
+    chain(ChainId::Kujira, "Kujira", MAINNET, Bech32),
+    chain(ChainId::Neutron, "Neutron", MAINNET, Bech32),
+    chain(ChainId::Celestia, "Celestia", MAINNET, Bech32),
+    chain(ChainId::Stargaze, "Stargaze", MAINNET, Bech32),
+    chain(ChainId::Seda, "SEDA", MAINNET, Bech32),
+    chain(ChainId::Dymension, "Dymension", MAINNET, Bech32),
+    chain(ChainId::Provenance, "Provenance", MAINNET, Bech32),
+    chain(ChainId::Noble, "Noble", MAINNET, Bech32),
+    chain(ChainId::Sepolia, "Sepolia", TESTNET, Evm),
+    chain(ChainId::ArbitrumSepolia, "Arbitrum Sepolia", TESTNET, Evm),
+    chain(ChainId::BaseSepolia, "Base Sepolia", TESTNET, Evm),
+    chain(ChainId::OptimismSepolia, "Optimism Sepolia", TESTNET, Evm),
+    chain(ChainId::Holesky, "Holesky", TESTNET, Evm),
+    chain(ChainId::PolygonSepolia, "Polygon Sepolia", TESTNET, Evm),
+    chain(ChainId::MonadDevnet, "Monad Devnet", TESTNET, Evm),
+];
+
+pub fn all() -> &'static [ChainInfo] {
+    CHAINS
+}
+
+pub fn get(id: u16) -> Option<&'static ChainInfo> {
+    CHAINS.iter().find(|chain| chain.id == id)
+}
+
+/// Human-readable name of a chain ID, if known.
+pub fn name(id: u16) -> Option<String> {
+    get(id).map(|chain| chain.name.to_string())
+}
+
+/// Looks up a chain ID supplied by a client, rejecting unknown chains and the
+/// ones not connected to `network`.
+pub fn resolve(id: u16, network: &str) -> Result<&'static ChainInfo, Error> {
+    let chain = get(id).ok_or_else(|| Error::Request(format!("Unknown chain ID {}", id)))?;
+    if !chain.is_on(network) {
+        return Err(Error::Request(format!("{} (chain ID {}) is not available on {}", chain.name, id, network)));
+    }
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_matches_proto_chain_ids() {
+        for id in 1..=u16::MAX {
+            let proto = ChainId::try_from(id as i32).ok();
+            let registered = get(id).map(|chain| chain.chain_id());
+            assert_eq!(proto, registered, "chain ID {} out of sync with publicrpc.v1.ChainID", id);
+        }
+
+        assert_eq!(get(30).map(|chain| chain.name), Some("Base"));
+        assert_eq!(get(24).map(|chain| chain.proto_name()), Some("CHAIN_ID_OPTIMISM"));
+        assert!(resolve(10002, "mainnet").is_err());
+        assert!(resolve(10002, "testnet").is_ok());
+        assert!(resolve(17, "devnet").is_err());
+    }
+
+    #[test]
+    fn test_evm_emitters_are_left_padded() {
+        let ethereum = get(2).unwrap();
+        let mut emitter = [0u8; 32];
+        emitter[12..].copy_from_slice(&[0x70; 20]);
+        assert!(ethereum.validate_emitter(&emitter).is_ok());
+        assert!(ethereum.validate_emitter(&[0x70; 32]).is_err());
+        assert!(get(1).unwrap().validate_emitter(&[0x70; 32]).is_ok());
+    }
+}
@@ -0,0 +1,178 @@
+// Events recorded by the observer, the audit trail its read models are rebuilt from
+
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use tracing::error;
+
+use crate::AppState;
+use crate::library::errors::Error;
+use crate::storage::events::{Event, EventQuery, EventStore, StoredEvent, MAX_EVENT_LIMIT};
+use super::backfill::GapBackfillStatus;
+use super::guardians::registry::GuardianSet;
+use super::models::SequenceGap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObserverEvent {
+    /// The Spy delivered a VAA for the first time
+    VaaObserved {
+        /// keccak256(keccak256(body)), hex-encoded
+        digest: String,
+        message_id: String,
+        emitter_chain: u16,
+        /// Hex-encoded 32-byte emitter address, without leading 0x
+        emitter_address: String,
+        sequence: u64,
+        guardian_set_index: u32,
+        /// Base64-encoded VAA bytes
+        vaa: String,
+    },
+    /// A VAA missing from a gap was recovered from a guardian's public RPC
+    VaaBackfilled {
+        digest: String,
+        message_id: String,
+        emitter_chain: u16,
+        emitter_address: String,
+        sequence: u64,
+        guardian_set_index: u32,
+        /// Base64-encoded VAA bytes
+        vaa: String,
+        /// Guardian public RPC endpoint that served the VAA
+        rpc_addr: String,
+    },
+    /// Sequences skipped by an emitter
+    GapDetected {
+        emitter_chain: u16,
+        emitter_address: String,
+        gap: SequenceGap,
+        /// The gap opened across an ingestion outage, the VAAs may only have been missed by us
+        outage: bool,
+    },
+    /// A late VAA closed (part of) a gap
+    GapFilled {
+        emitter_chain: u16,
+        emitter_address: String,
+        sequence: u64,
+    },
+    /// Outcome of a backfill round for a gap, as opened at the time
+    GapBackfilled {
+        emitter_chain: u16,
+        emitter_address: String,
+        gap: SequenceGap,
+        outage: bool,
+        status: GapBackfillStatus,
+        /// Sequences recovered from the guardians
+        recovered: Vec<u64>,
+        /// Sequences the guardians answered NotFound for
+        not_found: Vec<u64>,
+        /// Sequences no guardian could be asked for, retried on the next round
+        errored: Vec<u64>,
+        /// Sequences left to the next round
+        pending: u64,
+    },
+    /// A VAA was received again
+    DuplicateSeen {
+        digest: String,
+        emitter_chain: u16,
+        emitter_address: String,
+        sequence: u64,
+    },
+    /// The guardian set registry changed
+    GuardianSetChanged {
+        guardian_set: GuardianSet,
+    },
+    /// Progress of a Wormholescan history crawl, resumed from the latest one
+    CrawlCheckpoint {
+        emitter_chain: u16,
+        emitter_address: String,
+        page_size: u32,
+        /// Page the crawl resumes from
+        next_page: u32,
+        /// VAAs of the emitter's history crawled so far
+        vaas_crawled: u64,
+        /// VAAs that were not stored yet
+        vaas_stored: u64,
+        /// The crawl reached the end of the history
+        complete: bool,
+    },
+}
+
+impl Event for ObserverEvent {
+    fn kind(&self) -> &'static str {
+        match self {
+            ObserverEvent::VaaObserved { .. } => "vaa_observed",
+            ObserverEvent::VaaBackfilled { .. } => "vaa_backfilled",
+            ObserverEvent::GapDetected { .. } => "gap_detected",
+            ObserverEvent::GapFilled { .. } => "gap_filled",
+            ObserverEvent::GapBackfilled { .. } => "gap_backfilled",
+            ObserverEvent::DuplicateSeen { .. } => "duplicate_seen",
+            ObserverEvent::GuardianSetChanged { .. } => "guardian_set_changed",
+            ObserverEvent::CrawlCheckpoint { .. } => "crawl_checkpoint",
+        }
+    }
+
+    /// VAA events are streamed by digest, gap events by `chain/emitter`,
+    /// guardian set changes by `guardian_set/index` and crawl checkpoints by
+    /// `crawl/chain/emitter`.
+    fn stream_id(&self) -> String {
+        match self {
+            ObserverEvent::VaaObserved { digest, .. }
+            | ObserverEvent::VaaBackfilled { digest, .. }
+            | ObserverEvent::DuplicateSeen { digest, .. } => digest.clone(),
+            ObserverEvent::GapDetected { emitter_chain, emitter_address, .. }
+            | ObserverEvent::GapFilled { emitter_chain, emitter_address, .. }
+            | ObserverEvent::GapBackfilled { emitter_chain, emitter_address, .. } => emitter_stream_id(*emitter_chain, emitter_address),
+            ObserverEvent::GuardianSetChanged { guardian_set } => format!("guardian_set/{}", guardian_set.index),
+            ObserverEvent::CrawlCheckpoint { emitter_chain, emitter_address, .. } => crawl_stream_id(*emitter_chain, emitter_address),
+        }
+    }
+}
+
+pub fn emitter_stream_id(emitter_chain: u16, emitter_address: &str) -> String {
+    format!("{}/{}", emitter_chain, emitter_address)
+}
+
+pub fn crawl_stream_id(emitter_chain: u16, emitter_address: &str) -> String {
+    format!("crawl/{}", emitter_stream_id(emitter_chain, emitter_address))
+}
+
+/// Whether the Spy delivered the VAA with this digest before.
+pub async fn observed(events: &EventStore<ObserverEvent>, digest: &str) -> Result<bool, Error> {
+    Ok(!observed_digests(events, vec![digest.to_string()]).await?.is_empty())
+}
+
+/// The digests among these that the Spy delivered before.
+pub async fn observed_digests(events: &EventStore<ObserverEvent>, digests: Vec<String>) -> Result<HashSet<String>, Error> {
+    let mut observed = HashSet::new();
+    if digests.is_empty() {
+        return Ok(observed);
+    }
+
+    let mut query = EventQuery {
+        after: 0,
+        stream_ids: digests,
+        kind: Some("vaa_observed"),
+        limit: Some(MAX_EVENT_LIMIT),
+    };
+    loop {
+        let page = events.read(&query).await?;
+        let Some(last) = page.last() else { break };
+        query.after = last.position;
+        observed.extend(page.into_iter().map(|stored| stored.stream_id));
+    }
+    Ok(observed)
+}
+
+/// Appends events to the log and applies the recorded ones to the live projections.
+pub async fn record(state: &AppState, events: impl IntoIterator<Item = ObserverEvent>) -> Vec<StoredEvent<ObserverEvent>> {
+    let mut recorded = Vec::new();
+    for event in events {
+        match state.events().append(event).await {
+            Ok(stored) => recorded.push(stored),
+            Err(e) => error!("Failed to record event: {}", e),
+        }
+    }
+    state.projections().apply(&recorded).await;
+    recorded
+}
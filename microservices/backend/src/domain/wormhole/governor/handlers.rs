@@ -0,0 +1,196 @@
+use std::sync::Arc;
+use aide::{
+    axum::{routing::get_with, ApiRouter, IntoApiResponse},
+    transform::TransformOperation,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::AppState;
+use crate::library::config::get_config;
+use crate::library::errors::{AppError, Error};
+use crate::domain::wormhole::chains;
+use crate::domain::wormhole::grpc::public_rpc::PublicRpcClient;
+use super::queue::{ChainNotional, EnqueuedSnapshot, GovernorToken};
+
+pub fn governor_routes(state: Arc<AppState>) -> ApiRouter {
+    ApiRouter::new()
+        .api_route(
+            "/observer/governor/enqueued",
+            get_with(get_enqueued_vaas, get_enqueued_vaas_docs),
+        )
+        .api_route(
+            "/observer/governor/enqueued/{chain_id}/{emitter}/{sequence}",
+            get_with(is_vaa_enqueued, is_vaa_enqueued_docs),
+        )
+        .api_route(
+            "/observer/governor/notional",
+            get_with(get_available_notional, get_available_notional_docs),
+        )
+        .api_route(
+            "/observer/governor/tokens",
+            get_with(get_token_list, get_token_list_docs),
+        )
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct EnqueuedParams {
+    /// Only VAAs emitted from this chain
+    chain_id: Option<u16>,
+    /// Only VAAs from this hex-encoded 32-byte emitter address
+    emitter: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct MessageIdParams {
+    chain_id: u16,
+    emitter: String,
+    sequence: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct IsEnqueuedResponse {
+    emitter_chain: u16,
+    emitter_address: String,
+    sequence: u64,
+    enqueued: bool,
+    /// Guardian public RPC endpoint that answered
+    rpc_addr: String,
+}
+
+// Guardian the Governor calls are made to, the first configured one that can be reached
+async fn connect() -> Result<(String, PublicRpcClient), Error> {
+    let rpc_addrs = &get_config().wormhole_guardian_rpc_addrs;
+    if rpc_addrs.is_empty() {
+        return Err(Error::Request(
+            "No guardian public RPC endpoint configured, set WORMHOLE_GUARDIAN_RPC_ADDRS".to_string(),
+        ));
+    }
+    PublicRpcClient::connect_any(rpc_addrs).await
+}
+
+// Request errors only come from a missing configuration, the rest from the guardian
+fn governor_error(e: Error) -> Response {
+    match e {
+        Error::Request(message) => AppError::new(&message).with_status(StatusCode::SERVICE_UNAVAILABLE),
+        e => AppError::new(&e.to_string()).with_status(StatusCode::BAD_GATEWAY),
+    }
+    .into_response()
+}
+
+async fn get_enqueued_vaas(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<EnqueuedParams>,
+) -> impl IntoApiResponse {
+    let fetched = async {
+        let (rpc_addr, mut client) = connect().await?;
+        let vaas = client.governor_get_enqueued_vaas().await?;
+        // Gap reports pick up the fresh queue too
+        Ok::<_, Error>(state.governor().update(rpc_addr, vaas).await)
+    };
+    let mut snapshot = match fetched.await {
+        Ok(snapshot) => snapshot,
+        Err(e) => return governor_error(e),
+    };
+
+    let emitter = params.emitter.map(|emitter| emitter.trim_start_matches("0x").to_lowercase());
+    snapshot.vaas.retain(|vaa| {
+        params.chain_id.is_none_or(|chain_id| vaa.emitter_chain == chain_id)
+            && emitter.as_ref().is_none_or(|emitter| &vaa.emitter_address == emitter)
+    });
+    Json(snapshot).into_response()
+}
+
+async fn is_vaa_enqueued(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<MessageIdParams>,
+) -> impl IntoApiResponse {
+    if let Err(e) = chains::resolve(params.chain_id, state.guardian_sets().network()) {
+        return AppError::new(&e.to_string()).into_response();
+    }
+    let emitter_address = params.emitter.trim_start_matches("0x").to_lowercase();
+    if !hex::decode(&emitter_address).is_ok_and(|bytes| bytes.len() == 32) {
+        return AppError::new(&format!("Invalid emitter address {}: expected 32 hex-encoded bytes", emitter_address))
+            .into_response();
+    }
+
+    let enqueued = async {
+        let (rpc_addr, mut client) = connect().await?;
+        let enqueued = client.governor_is_vaa_enqueued(params.chain_id, &emitter_address, params.sequence).await?;
+        Ok::<_, Error>((rpc_addr, enqueued))
+    };
+    match enqueued.await {
+        Ok((rpc_addr, enqueued)) => Json(IsEnqueuedResponse {
+            emitter_chain: params.chain_id,
+            emitter_address,
+            sequence: params.sequence,
+            enqueued,
+            rpc_addr,
+        }).into_response(),
+        Err(e) => governor_error(e),
+    }
+}
+
+async fn get_available_notional() -> impl IntoApiResponse {
+    let notional = async {
+        let (_, mut client) = connect().await?;
+        client.governor_get_available_notional_by_chain().await
+    };
+    match notional.await {
+        Ok(notional) => Json(notional).into_response(),
+        Err(e) => governor_error(e),
+    }
+}
+
+async fn get_token_list() -> impl IntoApiResponse {
+    let tokens = async {
+        let (_, mut client) = connect().await?;
+        client.governor_get_token_list().await
+    };
+    match tokens.await {
+        Ok(tokens) => Json(tokens).into_response(),
+        Err(e) => governor_error(e),
+    }
+}
+
+fn get_enqueued_vaas_docs(op: TransformOperation) -> TransformOperation {
+    op.description("List the VAAs the Governor currently holds back, with their expected release \
+            time, as reported by a guardian's public RPC. Gap reports mark these sequences as governed.")
+        .tag("wormhole-observer")
+        .response::<200, Json<EnqueuedSnapshot>>()
+        .response::<502, AppError>()
+        .response::<503, AppError>()
+}
+
+fn is_vaa_enqueued_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Ask a guardian whether the Governor holds back the VAA with this message ID")
+        .tag("wormhole-observer")
+        .response::<200, Json<IsEnqueuedResponse>>()
+        .response::<400, AppError>()
+        .response::<502, AppError>()
+        .response::<503, AppError>()
+}
+
+fn get_available_notional_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Remaining and total notional limits (USD) of every governed chain. Chains that \
+            are not listed are not governed.")
+        .tag("wormhole-observer")
+        .response::<200, Json<Vec<ChainNotional>>>()
+        .response::<502, AppError>()
+        .response::<503, AppError>()
+}
+
+fn get_token_list_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Tokens the Governor counts in its notional value, with their USD price")
+        .tag("wormhole-observer")
+        .response::<200, Json<Vec<GovernorToken>>>()
+        .response::<502, AppError>()
+        .response::<503, AppError>()
+}
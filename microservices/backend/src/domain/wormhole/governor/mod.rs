@@ -0,0 +1,8 @@
+pub mod handlers;
+pub mod queue;
+pub mod sync;
+
+// Re-export scoped routes
+pub use handlers::governor_routes;
+
+pub use queue::{ChainNotional, EnqueuedSnapshot, EnqueuedVaa, GovernorQueue, GovernorToken};
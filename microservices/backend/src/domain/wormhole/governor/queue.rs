@@ -0,0 +1,158 @@
+// The Governor's queue of held-back VAAs, as reported by the guardians
+
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use schemars::JsonSchema;
+use tokio::sync::RwLock;
+
+use crate::domain::wormhole::grpc::vaa::VaaMetadata;
+use crate::domain::wormhole::models::{GovernedSequence, SequenceGap};
+
+/// A VAA held back by the Governor until its release time.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct EnqueuedVaa {
+    pub emitter_chain: u16,
+    pub emitter_chain_name: Option<String>,
+    /// Hex-encoded emitter address, without leading 0x
+    pub emitter_address: String,
+    pub sequence: u64,
+    #[schemars(with = "String")]
+    pub release_time: DateTime<Utc>,
+    /// Notional value of the transfer, in USD
+    pub notional_value: u64,
+    pub tx_hash: String,
+}
+
+/// Notional limits of a governed chain, in USD.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChainNotional {
+    pub chain_id: u16,
+    pub chain_name: Option<String>,
+    pub remaining_available_notional: u64,
+    pub notional_limit: u64,
+    /// Transfers from this size on are always delayed
+    pub big_transaction_size: u64,
+}
+
+/// A token counted in the Governor's notional value.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GovernorToken {
+    pub origin_chain_id: u16,
+    pub origin_chain_name: Option<String>,
+    /// Hex-encoded token address on its origin chain, without leading 0x
+    pub origin_address: String,
+    /// Price in USD
+    pub price: f32,
+}
+
+/// The enqueued VAAs reported by a guardian at some point.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EnqueuedSnapshot {
+    /// Guardian public RPC endpoint that reported them
+    pub rpc_addr: String,
+    #[schemars(with = "String")]
+    pub fetched_at: DateTime<Utc>,
+    pub vaas: Vec<EnqueuedVaa>,
+}
+
+/// Latest view of the Governor's queue, used to tell governed sequences from
+/// missing ones in gap reports.
+#[derive(Debug, Default)]
+pub struct GovernorQueue {
+    snapshot: RwLock<Option<EnqueuedSnapshot>>,
+}
+
+impl GovernorQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn update(&self, rpc_addr: String, vaas: Vec<EnqueuedVaa>) -> EnqueuedSnapshot {
+        let snapshot = EnqueuedSnapshot { rpc_addr, fetched_at: Utc::now(), vaas };
+        *self.snapshot.write().await = Some(snapshot.clone());
+        snapshot
+    }
+
+    pub async fn snapshot(&self) -> Option<EnqueuedSnapshot> {
+        self.snapshot.read().await.clone()
+    }
+
+    /// The emitter's sequences currently enqueued
+    pub async fn governed(&self, emitter_chain: u16, emitter_address: &str) -> BTreeMap<u64, GovernedSequence> {
+        let snapshot = self.snapshot.read().await;
+        snapshot
+            .iter()
+            .flat_map(|snapshot| &snapshot.vaas)
+            .filter(|vaa| vaa.emitter_chain == emitter_chain && vaa.emitter_address == emitter_address)
+            .map(|vaa| (vaa.sequence, GovernedSequence {
+                sequence: vaa.sequence,
+                release_time: vaa.release_time,
+                notional_value: vaa.notional_value,
+                tx_hash: vaa.tx_hash.clone(),
+            }))
+            .collect()
+    }
+
+    /// Marks the sequences of the emitter's gaps that are enqueued
+    pub async fn mark_gaps(&self, emitter_chain: u16, emitter_address: &str, gaps: &mut [SequenceGap]) {
+        let emitter_address = emitter_address.trim_start_matches("0x").to_lowercase();
+        mark_governed(gaps, &self.governed(emitter_chain, &emitter_address).await);
+    }
+
+    /// Marks the enqueued sequences of every emitter's gaps, outage ones included
+    pub async fn mark_metadata(&self, metadata: &mut VaaMetadata) {
+        for emitter in &mut metadata.emitters {
+            let governed = self.governed(emitter.emitter_chain, &emitter.emitter_address).await;
+            mark_governed(&mut emitter.sequences.sequence_gaps, &governed);
+            mark_governed(&mut emitter.outage_gaps, &governed);
+        }
+    }
+}
+
+/// Lists the `governed` sequences falling into each gap.
+pub fn mark_governed(gaps: &mut [SequenceGap], governed: &BTreeMap<u64, GovernedSequence>) {
+    for gap in gaps {
+        gap.governed = governed.range(gap.from..=gap.to).map(|(_, sequence)| sequence.clone()).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::wormhole::sequences::SequenceTracker;
+
+    const EMITTER: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+
+    fn enqueued(emitter_chain: u16, sequence: u64) -> EnqueuedVaa {
+        EnqueuedVaa {
+            emitter_chain,
+            emitter_chain_name: None,
+            emitter_address: EMITTER.to_string(),
+            sequence,
+            release_time: DateTime::from_timestamp(1_700_086_400, 0).unwrap(),
+            notional_value: 2_000_000,
+            tx_hash: "0xabc".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gaps_mark_governed_sequences() {
+        let queue = GovernorQueue::new();
+        queue.update("guardian:7070".to_string(), vec![enqueued(2, 3), enqueued(2, 9), enqueued(4, 4)]).await;
+
+        let mut tracker = SequenceTracker::new();
+        for sequence in [1, 5, 6, 8] {
+            tracker.observe(sequence);
+        }
+        let mut metadata = tracker.metadata();
+        queue.mark_gaps(2, &format!("0x{}", EMITTER.to_uppercase()), &mut metadata.sequence_gaps).await;
+
+        let governed: Vec<(u64, Vec<u64>)> = metadata.sequence_gaps
+            .iter()
+            .map(|gap| (gap.from, gap.governed.iter().map(|governed| governed.sequence).collect()))
+            .collect();
+        assert_eq!(governed, vec![(2, vec![3]), (7, vec![])]);
+        assert_eq!(metadata.sequence_gaps[0].governed[0].release_time.timestamp(), 1_700_086_400);
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::AppState;
+use crate::domain::wormhole::grpc::public_rpc::PublicRpcClient;
+
+/// Periodically refreshes the Governor's queue from the first guardian public
+/// RPC endpoint that answers, so gap reports can tell governed sequences apart.
+pub fn spawn_governor_sync(
+    state: Arc<AppState>,
+    rpc_addrs: Vec<String>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            for addr in &rpc_addrs {
+                let result = match PublicRpcClient::connect(addr.clone()).await {
+                    Ok(mut client) => client.governor_get_enqueued_vaas().await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(vaas) => {
+                        debug!("Guardian {} reports {} enqueued VAAs", addr, vaas.len());
+                        state.governor().update(addr.clone(), vaas).await;
+                        break;
+                    }
+                    Err(e) => warn!("Failed to fetch the Governor's queue from {}: {}", addr, e),
+                }
+            }
+        }
+    })
+}
@@ -1,97 +1,151 @@
 use std::time::Duration;
-use tonic::{transport::Channel, Request};
+use tokio::time::Instant;
+use tonic::{transport::Channel, Request, Streaming};
 use tokio_stream::StreamExt;
-use tracing::{info, debug, error};
-use base64::{
-  Engine,
-  engine::general_purpose::STANDARD as BASE64_STANDARD
-};
-use hex;
+use tracing::{info, debug, error, warn};
 
+use crate::library::backoff::Backoff;
+use crate::library::config::get_config;
 use crate::library::errors::Error;
 
 // Generated proto types
 use crate::domain::wormhole::grpc::proto::{
     spy::v1::{
         spy_rpc_service_client::SpyRpcServiceClient,
-        EmitterFilter,
         SubscribeSignedVaaRequest,
         FilterEntry,
         SubscribeSignedVaaResponse,
     },
-    publicrpc::v1::ChainId,
 };
 use crate::domain::wormhole::grpc::vaa::VaaMetadata;
 use super::vaa::VaaProcessor;
 
+// Consecutive failed resubscriptions after which a collection without a
+// timeout gives up
+const MAX_RESUBSCRIBE_ATTEMPTS: u32 = 5;
+
 #[derive(Clone)]
 pub struct GrpcClient {
     client: SpyRpcServiceClient<Channel>,
 }
 
+// Opens a gRPC channel, shared by the Spy and guardian PublicRPC clients
+pub async fn connect_channel(addr: String) -> Result<Channel, Error> {
+    let addr = if !addr.starts_with("http") {
+        format!("http://{}", addr)
+    } else {
+        addr
+    };
+
+    debug!("Connecting to {}", addr);
+
+    Channel::from_shared(addr)
+        .map_err(|e| Error::Connection(e.to_string()))?
+        .connect_timeout(Duration::from_secs(get_config().wormhole_connect_timeout))
+        // Disables Nagle's algorithm
+        .tcp_nodelay(true) 
+        .connect()
+        .await
+        .map_err(|e| Error::Connection(e.to_string()))
+}
+
 impl GrpcClient {
     pub async fn connect(addr: String) -> Result<Self, Error> {
-        let addr = if !addr.starts_with("http") {
-            format!("http://{}", addr)
-        } else {
-            addr
-        };
-
-        debug!("Connecting to spy at {}", addr);
-        
-        let channel = Channel::from_shared(addr)
-            .map_err(|e| Error::Connection(e.to_string()))?
-            // TODO: Make configurable
-            .connect_timeout(Duration::from_secs(5))
-            // Disables Nagle's algorithm
-            .tcp_nodelay(true) 
-            .connect()
-            .await
-            .map_err(|e| Error::Connection(e.to_string()))?;
-            
+        let channel = connect_channel(addr).await?;
         let client = SpyRpcServiceClient::new(channel);
         info!("Successfully connected to spy service");
         Ok(Self { client })
     }
 
-    pub async fn subscribe_all_vaas(&mut self, limit: usize) 
-        -> Result<(usize, Vec<SubscribeSignedVaaResponse>, VaaMetadata), Error> 
+    /// Opens a `SubscribeSignedVAA` stream, left to the caller to consume.
+    pub async fn subscribe(&mut self, filters: Vec<FilterEntry>) 
+        -> Result<Streaming<SubscribeSignedVaaResponse>, Error> 
     {
-        debug!("Starting VAA subscription for all messages (limit: {})", limit);
-
-        let request = Request::new(SubscribeSignedVaaRequest {
-            // Empty filters to get all VAAs
-            filters: vec![],
-        });
-
+        let request = Request::new(SubscribeSignedVaaRequest { filters });
         debug!("Sending request: {:#?}", request);
-        info!("Starting VAA stream...");
-        
-        let mut stream = self.client
+
+        self.client
             .subscribe_signed_vaa(request)
             .await
             .map(|response| response.into_inner())
             .map_err(|e| {
                 error!("gRPC subscription error: {:?}", e);
                 Error::Subscription(e.to_string())
-            })?;
+            })
+    }
 
+    pub async fn subscribe_all_vaas(&mut self, limit: usize) 
+        -> Result<(usize, Vec<SubscribeSignedVaaResponse>, VaaMetadata), Error> 
+    {
+        debug!("Starting VAA subscription for all messages (limit: {})", limit);
+        // Empty filters to get all VAAs
+        self.collect_vaas(vec![], limit, None).await
+    }
+
+    /// Collects up to `limit` VAAs matching any of `filters`, or as many as
+    /// arrive before `timeout`. The stream is resubscribed with backoff whenever
+    /// it fails or ends early. Without a `timeout`, it gives up after
+    /// `MAX_RESUBSCRIBE_ATTEMPTS` resubscriptions in a row without a VAA.
+    pub async fn collect_vaas(&mut self, filters: Vec<FilterEntry>, limit: usize, timeout: Option<Duration>)
+        -> Result<(usize, Vec<SubscribeSignedVaaResponse>, VaaMetadata), Error>
+    {
+        info!("Starting VAA stream...");
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut stream = self.subscribe(filters.clone()).await?;
+        // Without a deadline, give up on a Spy that stays down
+        let mut backoff = match deadline {
+            Some(_) => Backoff::from_config(get_config()),
+            None => Backoff::from_config(get_config()).with_max_attempts(MAX_RESUBSCRIBE_ATTEMPTS),
+        };
         let mut processor = VaaProcessor::new(limit);
         let mut vaas = Vec::new();
 
-        while let Some(response) = stream.next().await {
-            match response {
-                Ok(vaa) => {
-                    if !processor.process_vaa(vaa.clone()) {
-                        // Don't process more VAAs if we've hit the limit
+        'collect: loop {
+            loop {
+                let next = match deadline {
+                    Some(deadline) => match tokio::time::timeout_at(deadline, stream.next()).await {
+                        Ok(next) => next,
+                        Err(_) => break 'collect,
+                    },
+                    None => stream.next().await,
+                };
+                let Some(response) = next else {
+                    break;
+                };
+                match response {
+                    Ok(vaa) => {
+                        backoff.reset();
+                        if !processor.process_vaa(vaa.clone()) {
+                            // Don't process more VAAs if we've hit the limit
+                            break 'collect;
+                        }
+                        vaas.push(vaa);
+                    }
+                    Err(e) => {
+                        error!("Error receiving VAA: {}", e);
                         break;
                     }
-                    vaas.push(vaa);
                 }
+            }
+
+            processor.mark_disconnected();
+            let resubscribe = self.resubscribe(filters.clone(), &mut backoff);
+            let resubscribed = match deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline, resubscribe).await {
+                    Ok(resubscribed) => resubscribed,
+                    Err(_) => break 'collect,
+                },
+                None => resubscribe.await,
+            };
+            stream = match resubscribed {
+                Ok(stream) => stream,
                 Err(e) => {
-                    error!("Error receiving VAA: {}", e);
+                    // Keep what was collected before the Spy went away
+                    warn!("{}, returning the VAAs collected so far", e);
+                    break 'collect;
                 }
-            }
+            };
         }
 
         processor.finalize_metadata();
@@ -108,78 +162,26 @@ impl GrpcClient {
         ))
     }
 
-    pub async fn subscribe_to_emitter(
+    // Subscribes again after the stream dropped, waiting `backoff` between
+    // attempts until it runs out of them. The channel reconnects by itself on
+    // the next request. The caller resets `backoff` once VAAs flow again.
+    async fn resubscribe(
         &mut self,
-        chain_id: u16,
-        emitter_address: &str,
-    ) -> Result<(), Error> {
-        debug!("Creating filter with chain_id: {}, emitter: {}", chain_id, emitter_address);
-
-        // Ensure emitter is valid hex without 0x prefix
-        let emitter_address = emitter_address.trim_start_matches("0x");
-        hex::decode(emitter_address)
-            .map_err(|_| Error::External("Invalid emitter address format".to_string()))?;
-
-        // Map chain_id to the correct enum value
-        let chain_id = match chain_id {
-            30 => ChainId::Optimism,
-            2 => ChainId::Ethereum,
-            4 => ChainId::Bsc,
-            6 => ChainId::Avalanche,
-            _ => return Err(Error::External("Unsupported chain ID".to_string())),
-        };
-
-        let filter = EmitterFilter {
-            chain_id: chain_id.into(),
-            emitter_address: emitter_address.to_string(),
-        };
-
-        let filter_entry = FilterEntry {
-            filter: Some(crate::domain::wormhole::grpc::proto::spy::v1::filter_entry::Filter::EmitterFilter(filter)),
-        };
-
-        let request = Request::new(SubscribeSignedVaaRequest {
-            filters: vec![filter_entry],
-        });
-
-        self.handle_vaa_stream(request).await
-    }
+        filters: Vec<FilterEntry>,
+        backoff: &mut Backoff,
+    ) -> Result<Streaming<SubscribeSignedVaaResponse>, Error> {
+        loop {
+            if backoff.is_exhausted() {
+                return Err(Error::Subscription(format!("Spy unreachable after {} attempts", backoff.attempt())));
+            }
+            let delay = backoff.next_delay();
+            warn!("Spy stream dropped, resubscribing in {:?} (attempt {})", delay, backoff.attempt());
+            tokio::time::sleep(delay).await;
 
-    // Private helper to handle the stream
-    async fn handle_vaa_stream(&mut self, request: Request<SubscribeSignedVaaRequest>) -> Result<(), Error> {
-        debug!("Sending request: {:#?}", request);
-        info!("Starting VAA stream...");
-        
-        let mut stream = self.client
-            .subscribe_signed_vaa(request)
-            .await
-            .map(|response| response.into_inner())
-            .map_err(|e| {
-                error!("gRPC subscription error: {:?}", e);
-                Error::Subscription(e.to_string())
-            })?;
-
-        let mut vaa_count = 0;
-        while let Some(response) = stream.next().await {
-            match response {
-                Ok(vaa) => {
-                    vaa_count += 1;
-                    info!(
-                        "VAA #{}: {} bytes, hash: {}",
-                        vaa_count,
-                        vaa.vaa_bytes.len(),
-                        hex::encode(&vaa.vaa_bytes[0..32].to_vec()),  // First 32 bytes as identifier
-                    );
-                    debug!("Full VAA: {}", BASE64_STANDARD.encode(&vaa.vaa_bytes));
-                    // TODO: Parse VAA bytes to extract more meaningful data
-                }
-                Err(e) => {
-                    error!("Error receiving VAA: {}", e);
-                }
+            if let Ok(stream) = self.subscribe(filters.clone()).await {
+                return Ok(stream);
             }
         }
-
-        Ok(())
     }
 }
 
@@ -190,13 +192,16 @@ mod tests {
     use crate::library::config::get_config;
 
     #[tokio::test]
+    #[ignore = "requires a running Spy service"]
     async fn test_spy_subscription() -> Result<(), Error> {
         // Known test values
-        const CHAIN_ID: u16 = 30;  // Optimism
+        #[allow(dead_code)]
+        const CHAIN_ID: u16 = 30;  // Base
+        #[allow(dead_code)]
         const EMITTER: &str = "000000000000000000000000706f82e9bb5b0813501714ab5974216704980e31";
 
         let config = get_config();
-        let spy_addr = config.wormhole_spy_addr.expect("Spy address not configured");
+        let spy_addr = config.wormhole_spy_addr.clone().expect("Spy address not configured");
         
         info!("Testing spy subscription...");
         let mut client = GrpcClient::connect(spy_addr).await?;
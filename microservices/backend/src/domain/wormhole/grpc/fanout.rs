@@ -0,0 +1,97 @@
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::StreamExt;
+use tracing::{info, warn};
+
+use crate::AppState;
+use crate::library::backoff::Backoff;
+use crate::library::errors::Error;
+use crate::storage::redis::RedisRepository;
+use super::ingest::VaaEvent;
+
+/// Publishes every VAA ingested by this replica on the Redis `channel`, for
+/// the replicas that don't run a Spy ingestion worker of their own.
+pub fn spawn_fanout_publisher(
+    state: Arc<AppState>,
+    cache: RedisRepository,
+    channel: String,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut events = state.spy_ingestion().subscribe();
+        info!("Publishing ingested VAAs on Redis channel {}", channel);
+
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Redis fan-out fell behind, {} VAAs were not published", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let published = match serde_json::to_string(&*event) {
+                Ok(payload) => cache.publish(&channel, payload).await,
+                Err(e) => Err(Error::Parsing(e.to_string())),
+            };
+            if let Err(e) = published {
+                warn!("Failed to publish VAA {}: {}", event.vaa.digest, e);
+            }
+        }
+    })
+}
+
+/// Feeds the VAAs published on the Redis `channel` by the ingesting replica to
+/// this replica's live stream subscribers. They are stored by the ingesting
+/// replica already, so shared storage serves them here too. The subscription
+/// is restarted with `backoff` when it drops.
+pub fn spawn_fanout_subscriber(
+    state: Arc<AppState>,
+    cache: RedisRepository,
+    channel: String,
+    mut backoff: Backoff,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let error = match run_subscriber(&state, &cache, &channel, &mut backoff).await {
+                Ok(()) => "Redis subscription ended".to_string(),
+                Err(e) => e.to_string(),
+            };
+            let delay = backoff.next_delay();
+            warn!(
+                "Redis fan-out stopped: {}, resubscribing in {:?} (attempt {})",
+                error, delay, backoff.attempt()
+            );
+            tokio::time::sleep(delay).await;
+        }
+    })
+}
+
+async fn run_subscriber(
+    state: &AppState,
+    cache: &RedisRepository,
+    channel: &str,
+    backoff: &mut Backoff,
+) -> Result<(), Error> {
+    let mut pubsub = cache.client()
+        .get_async_pubsub()
+        .await
+        .map_err(|e| Error::Subscription(format!("Failed to connect to Redis: {}", e)))?;
+    pubsub.subscribe(channel)
+        .await
+        .map_err(|e| Error::Subscription(e.to_string()))?;
+    backoff.reset();
+    info!("Receiving VAAs from Redis channel {}", channel);
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let event = message
+            .get_payload::<String>()
+            .map_err(|e| e.to_string())
+            .and_then(|payload| serde_json::from_str::<VaaEvent>(&payload).map_err(|e| e.to_string()));
+        match event {
+            Ok(event) => state.spy_ingestion().publish(Arc::new(event)),
+            Err(e) => warn!("Skipping invalid fan-out message: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,186 @@
+// Spy subscription filters, as accepted over HTTP and in the ingestion config
+
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::library::errors::Error;
+use crate::domain::wormhole::chains;
+use super::proto::spy::v1::{
+    filter_entry::Filter,
+    BatchFilter,
+    BatchTransactionFilter,
+    EmitterFilter,
+    FilterEntry,
+};
+
+/// One of the `spy.v1` filter kinds. A subscription with several filters
+/// receives the VAAs matching any of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpyFilter {
+    /// Every message from an emitter
+    Emitter {
+        chain_id: u16,
+        /// Hex-encoded 32-byte emitter address
+        emitter_address: String,
+    },
+    /// Messages with a given nonce emitted by a transaction
+    Batch {
+        chain_id: u16,
+        /// Hex-encoded native transaction identifier
+        tx_id: String,
+        nonce: u32,
+    },
+    /// Every message emitted by a transaction
+    BatchTransaction {
+        chain_id: u16,
+        /// Hex-encoded native transaction identifier
+        tx_id: String,
+    },
+}
+
+impl SpyFilter {
+    pub fn chain_id(&self) -> u16 {
+        match self {
+            SpyFilter::Emitter { chain_id, .. }
+            | SpyFilter::Batch { chain_id, .. }
+            | SpyFilter::BatchTransaction { chain_id, .. } => *chain_id,
+        }
+    }
+
+    /// Validates the filter and builds the matching `spy.v1.FilterEntry`.
+    pub fn to_entry(&self) -> Result<FilterEntry, Error> {
+        let chain = chains::get(self.chain_id())
+            .ok_or_else(|| Error::Request(format!("Unknown chain ID {}", self.chain_id())))?;
+        let chain_id = chain.chain_id().into();
+
+        let filter = match self {
+            SpyFilter::Emitter { emitter_address, .. } => {
+                // The Spy expects hex without the leading 0x
+                let emitter_address = emitter_address.trim_start_matches("0x").to_lowercase();
+                let emitter: [u8; 32] = hex::decode(&emitter_address)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| Error::Request(format!(
+                        "Invalid emitter address {}: expected 32 hex-encoded bytes", emitter_address
+                    )))?;
+                chain.validate_emitter(&emitter)?;
+                Filter::EmitterFilter(EmitterFilter { chain_id, emitter_address })
+            }
+            SpyFilter::Batch { tx_id, nonce, .. } => Filter::BatchFilter(BatchFilter {
+                chain_id,
+                tx_id: decode_tx_id(tx_id)?,
+                nonce: *nonce,
+            }),
+            SpyFilter::BatchTransaction { tx_id, .. } => Filter::BatchTransactionFilter(BatchTransactionFilter {
+                chain_id,
+                tx_id: decode_tx_id(tx_id)?,
+            }),
+        };
+
+        Ok(FilterEntry { filter: Some(filter) })
+    }
+}
+
+fn decode_tx_id(tx_id: &str) -> Result<Vec<u8>, Error> {
+    match hex::decode(tx_id.trim_start_matches("0x")) {
+        Ok(bytes) if !bytes.is_empty() => Ok(bytes),
+        _ => Err(Error::Request(format!("Invalid transaction ID {}: expected hex-encoded bytes", tx_id))),
+    }
+}
+
+/// Compact form used in query strings and `WORMHOLE_SPY_FILTERS`:
+/// `emitter:<chain>:<address>`, `batch:<chain>:<tx_id>:<nonce>` or `tx:<chain>:<tx_id>`.
+impl FromStr for SpyFilter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Request(format!(
+            "Invalid filter {:?}, expected emitter:<chain>:<address>, batch:<chain>:<tx_id>:<nonce> or tx:<chain>:<tx_id>", s
+        ));
+        let parts: Vec<&str> = s.trim().split(':').collect();
+        let chain_id = parts.get(1).and_then(|chain| chain.parse().ok()).ok_or_else(invalid)?;
+
+        let filter = match parts.as_slice() {
+            ["emitter", _, emitter_address] => SpyFilter::Emitter {
+                chain_id,
+                emitter_address: emitter_address.to_string(),
+            },
+            ["batch", _, tx_id, nonce] => SpyFilter::Batch {
+                chain_id,
+                tx_id: tx_id.to_string(),
+                nonce: nonce.parse().map_err(|_| invalid())?,
+            },
+            ["tx", _, tx_id] => SpyFilter::BatchTransaction {
+                chain_id,
+                tx_id: tx_id.to_string(),
+            },
+            _ => return Err(invalid()),
+        };
+
+        // Reject malformed filters up front rather than when subscribing
+        filter.to_entry()?;
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for SpyFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpyFilter::Emitter { chain_id, emitter_address } => write!(f, "emitter:{}:{}", chain_id, emitter_address),
+            SpyFilter::Batch { chain_id, tx_id, nonce } => write!(f, "batch:{}:{}:{}", chain_id, tx_id, nonce),
+            SpyFilter::BatchTransaction { chain_id, tx_id } => write!(f, "tx:{}:{}", chain_id, tx_id),
+        }
+    }
+}
+
+/// Parses a comma-separated list of compact filters.
+pub fn parse_filters(list: &str) -> Result<Vec<SpyFilter>, Error> {
+    list.split(',')
+        .filter(|filter| !filter.trim().is_empty())
+        .map(SpyFilter::from_str)
+        .collect()
+}
+
+pub fn to_entries(filters: &[SpyFilter]) -> Result<Vec<FilterEntry>, Error> {
+    filters.iter().map(SpyFilter::to_entry).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMITTER: &str = "000000000000000000000000706f82e9bb5b0813501714ab5974216704980e31";
+
+    #[test]
+    fn test_parse_compact_filters() {
+        let filters = parse_filters(&format!("emitter:30:{}, tx:2:0xabcd,batch:1:ab:7", EMITTER)).unwrap();
+        assert_eq!(filters.len(), 3);
+        assert_eq!(filters[0].to_string(), format!("emitter:30:{}", EMITTER));
+
+        let entries = to_entries(&filters).unwrap();
+        assert!(matches!(
+            &entries[1].filter,
+            Some(Filter::BatchTransactionFilter(BatchTransactionFilter { chain_id: 2, tx_id })) if tx_id == &vec![0xab, 0xcd]
+        ));
+        assert!(matches!(&entries[2].filter, Some(Filter::BatchFilter(BatchFilter { nonce: 7, .. }))));
+
+        assert!(parse_filters("").unwrap().is_empty());
+        assert!(parse_filters("tx:99:abcd").is_err());
+        assert!(parse_filters("batch:2:abcd").is_err());
+        assert!(parse_filters("emitter:2:706f82e9").is_err());
+    }
+
+    #[test]
+    fn test_json_filters() {
+        let filter: SpyFilter = serde_json::from_value(serde_json::json!({
+            "type": "batch_transaction",
+            "chain_id": 2,
+            "tx_id": "0xabcd",
+        }))
+        .unwrap();
+        assert_eq!(filter.to_string(), "tx:2:0xabcd");
+    }
+}
@@ -8,150 +8,221 @@ use aide::{
     OperationOutput
 };
 use axum::{
+    extract::{Query, State},
     http::StatusCode,
-    extract::State,
+    response::{IntoResponse, Response},
     Json,
 };
 use std::sync::Arc;
-use tracing::{info, error};
-use tokio::time::timeout;
 use std::time::Duration;
-use uuid::Uuid;
-use hex;
-use chrono;
+use serde::Deserialize;
 use schemars::JsonSchema;
+use tracing::{info, error};
 
 use crate::AppState;
-use crate::library::errors::AppError;
 use crate::library::config::get_config;
+use crate::library::errors::AppError;
+use crate::storage::ReadModel;
+use crate::storage::query::{Direction, VaaQuery};
 use crate::domain::wormhole::models::{VaaRecord, VaaRecordView};
 use crate::domain::wormhole::grpc::vaa::VaaMetadata;
 use super::client::GrpcClient;
+use super::filters::{parse_filters, to_entries, SpyFilter};
+use super::ingest::IngestionStatus;
+use super::stream::{stream_vaas_sse, stream_vaas_sse_docs, stream_vaas_ws, stream_vaas_ws_docs};
 
-const DEFAULT_VAA_LIMIT: usize = 50;
+// Bounds of a filtered, request-scoped subscription
+const DEFAULT_SUBSCRIPTION_LIMIT: usize = 50;
+const MAX_SUBSCRIPTION_LIMIT: usize = 1_000;
+// Seconds
+const MAX_SUBSCRIPTION_TIMEOUT: u64 = 300;
+// Latest stored VAAs returned with the ingestion status, older ones are paged
+// through `/observer/vaas`
+const RECENT_VAAS: usize = 50;
 
 pub fn wormhole_routes(state: Arc<AppState>) -> ApiRouter {
     ApiRouter::new()
         .api_route(
             "/spy/vaas",
-            get_with(get_spy_vaas, get_spy_vaas_docs),
+            get_with(get_spy_vaas, get_spy_vaas_docs)
+                .post_with(subscribe_spy_vaas, subscribe_spy_vaas_docs),
+        )
+        .api_route(
+            "/spy/vaas/stream",
+            get_with(stream_vaas_sse, stream_vaas_sse_docs),
+        )
+        .api_route(
+            "/spy/vaas/stream/ws",
+            get_with(stream_vaas_ws, stream_vaas_ws_docs),
         )
         .with_state(state)
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SpyVaasQuery {
+    /// Comma-separated filters: `emitter:<chain>:<address>`,
+    /// `batch:<chain>:<tx_id>:<nonce>` or `tx:<chain>:<tx_id>`
+    filter: Option<String>,
+    /// Maximum number of VAAs to collect when filtering (default 50)
+    limit: Option<usize>,
+    /// Seconds to wait for matching VAAs when filtering (default `WORMHOLE_SPY_TIMEOUT`, at most 300)
+    timeout: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SpySubscriptionRequest {
+    /// VAAs matching any of the filters are returned, every VAA if empty
+    filters: Vec<SpyFilter>,
+    /// Maximum number of VAAs to collect (default 50)
+    limit: Option<usize>,
+    /// Seconds to wait for matching VAAs (default `WORMHOLE_SPY_TIMEOUT`, at most 300)
+    timeout: Option<u64>,
+}
+
+#[derive(Debug, serde::Serialize, Clone, JsonSchema)]
+struct SpySubscriptionResponse {
+    filters: Vec<SpyFilter>,
+    metadata: VaaMetadata,
+    processed_vaas: usize,
+    vaas: Vec<VaaRecordView>,
+}
+
+impl OperationOutput for SpySubscriptionResponse {
+    type Inner = Self;
+}
+
 #[derive(Debug, serde::Serialize, Clone, JsonSchema)]
 struct SpyResponse {
     metadata: VaaMetadata,
+    ingestion: IngestionStatus,
     message: String,
     processed_vaas: usize,
     note: String,
+    /// Most recently stored VAAs, newest first
     vaas: Vec<VaaRecordView>,
+    /// Cursor for the older ones on `/observer/vaas?direction=desc`
+    next_cursor: Option<String>,
 }
 
 impl OperationOutput for SpyResponse {
     type Inner = Self;
 }
 
+// Serves what the background Spy worker has ingested so far, or the VAAs of a
+// dedicated subscription when filters are given
 async fn get_spy_vaas(
     State(state): State<Arc<AppState>>,
+    Query(query): Query<SpyVaasQuery>,
 ) -> impl IntoApiResponse {
-    let result: Result<_, (StatusCode, Json<AppError>)> = async {
-        info!("Starting VAA spy service with default limit...");
-        
-        let spy_addr = get_config().wormhole_spy_addr
-            .clone()
-            .ok_or_else(|| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(AppError {
-                        error: "Spy address not configured".to_string(),
-                        error_id: Uuid::new_v4(),
-                        status: StatusCode::INTERNAL_SERVER_ERROR,
-                        error_details: None,
-                    })
-                )
-            })?;
-
-        let mut client = GrpcClient::connect(spy_addr).await
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(AppError {
-                        error: format!("Failed to connect to spy: {}", e),
-                        error_id: Uuid::new_v4(),
-                        status: StatusCode::INTERNAL_SERVER_ERROR,
-                        error_details: None,
-                    })
-                )
-            })?;
-
-        match timeout(
-            Duration::from_secs(get_config().wormhole_spy_timeout),
-            client.subscribe_all_vaas(DEFAULT_VAA_LIMIT)
-        ).await {
-            Ok(result) => match result {
-                Ok((count, vaas, metadata)) => {
-                    let mut stored_vaas = Vec::new();
-                    for vaa in vaas {
-                        let record = VaaRecord {
-                            id: Uuid::new_v4(),
-                            hash: hex::encode(&vaa.vaa_bytes[0..32]),
-                            bytes: vaa.vaa_bytes,
-                            timestamp: chrono::Utc::now(),
-                        };
-                        stored_vaas.push(state.vaas_repository().create(record).await);
-                    }
-
-                    let vaa_views = state.vaas_repository().list().await;
-
-                    Ok(Json(SpyResponse {
-                        metadata,
-                        message: "Successfully processed VAA stream".to_string(),
-                        processed_vaas: count,
-                        note: "Check server logs for details".to_string(),
-                        vaas: vaa_views,
-                    }))
-                },
-                Err(e) => {
-                    error!("Failed to subscribe: {}", e);
-                    Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(AppError {
-                            error: format!("Failed to subscribe: {}", e),
-                            error_id: Uuid::new_v4(),
-                            status: StatusCode::INTERNAL_SERVER_ERROR,
-                            error_details: None,
-                        })
-                    ))
-                }
-            },
-            Err(_) => {
-                error!("Timeout waiting for spy response");
-                Err((
-                    StatusCode::GATEWAY_TIMEOUT,
-                    Json(AppError {
-                        error: "Timeout waiting for spy response".to_string(),
-                        error_id: Uuid::new_v4(),
-                        status: StatusCode::GATEWAY_TIMEOUT,
-                        error_details: None,
-                    })
-                ))
-            }
+    if let Some(filter) = query.filter.filter(|filter| !filter.trim().is_empty()) {
+        return match parse_filters(&filter) {
+            Ok(filters) => subscribe(&state, filters, query.limit, query.timeout).await,
+            Err(e) => AppError::new(&e.to_string()).into_response(),
+        };
+    }
+
+    let ingestion = state.spy_ingestion();
+    let status = ingestion.status().await;
+    let (count, mut metadata) = ingestion.metadata().await;
+    state.governor().mark_metadata(&mut metadata).await;
+    let recent = VaaQuery {
+        direction: Direction::Desc,
+        limit: Some(RECENT_VAAS),
+        ..Default::default()
+    };
+    let page = match state.vaas_repository().query(&recent).await {
+        Ok(page) => page,
+        Err(e) => {
+            return AppError::new(&e.to_string())
+                .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+                .into_response()
         }
-    }.await;
+    };
+
+    let message = if status.connected {
+        "Spy ingestion is running".to_string()
+    } else {
+        "Spy ingestion is not connected".to_string()
+    };
 
-    match result {
-        Ok(json) => Ok(json),
-        Err((status, error)) => Err((status, error)),
+    Json(SpyResponse {
+        metadata,
+        ingestion: status,
+        message,
+        processed_vaas: count,
+        note: "Check server logs for details".to_string(),
+        vaas: page.items,
+        next_cursor: page.next_cursor,
+    }).into_response()
+}
+
+async fn subscribe_spy_vaas(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SpySubscriptionRequest>,
+) -> impl IntoApiResponse {
+    subscribe(&state, request.filters, request.limit, request.timeout).await
+}
+
+// Opens a Spy subscription for the request only, collecting up to `limit`
+// matching VAAs or as many as arrive within `timeout` seconds
+async fn subscribe(state: &AppState, filters: Vec<SpyFilter>, limit: Option<usize>, timeout: Option<u64>) -> Response {
+    let entries = match to_entries(&filters) {
+        Ok(entries) => entries,
+        Err(e) => return AppError::new(&e.to_string()).into_response(),
+    };
+    let config = get_config();
+    let Some(spy_addr) = config.wormhole_spy_addr.clone() else {
+        return AppError::new("No Spy service configured")
+            .with_status(StatusCode::SERVICE_UNAVAILABLE)
+            .into_response();
+    };
+    let limit = limit.unwrap_or(DEFAULT_SUBSCRIPTION_LIMIT).clamp(1, MAX_SUBSCRIPTION_LIMIT);
+    let timeout = Duration::from_secs(timeout.unwrap_or(config.wormhole_spy_timeout).min(MAX_SUBSCRIPTION_TIMEOUT));
+
+    info!("Subscribing to the Spy with {} filters (limit: {}, timeout: {:?})", filters.len(), limit, timeout);
+    let collected = match GrpcClient::connect(spy_addr).await {
+        Ok(mut client) => client.collect_vaas(entries, limit, Some(timeout)).await,
+        Err(e) => Err(e),
+    };
+
+    match collected {
+        Ok((processed_vaas, vaas, mut metadata)) => {
+            state.governor().mark_metadata(&mut metadata).await;
+            Json(SpySubscriptionResponse {
+                filters,
+                metadata,
+                processed_vaas,
+                vaas: vaas
+                    .into_iter()
+                    .filter_map(|vaa| VaaRecord::from_bytes(vaa.vaa_bytes, state.guardian_sets().network()).ok())
+                    .map(|record| VaaRecordView::from_write_model(&record))
+                    .collect(),
+            }).into_response()
+        }
+        Err(e) => {
+            error!("Filtered Spy subscription failed: {}", e);
+            AppError::new(&format!("Spy subscription failed: {}", e))
+                .with_status(StatusCode::BAD_GATEWAY)
+                .into_response()
+        }
     }
 }
 
 fn get_spy_vaas_docs(op: TransformOperation) -> TransformOperation {
-    op.description("Stream VAAs from Wormhole Spy service")
+    op.description("Anomaly metadata and the latest VAAs ingested from the Wormhole Spy service, \
+            older ones are paged through `/observer/vaas`. With `filter`, opens a dedicated \
+            subscription instead and returns the matching VAAs.")
         .tag("wormhole-spy")
-        // TODO: Add missing schemas
-        .response::<200, ()>()
+        .response::<200, Json<SpyResponse>>()
         .response::<400, AppError>()
+        .response::<502, AppError>()
         .response::<500, AppError>()
-        .response::<504, AppError>()
+}
+
+fn subscribe_spy_vaas_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Collect the VAAs matching any of the given emitter, batch or transaction filters")
+        .tag("wormhole-spy")
+        .response::<200, Json<SpySubscriptionResponse>>()
+        .response::<400, AppError>()
+        .response::<502, AppError>()
 }
\ No newline at end of file
@@ -0,0 +1,521 @@
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use tokio::sync::{broadcast, Mutex};
+use tokio_stream::StreamExt;
+use tracing::{info, debug, error, warn};
+
+use crate::AppState;
+use crate::library::backoff::Backoff;
+use crate::library::errors::Error;
+use crate::storage::RepositoryImpl;
+use crate::storage::query::{QueryOrder, VaaQuery, MAX_QUERY_LIMIT};
+use crate::domain::wormhole::events::{observed, record as record_events, ObserverEvent};
+use crate::domain::wormhole::models::VaaRecord;
+use crate::domain::wormhole::sequences::SequenceObservation;
+use crate::domain::wormhole::vaa::Vaa;
+use crate::domain::wormhole::guardians::governance::apply_guardian_set_upgrade;
+use crate::domain::wormhole::rest::vaa::DecodedVaa;
+use super::client::GrpcClient;
+use super::filters::{to_entries, SpyFilter};
+use super::proto::spy::v1::SubscribeSignedVaaResponse;
+use super::vaa::{OpenGap, VaaAnnotations, VaaMetadata, VaaProcessor};
+
+// Most recent outages kept in the ingestion status
+const MAX_RECORDED_OUTAGES: usize = 50;
+// Events buffered for live stream subscribers, slower ones skip ahead
+const EVENT_BUFFER: usize = 1_024;
+
+/// A VAA as pushed to live stream subscribers, and to other replicas over Redis.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct VaaEvent {
+    pub vaa: DecodedVaa,
+    pub annotations: VaaAnnotations,
+    /// First time this digest was stored
+    pub stored: bool,
+}
+
+/// A period during which the Spy stream was down after having been connected.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct IngestionOutage {
+    #[schemars(with = "String")]
+    pub started_at: DateTime<Utc>,
+    #[schemars(with = "Option<String>")]
+    pub ended_at: Option<DateTime<Utc>>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct IngestionStatus {
+    pub connected: bool,
+    pub total_received: u64,
+    pub total_stored: u64,
+    pub restarts: u64,
+    #[schemars(with = "Option<String>")]
+    pub connected_since: Option<DateTime<Utc>>,
+    #[schemars(with = "Option<String>")]
+    pub last_vaa_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Filters of the ingestion subscription, empty for the whole network
+    pub filters: Vec<SpyFilter>,
+    /// Stream drops, the VAAs published meanwhile show up as `outage_gaps`
+    pub outages: Vec<IngestionOutage>,
+}
+
+/// State shared between the background Spy worker and the HTTP handlers.
+pub struct SpyIngestion {
+    processor: Mutex<VaaProcessor>,
+    status: Mutex<IngestionStatus>,
+    events: broadcast::Sender<Arc<VaaEvent>>,
+}
+
+impl Default for SpyIngestion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpyIngestion {
+    pub fn new() -> Self {
+        Self {
+            processor: Mutex::new(VaaProcessor::unbounded()),
+            status: Mutex::new(IngestionStatus::default()),
+            events: broadcast::channel(EVENT_BUFFER).0,
+        }
+    }
+
+    /// Receives every VAA ingested from now on. A receiver more than
+    /// `EVENT_BUFFER` events behind skips the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<VaaEvent>> {
+        self.events.subscribe()
+    }
+
+    /// Hands an event to live subscribers. Fails only when nobody is listening.
+    pub(crate) fn publish(&self, event: Arc<VaaEvent>) {
+        let _ = self.events.send(event);
+    }
+
+    pub async fn status(&self) -> IngestionStatus {
+        self.status.lock().await.clone()
+    }
+
+    /// Snapshot of the anomaly metadata gathered since startup
+    pub async fn metadata(&self) -> (usize, VaaMetadata) {
+        let mut processor = self.processor.lock().await;
+        processor.finalize_metadata();
+        (processor.processed_count(), processor.get_metadata().clone())
+    }
+
+    /// Sequence gaps, outage ones included, that no VAA filled yet
+    pub async fn open_gaps(&self) -> Vec<OpenGap> {
+        self.processor.lock().await.open_gaps()
+    }
+}
+
+// Where an ingested VAA came from
+#[derive(Clone, Copy)]
+enum VaaSource<'a> {
+    Spy,
+    // Recovered from the guardian public RPC endpoint at this address
+    Backfill(&'a str),
+}
+
+/// Feeds a single VAA into the anomaly pipeline, the guardian set registry, the
+/// `vaas` repository and live subscribers. Returns whether the VAA was stored
+/// for the first time.
+pub async fn ingest_vaa(state: &AppState, vaa: SubscribeSignedVaaResponse) -> bool {
+    ingest(state, vaa, VaaSource::Spy).await
+}
+
+/// Ingests a VAA recovered from a guardian's public RPC to fill a gap. It is
+/// recorded as `vaa_backfilled` rather than `vaa_observed`, the Spy never
+/// delivered it.
+pub async fn ingest_backfilled_vaa(state: &AppState, vaa: SubscribeSignedVaaResponse, rpc_addr: &str) -> bool {
+    ingest(state, vaa, VaaSource::Backfill(rpc_addr)).await
+}
+
+async fn ingest(state: &AppState, vaa: SubscribeSignedVaaResponse, source: VaaSource<'_>) -> bool {
+    let ingestion = state.spy_ingestion();
+    if let VaaSource::Spy = source {
+        let mut status = ingestion.status.lock().await;
+        status.total_received += 1;
+        status.last_vaa_at = Some(Utc::now());
+    }
+
+    // Parsed once, for the anomaly pipeline, the live event and the record
+    let parsed = match Vaa::parse(&vaa.vaa_bytes) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            ingestion.processor.lock().await.skip_unparseable(&e);
+            return false;
+        }
+    };
+    let annotations = ingestion.processor.lock().await.annotate_parsed(&parsed, &vaa.vaa_bytes);
+
+    // Keep the guardian set registry in sync with governance VAAs
+    let mut events = Vec::new();
+    let decoded = DecodedVaa::from_vaa(&parsed, state.guardian_sets());
+    match apply_guardian_set_upgrade(state.guardian_sets(), &decoded) {
+        Ok(Some(index)) => events.extend(
+            state.guardian_sets().get(index).map(|guardian_set| ObserverEvent::GuardianSetChanged { guardian_set })
+        ),
+        Ok(None) => {}
+        Err(e) => error!("Failed to apply guardian set upgrade: {}", e),
+    }
+
+    let record = VaaRecord::from_vaa(&parsed, vaa.vaa_bytes, state.guardian_sets().network());
+
+    // Records are keyed by digest, so known VAAs are not stored twice
+    let stored = state.vaas_repository().get(record.id).await.is_none();
+    let vaa_base64 = || base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &record.bytes);
+    match source {
+        VaaSource::Spy => {
+            // Crawled VAAs are stored before the Spy delivers them, if ever
+            let first_delivery = stored || match observed(state.events(), &record.digest).await {
+                Ok(observed) => !observed,
+                Err(e) => {
+                    error!("Failed to look up VAA {} in the event log: {}", record.digest, e);
+                    false
+                }
+            };
+            if first_delivery {
+                events.insert(0, ObserverEvent::VaaObserved {
+                    digest: record.digest.clone(),
+                    message_id: record.message_id.clone(),
+                    emitter_chain: parsed.emitter_chain,
+                    emitter_address: hex::encode(parsed.emitter_address),
+                    sequence: parsed.sequence,
+                    guardian_set_index: parsed.guardian_set_index,
+                    vaa: vaa_base64(),
+                });
+            }
+        }
+        VaaSource::Backfill(rpc_addr) if stored => {
+            events.insert(0, ObserverEvent::VaaBackfilled {
+                digest: record.digest.clone(),
+                message_id: record.message_id.clone(),
+                emitter_chain: parsed.emitter_chain,
+                emitter_address: hex::encode(parsed.emitter_address),
+                sequence: parsed.sequence,
+                guardian_set_index: parsed.guardian_set_index,
+                vaa: vaa_base64(),
+                rpc_addr: rpc_addr.to_string(),
+            });
+        }
+        VaaSource::Backfill(_) => {}
+    }
+    if stored {
+        state.vaas_repository().create(record.clone()).await;
+        if let Some(cache) = state.cache() {
+            cache.create(record.clone()).await;
+        }
+        if let VaaSource::Spy = source {
+            ingestion.status.lock().await.total_stored += 1;
+        }
+    }
+    events.extend(annotation_events(&record, &parsed, &annotations));
+    record_events(state, events).await;
+
+    ingestion.publish(Arc::new(VaaEvent { vaa: decoded, annotations, stored }));
+    stored
+}
+
+// Events for the anomalies found in a VAA
+fn annotation_events(record: &VaaRecord, parsed: &Vaa, annotations: &VaaAnnotations) -> Vec<ObserverEvent> {
+    let emitter_chain = parsed.emitter_chain;
+    let emitter_address = hex::encode(parsed.emitter_address);
+    let mut events = Vec::new();
+
+    if annotations.duplicate {
+        events.push(ObserverEvent::DuplicateSeen {
+            digest: record.digest.clone(),
+            emitter_chain,
+            emitter_address: emitter_address.clone(),
+            sequence: parsed.sequence,
+        });
+    }
+    match &annotations.sequence {
+        Some(SequenceObservation::Gap(gap)) => events.push(ObserverEvent::GapDetected {
+            emitter_chain,
+            emitter_address,
+            gap: gap.clone(),
+            outage: false,
+        }),
+        Some(SequenceObservation::OutageGap(gap)) => events.push(ObserverEvent::GapDetected {
+            emitter_chain,
+            emitter_address,
+            gap: gap.clone(),
+            outage: true,
+        }),
+        Some(SequenceObservation::FilledGap) => events.push(ObserverEvent::GapFilled {
+            emitter_chain,
+            emitter_address,
+            sequence: parsed.sequence,
+        }),
+        _ => {}
+    }
+    events
+}
+
+/// Replays the VAAs kept by a persistent `vaas` repository into the anomaly
+/// pipeline, in the order they were observed, so gap reports survive restarts.
+/// Sequences published while the observer was down are then reported as
+/// outage gaps. Returns the number of VAAs replayed.
+pub async fn restore_ingestion(state: &AppState) -> usize {
+    // Paged in observation order, so the whole table is never held in memory
+    let mut query = VaaQuery {
+        order: QueryOrder::ObservedAt,
+        limit: Some(MAX_QUERY_LIMIT),
+        ..Default::default()
+    };
+    let mut restored = 0;
+    loop {
+        let page = match state.vaas_repository().query(&query).await {
+            Ok(page) => page,
+            Err(e) => {
+                error!("Stopped restoring stored VAAs after {}: {}", restored, e);
+                break;
+            }
+        };
+
+        let mut processor = state.spy_ingestion().processor.lock().await;
+        for record in &page.items {
+            match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &record.base64_bytes) {
+                Ok(vaa_bytes) => {
+                    processor.annotate(&SubscribeSignedVaaResponse { vaa_bytes });
+                }
+                Err(e) => error!("Skipping stored VAA {}: {}", record.digest, e),
+            }
+        }
+        restored += page.items.len();
+
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => break,
+        }
+    }
+    if restored == 0 {
+        return 0;
+    }
+
+    state.spy_ingestion().processor.lock().await.mark_disconnected();
+    info!("Restored {} stored VAAs into the anomaly pipeline", restored);
+    restored
+}
+
+/// Starts the supervised Spy ingestion worker. Each run keeps one
+/// `SubscribeSignedVAA` stream open, receiving the VAAs matching any of
+/// `filters` (all of them if empty). The supervisor restarts it with `backoff`
+/// when it fails, ends or panics.
+pub fn spawn_spy_ingestion(
+    state: Arc<AppState>,
+    spy_addr: String,
+    filters: Vec<SpyFilter>,
+    mut backoff: Backoff,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        state.spy_ingestion().status.lock().await.filters = filters.clone();
+
+        loop {
+            let run = tokio::spawn(run_ingestion(state.clone(), spy_addr.clone(), filters.clone()));
+            let error = match run.await {
+                Ok(Ok(())) => "Spy stream ended".to_string(),
+                Ok(Err(e)) => e.to_string(),
+                Err(e) => format!("Ingestion worker panicked: {}", e),
+            };
+
+            if state.spy_ingestion().disconnected(&error).await {
+                // The last run did connect, start backing off from scratch
+                backoff.reset();
+            }
+            let delay = backoff.next_delay();
+            warn!(
+                "Spy ingestion stopped: {}, reconnecting in {:?} (attempt {})",
+                error, delay, backoff.attempt()
+            );
+            tokio::time::sleep(delay).await;
+        }
+    })
+}
+
+impl SpyIngestion {
+    // Marks the stream as connected, closing the outage in progress if any
+    async fn connected(&self) {
+        let mut status = self.status.lock().await;
+        let now = Utc::now();
+        status.connected = true;
+        status.connected_since = Some(now);
+        if let Some(outage) = status.outages.last_mut().filter(|outage| outage.ended_at.is_none()) {
+            info!("Spy stream resumed after outage started at {}", outage.started_at);
+            outage.ended_at = Some(now);
+        }
+    }
+
+    // Records a failed run. Returns whether the stream had been connected, in
+    // which case a new outage starts and the emitters' high-water marks are kept.
+    async fn disconnected(&self, error: &str) -> bool {
+        let mut status = self.status.lock().await;
+        let was_connected = status.connected;
+        status.connected = false;
+        status.connected_since = None;
+        status.restarts += 1;
+        status.last_error = Some(error.to_string());
+
+        if was_connected {
+            self.processor.lock().await.mark_disconnected();
+            status.outages.push(IngestionOutage {
+                started_at: Utc::now(),
+                ended_at: None,
+                reason: error.to_string(),
+            });
+            if status.outages.len() > MAX_RECORDED_OUTAGES {
+                status.outages.remove(0);
+            }
+        }
+        was_connected
+    }
+}
+
+async fn run_ingestion(state: Arc<AppState>, spy_addr: String, filters: Vec<SpyFilter>) -> Result<(), Error> {
+    let mut client = GrpcClient::connect(spy_addr).await?;
+    let mut stream = client.subscribe(to_entries(&filters)?).await?;
+
+    state.spy_ingestion().connected().await;
+    info!("Spy ingestion stream started");
+
+    while let Some(response) = stream.next().await {
+        let vaa = response.map_err(|e| Error::Subscription(e.to_string()))?;
+        if ingest_vaa(&state, vaa).await {
+            debug!("Stored new VAA from Spy");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::wormhole::builder::test_vaa_bytes;
+    use crate::state::Repositories;
+    use crate::storage::{Repository, VaaRepository, events::{Event, EventStore, MemoryEventStore}, memory::MemoryRepository};
+    use crate::domain::wormhole::guardians::GuardianSetRegistry;
+
+    async fn test_state() -> AppState {
+        let repositories = Repositories::new(
+            Repository::new(MemoryRepository::new()),
+            VaaRepository::new(MemoryRepository::new()),
+            EventStore::new(MemoryEventStore::new()),
+        );
+        let guardian_sets = Arc::new(GuardianSetRegistry::bundled("devnet").unwrap());
+        AppState::new(repositories, guardian_sets).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ingest_stores_each_digest_once() {
+        let state = test_state().await;
+
+        for sequence in [1, 1, 4] {
+            ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(sequence) }).await;
+        }
+
+        assert_eq!(state.vaas_repository().list().await.len(), 2);
+        let status = state.spy_ingestion().status().await;
+        assert_eq!((status.total_received, status.total_stored), (3, 2));
+
+        let (_, metadata) = state.spy_ingestion().metadata().await;
+        assert_eq!(metadata.duplicate_count, 1);
+        assert_eq!(metadata.sequence_gaps, 1);
+        assert_eq!(metadata.emitters[0].sequences.sequence_gaps[0].from, 2);
+
+        let events = state.events().read(&Default::default()).await.unwrap();
+        let kinds: Vec<&str> = events.iter().map(|stored| stored.event.kind()).collect();
+        assert_eq!(kinds, vec!["vaa_observed", "duplicate_seen", "vaa_observed", "gap_detected"]);
+        assert_eq!(state.projections().emitters().await[0].missing_sequences, 2);
+    }
+
+    #[tokio::test]
+    async fn test_crawled_vaas_are_observed_once_delivered() {
+        let state = test_state().await;
+        let crawled = VaaRecord::from_bytes(test_vaa_bytes(1), "mainnet").unwrap();
+        state.vaas_repository().create(crawled.clone()).await;
+
+        for _ in 0..2 {
+            assert!(!ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(1) }).await);
+        }
+        let events = state.events().read(&Default::default()).await.unwrap();
+        let kinds: Vec<&str> = events.iter().map(|stored| stored.event.kind()).collect();
+        assert_eq!(kinds, vec!["vaa_observed", "duplicate_seen"]);
+        assert!(observed(state.events(), &crawled.digest).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_reports_outage_gaps() {
+        let state = test_state().await;
+        let ingestion = state.spy_ingestion();
+
+        // Failing to connect at startup is not an outage
+        assert!(!ingestion.disconnected("connection refused").await);
+
+        ingestion.connected().await;
+        ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(1) }).await;
+        ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(3) }).await;
+        assert!(ingestion.disconnected("stream reset").await);
+        ingestion.connected().await;
+        ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(7) }).await;
+
+        let (_, metadata) = ingestion.metadata().await;
+        assert_eq!((metadata.sequence_gaps, metadata.outage_gaps), (1, 1));
+        let emitter = &metadata.emitters[0];
+        assert_eq!((emitter.sequences.sequence_gaps[0].from, emitter.sequences.sequence_gaps[0].to), (2, 2));
+        assert_eq!((emitter.outage_gaps[0].from, emitter.outage_gaps[0].to), (4, 6));
+
+        let status = ingestion.status().await;
+        assert_eq!(status.outages.len(), 1);
+        assert!(status.outages[0].ended_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_restore_keeps_gap_reports() {
+        let state = test_state().await;
+        for sequence in [1, 3] {
+            ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(sequence) }).await;
+        }
+
+        // A restarted observer on the same storage
+        let guardian_sets = Arc::new(GuardianSetRegistry::bundled("devnet").unwrap());
+        let repositories = Repositories::new(
+            state.wormhole_repository().clone(),
+            state.vaas_repository().clone(),
+            state.events().clone(),
+        );
+        let restarted = AppState::new(repositories, guardian_sets).await.unwrap();
+        assert_eq!(restore_ingestion(&restarted).await, 2);
+        ingest_vaa(&restarted, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(6) }).await;
+
+        let (_, metadata) = restarted.spy_ingestion().metadata().await;
+        assert_eq!((metadata.sequence_gaps, metadata.outage_gaps), (1, 1));
+        assert_eq!((metadata.emitters[0].outage_gaps[0].from, metadata.emitters[0].outage_gaps[0].to), (4, 5));
+    }
+
+    #[tokio::test]
+    async fn test_ingested_vaas_are_published() {
+        let state = test_state().await;
+        let mut events = state.spy_ingestion().subscribe();
+
+        for sequence in [1, 1, 3] {
+            ingest_vaa(&state, SubscribeSignedVaaResponse { vaa_bytes: test_vaa_bytes(sequence) }).await;
+        }
+
+        let first = events.recv().await.unwrap();
+        assert!(first.stored && !first.annotations.duplicate);
+        assert_eq!(first.vaa.sequence, 1);
+
+        let duplicate = events.recv().await.unwrap();
+        assert!(!duplicate.stored && duplicate.annotations.duplicate);
+
+        let gap = events.recv().await.unwrap();
+        assert!(matches!(gap.annotations.sequence, Some(SequenceObservation::Gap(_))));
+    }
+}
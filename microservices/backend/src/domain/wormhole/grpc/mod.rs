@@ -1,6 +1,11 @@
 pub mod proto;
 pub mod client;
+pub mod fanout;
+pub mod filters;
+pub mod public_rpc;
 pub mod handlers;
+pub mod ingest;
+pub mod stream;
 pub mod vaa;
 
 // Re-export scoped routes
@@ -0,0 +1,198 @@
+use tonic::{transport::Channel, Code, Request};
+use tracing::{info, error};
+
+use crate::library::errors::Error;
+use crate::domain::wormhole::chains;
+use crate::domain::wormhole::governor::{ChainNotional, EnqueuedVaa, GovernorToken};
+use crate::domain::wormhole::guardians::{GuardianSet, GuardianSetSource};
+use crate::domain::wormhole::guardians::heartbeats::GuardianHeartbeat;
+
+// Generated proto types
+use crate::domain::wormhole::grpc::proto::publicrpc::v1::{
+    public_rpc_service_client::PublicRpcServiceClient,
+    GetCurrentGuardianSetRequest,
+    GetLastHeartbeatsRequest,
+    GetSignedVaaRequest,
+    GovernorGetAvailableNotionalByChainRequest,
+    GovernorGetEnqueuedVaAsRequest,
+    GovernorGetTokenListRequest,
+    GovernorIsVaaEnqueuedRequest,
+    MessageId,
+};
+use super::client::connect_channel;
+
+/// Client for a guardian node's `PublicRPCService`.
+#[derive(Clone)]
+pub struct PublicRpcClient {
+    client: PublicRpcServiceClient<Channel>,
+}
+
+impl PublicRpcClient {
+    pub async fn connect(addr: String) -> Result<Self, Error> {
+        let channel = connect_channel(addr).await?;
+        let client = PublicRpcServiceClient::new(channel);
+        info!("Successfully connected to guardian public RPC");
+        Ok(Self { client })
+    }
+
+    /// Connects to the first of `addrs` that can be reached, returning its address too.
+    pub async fn connect_any(addrs: &[String]) -> Result<(String, Self), Error> {
+        let mut last_error = Error::Connection("No guardian public RPC endpoint configured".to_string());
+        for addr in addrs {
+            match Self::connect(addr.clone()).await {
+                Ok(client) => return Ok((addr.clone(), client)),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    pub async fn get_current_guardian_set(&mut self) -> Result<GuardianSet, Error> {
+        let response = self.client
+            .get_current_guardian_set(Request::new(GetCurrentGuardianSetRequest {}))
+            .await
+            .map_err(|e| rpc_error("GetCurrentGuardianSet", e))?
+            .into_inner();
+
+        let guardian_set = response.guardian_set
+            .ok_or_else(|| Error::External("Empty guardian set response".to_string()))?;
+
+        Ok(GuardianSet::new(guardian_set.index, guardian_set.addresses, GuardianSetSource::PublicRpc))
+    }
+
+    /// Last heartbeat of every guardian node in the guardian's active set. Nodes
+    /// the guardian has not heard from yet are left out.
+    pub async fn get_last_heartbeats(&mut self) -> Result<Vec<GuardianHeartbeat>, Error> {
+        let response = self.client
+            .get_last_heartbeats(Request::new(GetLastHeartbeatsRequest {}))
+            .await
+            .map_err(|e| rpc_error("GetLastHeartbeats", e))?
+            .into_inner();
+
+        Ok(response.entries
+            .into_iter()
+            .filter_map(|entry| {
+                let heartbeat = entry.raw_heartbeat?;
+                Some(GuardianHeartbeat::from_raw(entry.verified_guardian_addr, entry.p2p_node_addr, heartbeat))
+            })
+            .collect())
+    }
+
+    /// Asks the guardian for a signed VAA by message ID. `None` when the
+    /// guardian answers NotFound, i.e. it never signed or no longer keeps it.
+    pub async fn get_signed_vaa(
+        &mut self,
+        emitter_chain: u16,
+        emitter_address: &str,
+        sequence: u64,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let request = GetSignedVaaRequest {
+            message_id: Some(MessageId {
+                emitter_chain: emitter_chain.into(),
+                emitter_address: emitter_address.to_string(),
+                sequence,
+            }),
+        };
+        match self.client.get_signed_vaa(Request::new(request)).await {
+            Ok(response) => Ok(Some(response.into_inner().vaa_bytes)),
+            Err(status) if status.code() == Code::NotFound => Ok(None),
+            Err(e) => Err(rpc_error("GetSignedVAA", e)),
+        }
+    }
+
+    /// VAAs the guardian's Governor holds back, with their expected release time
+    pub async fn governor_get_enqueued_vaas(&mut self) -> Result<Vec<EnqueuedVaa>, Error> {
+        let response = self.client
+            .governor_get_enqueued_va_as(Request::new(GovernorGetEnqueuedVaAsRequest {}))
+            .await
+            .map_err(|e| rpc_error("GovernorGetEnqueuedVAAs", e))?
+            .into_inner();
+
+        Ok(response.entries
+            .into_iter()
+            .filter_map(|entry| {
+                let emitter_chain = u16::try_from(entry.emitter_chain).ok()?;
+                Some(EnqueuedVaa {
+                    emitter_chain,
+                    emitter_chain_name: chains::name(emitter_chain),
+                    emitter_address: entry.emitter_address.trim_start_matches("0x").to_lowercase(),
+                    sequence: entry.sequence,
+                    release_time: chrono::DateTime::from_timestamp(i64::from(entry.release_time), 0)?,
+                    notional_value: entry.notional_value,
+                    tx_hash: entry.tx_hash,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn governor_is_vaa_enqueued(
+        &mut self,
+        emitter_chain: u16,
+        emitter_address: &str,
+        sequence: u64,
+    ) -> Result<bool, Error> {
+        let request = GovernorIsVaaEnqueuedRequest {
+            message_id: Some(MessageId {
+                emitter_chain: emitter_chain.into(),
+                emitter_address: emitter_address.to_string(),
+                sequence,
+            }),
+        };
+        Ok(self.client
+            .governor_is_vaa_enqueued(Request::new(request))
+            .await
+            .map_err(|e| rpc_error("GovernorIsVAAEnqueued", e))?
+            .into_inner()
+            .is_enqueued)
+    }
+
+    /// Notional limits of the governed chains, the others are unlimited
+    pub async fn governor_get_available_notional_by_chain(&mut self) -> Result<Vec<ChainNotional>, Error> {
+        let response = self.client
+            .governor_get_available_notional_by_chain(Request::new(GovernorGetAvailableNotionalByChainRequest {}))
+            .await
+            .map_err(|e| rpc_error("GovernorGetAvailableNotionalByChain", e))?
+            .into_inner();
+
+        Ok(response.entries
+            .into_iter()
+            .filter_map(|entry| {
+                let chain_id = u16::try_from(entry.chain_id).ok()?;
+                Some(ChainNotional {
+                    chain_id,
+                    chain_name: chains::name(chain_id),
+                    remaining_available_notional: entry.remaining_available_notional,
+                    notional_limit: entry.notional_limit,
+                    big_transaction_size: entry.big_transaction_size,
+                })
+            })
+            .collect())
+    }
+
+    /// Tokens counted in the Governor's notional value, with their price
+    pub async fn governor_get_token_list(&mut self) -> Result<Vec<GovernorToken>, Error> {
+        let response = self.client
+            .governor_get_token_list(Request::new(GovernorGetTokenListRequest {}))
+            .await
+            .map_err(|e| rpc_error("GovernorGetTokenList", e))?
+            .into_inner();
+
+        Ok(response.entries
+            .into_iter()
+            .filter_map(|entry| {
+                let origin_chain_id = u16::try_from(entry.origin_chain_id).ok()?;
+                Some(GovernorToken {
+                    origin_chain_id,
+                    origin_chain_name: chains::name(origin_chain_id),
+                    origin_address: entry.origin_address.trim_start_matches("0x").to_lowercase(),
+                    price: entry.price,
+                })
+            })
+            .collect())
+    }
+}
+
+fn rpc_error(method: &str, status: tonic::Status) -> Error {
+    error!("{} error: {:?}", method, status);
+    Error::External(status.to_string())
+}
@@ -0,0 +1,175 @@
+// Live VAA feeds over Server-Sent Events and WebSocket, fed by the ingestion worker
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use aide::{axum::IntoApiResponse, transform::TransformOperation};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_stream::{wrappers::{errors::BroadcastStreamRecvError, BroadcastStream}, StreamExt};
+use tracing::{debug, warn};
+
+use crate::AppState;
+use crate::library::errors::{AppError, Error};
+use crate::domain::wormhole::chains;
+use super::ingest::VaaEvent;
+
+/// Per-connection filter, both fields are optional.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct StreamFilter {
+    /// Only VAAs emitted from this chain
+    pub chain_id: Option<u16>,
+    /// Only VAAs from this hex-encoded 32-byte emitter address
+    pub emitter: Option<String>,
+}
+
+impl StreamFilter {
+    // Validates the filter and normalizes the emitter to the `DecodedVaa` format
+    fn validated(mut self) -> Result<Self, Error> {
+        if let Some(chain_id) = self.chain_id {
+            chains::get(chain_id).ok_or_else(|| Error::Request(format!("Unknown chain ID {}", chain_id)))?;
+        }
+        if let Some(emitter) = &self.emitter {
+            let emitter = emitter.trim_start_matches("0x").to_lowercase();
+            if hex::decode(&emitter).map(|bytes| bytes.len()) != Ok(32) {
+                return Err(Error::Request(format!("Invalid emitter address {}: expected 32 hex-encoded bytes", emitter)));
+            }
+            self.emitter = Some(format!("0x{}", emitter));
+        }
+        Ok(self)
+    }
+
+    fn matches(&self, event: &VaaEvent) -> bool {
+        self.chain_id.is_none_or(|chain_id| event.vaa.emitter_chain == chain_id)
+            && self.emitter.as_ref().is_none_or(|emitter| event.vaa.emitter_address == *emitter)
+    }
+}
+
+/// Message sent to stream clients.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage<'a> {
+    Vaa(&'a VaaEvent),
+    /// The client fell behind and `skipped` VAAs were dropped for it
+    Lagged { skipped: u64 },
+}
+
+impl StreamMessage<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            StreamMessage::Vaa(_) => "vaa",
+            StreamMessage::Lagged { .. } => "lagged",
+        }
+    }
+}
+
+pub async fn stream_vaas_sse(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<StreamFilter>,
+) -> impl IntoApiResponse {
+    let filter = match filter.validated() {
+        Ok(filter) => filter,
+        Err(e) => return AppError::new(&e.to_string()).into_response(),
+    };
+
+    let events = BroadcastStream::new(state.spy_ingestion().subscribe())
+        .filter_map(move |event| {
+            let message = match &event {
+                Ok(event) if filter.matches(event) => StreamMessage::Vaa(event),
+                Ok(_) => return None,
+                Err(BroadcastStreamRecvError::Lagged(skipped)) => StreamMessage::Lagged { skipped: *skipped },
+            };
+            let event = Event::default()
+                .event(message.name())
+                .json_data(&message)
+                .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()));
+            Some(Ok::<_, Infallible>(event))
+        });
+
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+pub async fn stream_vaas_ws(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<StreamFilter>,
+    ws: WebSocketUpgrade,
+) -> impl IntoApiResponse {
+    let filter = match filter.validated() {
+        Ok(filter) => filter,
+        Err(e) => return AppError::new(&e.to_string()).into_response(),
+    };
+
+    let events = state.spy_ingestion().subscribe();
+    ws.on_upgrade(move |socket| forward_to_socket(socket, events, filter))
+        .into_response()
+}
+
+// Sends each matching event, waiting for the socket to accept it before
+// reading the next one. Events piling up meanwhile are bounded by the channel.
+async fn forward_to_socket(mut socket: WebSocket, mut events: broadcast::Receiver<Arc<VaaEvent>>, filter: StreamFilter) {
+    loop {
+        let json = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) if filter.matches(&event) => serde_json::to_string(&StreamMessage::Vaa(&event)),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("WebSocket client lagged, skipping {} VAAs", skipped);
+                    serde_json::to_string(&StreamMessage::Lagged { skipped })
+                }
+                Err(RecvError::Closed) => break,
+            },
+            // Only used to notice the client going away
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        let Ok(json) = json else { continue };
+        if socket.send(Message::Text(json.into())).await.is_err() {
+            break;
+        }
+    }
+    debug!("WebSocket client disconnected");
+}
+
+pub fn stream_vaas_sse_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Server-Sent Events feed of the VAAs ingested from the Spy, as they arrive. \
+            Each `vaa` event holds the decoded VAA and its anomaly annotations, a `lagged` event \
+            tells how many VAAs a slow client skipped.")
+        .tag("wormhole-spy")
+        .response::<400, AppError>()
+}
+
+pub fn stream_vaas_ws_docs(op: TransformOperation) -> TransformOperation {
+    op.description("WebSocket feed of the VAAs ingested from the Spy, as they arrive. \
+            Messages are JSON objects whose `type` is `vaa` (decoded VAA and anomaly annotations) \
+            or `lagged` (VAAs skipped by a slow client).")
+        .tag("wormhole-spy")
+        .response::<400, AppError>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_validation() {
+        let emitter = format!("0x{}", "AB".repeat(32));
+        let filter = StreamFilter { chain_id: Some(30), emitter: Some(emitter) }.validated().unwrap();
+        assert_eq!(filter.emitter, Some(format!("0x{}", "ab".repeat(32))));
+
+        assert!(StreamFilter { chain_id: Some(9_999), emitter: None }.validated().is_err());
+        assert!(StreamFilter { chain_id: None, emitter: Some("abcd".to_string()) }.validated().is_err());
+    }
+}
@@ -1,26 +1,65 @@
 use std::collections::{HashSet, HashMap};
-use tracing::{debug, info, error};
+use tracing::{debug, info, warn, error};
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64_STANDARD};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 
 use crate::domain::wormhole::grpc::proto::spy::v1::SubscribeSignedVaaResponse;
+use crate::domain::wormhole::chains;
+use crate::domain::wormhole::vaa::{Vaa, VaaDecodeError};
+use crate::domain::wormhole::models::{SequenceGap, VaaMetadata as SequenceMetadata};
+use crate::domain::wormhole::sequences::{SequenceObservation, SequenceTracker};
 
 #[derive(Debug, Clone, Serialize, Default, JsonSchema)]
 pub struct VaaMetadata {
     pub total_processed: usize,
     pub unique_count: usize,
     pub duplicate_count: usize,
-    // Potential missing VAAs
+    pub unparseable_count: usize,
+    // Potential missing VAAs, across all emitters
     pub sequence_gaps: usize,
+    // Ranges that may only have been missed while the Spy stream was down
+    pub outage_gaps: usize,
     pub duplicated_hashes: Vec<String>,
+    // Per-emitter sequence analysis, same shape as the Scan path
+    pub emitters: Vec<EmitterSequences>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EmitterSequences {
+    pub emitter_chain: u16,
+    pub emitter_chain_name: Option<String>,
+    pub emitter_address: String,
+    #[serde(flatten)]
+    pub sequences: SequenceMetadata,
+    /// Sequences missed across ingestion outages, not counted in `sequence_gaps`
+    pub outage_gaps: Vec<SequenceGap>,
+}
+
+/// A range of sequences still missing for an emitter.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct OpenGap {
+    pub emitter_chain: u16,
+    pub emitter_address: String,
+    pub gap: SequenceGap,
+    /// The gap opened across an ingestion outage
+    pub outage: bool,
+}
+
+/// Anomalies spotted on a single VAA as it is processed.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VaaAnnotations {
+    /// The digest was already seen on the stream
+    pub duplicate: bool,
+    /// How the sequence relates to the emitter's previous ones, unset for duplicates
+    pub sequence: Option<SequenceObservation>,
 }
 
 pub struct VaaProcessor {
     seen_hashes: HashSet<String>,
     duplicated_hashes: HashSet<String>, 
-    // emitter -> sequence numbers
-    sequences: HashMap<String, Vec<u64>>,
+    // (chain, emitter) -> sequence tracking
+    sequences: HashMap<(u16, String), SequenceTracker>,
     count: usize,
     limit: usize,
     metadata: VaaMetadata,
@@ -38,59 +77,147 @@ impl VaaProcessor {
         }
     }
 
+    /// A processor without a VAA limit, for the long-running ingestion worker
+    pub fn unbounded() -> Self {
+        Self::new(usize::MAX)
+    }
+
     pub fn process_vaa(&mut self, vaa: SubscribeSignedVaaResponse) -> bool {
         // Stop processing if we've reached the limit
         if self.count >= self.limit {
             return false;
         }
 
+        self.annotate(&vaa);
+        true
+    }
+
+    /// Processes a VAA regardless of the limit, returning its anomalies.
+    /// Unparseable VAAs are only counted.
+    pub fn annotate(&mut self, vaa: &SubscribeSignedVaaResponse) -> Option<VaaAnnotations> {
+        match Vaa::parse(&vaa.vaa_bytes) {
+            Ok(parsed) => Some(self.annotate_parsed(&parsed, &vaa.vaa_bytes)),
+            Err(e) => {
+                self.skip_unparseable(&e);
+                None
+            }
+        }
+    }
+
+    /// Counts a VAA that failed to parse before reaching the processor.
+    pub fn skip_unparseable(&mut self, e: &VaaDecodeError) {
+        error!("Skipping unparseable VAA: {}", e);
+        self.metadata.total_processed += 1;
+        self.metadata.unparseable_count += 1;
+    }
+
+    /// `annotate` for a VAA already parsed from `vaa_bytes`.
+    pub fn annotate_parsed(&mut self, parsed: &Vaa, vaa_bytes: &[u8]) -> VaaAnnotations {
         self.metadata.total_processed += 1;
-        // Use first 32 bytes as hash for deduplication
-        let hash = hex::encode(&vaa.vaa_bytes[0..32]);
+        // Use the VAA digest for deduplication, it only covers the signed body
+        let hash = hex::encode(parsed.digest());
         
         if !self.seen_hashes.contains(&hash) {
             self.seen_hashes.insert(hash.clone());
             self.count += 1;
             self.metadata.unique_count += 1;
             
-            // TODO: Extract emitter and sequence from VAA bytes (those are just placeholders)
-            let emitter = "default";
-            let sequence = self.count as u64;
-            
             // Track sequence numbers per emitter
-            let sequences = self.sequences
-                .entry(emitter.to_string())
-                .or_insert_with(Vec::new);
-            sequences.push(sequence);
+            let emitter = hex::encode(parsed.emitter_address);
+            let tracker = self.sequences
+                .entry((parsed.emitter_chain, emitter.clone()))
+                .or_default();
             
             // Check for gaps in sequence
-            if sequences.len() > 1 {
-                let last = sequences[sequences.len() - 2];
-                if sequence > last + 1 {
+            let observation = tracker.observe(parsed.sequence);
+            match &observation {
+                SequenceObservation::Gap(gap) => {
                     self.metadata.sequence_gaps += 1;
+                    info!(
+                        "Sequence gap for {}/{}: {}..={} ({} missing)",
+                        parsed.emitter_chain, emitter, gap.from, gap.to, gap.size
+                    );
+                }
+                SequenceObservation::OutageGap(gap) => {
+                    self.metadata.outage_gaps += 1;
+                    warn!(
+                        "Possibly missed during ingestion outage for {}/{}: {}..={} ({} sequences)",
+                        parsed.emitter_chain, emitter, gap.from, gap.to, gap.size
+                    );
+                }
+                SequenceObservation::FilledGap => {
+                    debug!("Late VAA filled a gap: {}", parsed.message_id());
                 }
+                SequenceObservation::Duplicate => {
+                    info!("Sequence reused with a different digest: {}", parsed.message_id());
+                }
+                SequenceObservation::InOrder => {}
             }
 
             info!(
-                "VAA #{}: {} bytes, hash: {}",
+                "VAA #{}: {} bytes, {} digest: {}",
                 self.count,
-                vaa.vaa_bytes.len(),
+                vaa_bytes.len(),
+                parsed.message_id(),
                 hash
             );
-            debug!("Full VAA: {}", BASE64_STANDARD.encode(&vaa.vaa_bytes));
-            // TODO: Add to missing sequence anomaly detection logic
-            true
+            debug!("Full VAA: {}", BASE64_STANDARD.encode(vaa_bytes));
+            VaaAnnotations { duplicate: false, sequence: Some(observation) }
         } else {
             debug!("Found duplicate VAA with hash: {}", hash);
             self.metadata.duplicate_count += 1;
             self.duplicated_hashes.insert(hash.clone());
-            true
+            VaaAnnotations { duplicate: true, sequence: None }
+        }
+    }
+
+    /// Remembers every emitter's high-water mark when the stream drops, so the
+    /// sequences skipped until it resumes are reported as outage gaps.
+    pub fn mark_disconnected(&mut self) {
+        for tracker in self.sequences.values_mut() {
+            tracker.mark_outage();
         }
     }
 
     pub fn finalize_metadata(&mut self) {
         // Convert duplicated_hashes to Vec for the response
         self.metadata.duplicated_hashes = self.duplicated_hashes.iter().cloned().collect();
+
+        let mut emitters: Vec<EmitterSequences> = self.sequences
+            .iter()
+            .map(|((emitter_chain, emitter_address), tracker)| EmitterSequences {
+                emitter_chain: *emitter_chain,
+                emitter_chain_name: chains::name(*emitter_chain),
+                emitter_address: emitter_address.clone(),
+                sequences: tracker.metadata(),
+                outage_gaps: tracker.outage_gaps().to_vec(),
+            })
+            .collect();
+        emitters.sort_by(|a, b| (a.emitter_chain, &a.emitter_address).cmp(&(b.emitter_chain, &b.emitter_address)));
+        self.metadata.sequence_gaps = emitters.iter().map(|emitter| emitter.sequences.total_gaps).sum();
+        self.metadata.outage_gaps = emitters.iter().map(|emitter| emitter.outage_gaps.len()).sum();
+        self.metadata.emitters = emitters;
+    }
+
+    /// Every gap not filled yet, by emitter and then sequence
+    pub fn open_gaps(&self) -> Vec<OpenGap> {
+        let mut open: Vec<OpenGap> = self.sequences
+            .iter()
+            .flat_map(|((emitter_chain, emitter_address), tracker)| {
+                let gaps = tracker.gaps().iter().map(|gap| (gap, false));
+                let outage_gaps = tracker.outage_gaps().iter().map(|gap| (gap, true));
+                gaps.chain(outage_gaps).map(|(gap, outage)| OpenGap {
+                    emitter_chain: *emitter_chain,
+                    emitter_address: emitter_address.clone(),
+                    gap: gap.clone(),
+                    outage,
+                })
+            })
+            .collect();
+        open.sort_by(|a, b| {
+            (a.emitter_chain, &a.emitter_address, a.gap.from).cmp(&(b.emitter_chain, &b.emitter_address, b.gap.from))
+        });
+        open
     }
 
     pub fn processed_count(&self) -> usize {
@@ -103,7 +230,9 @@ impl VaaProcessor {
 
     // Add a helper method to verify counts
     pub fn verify_counts(&self) -> bool {
-        let expected_total = self.metadata.unique_count + self.metadata.duplicate_count;
+        let expected_total = self.metadata.unique_count
+            + self.metadata.duplicate_count
+            + self.metadata.unparseable_count;
         if expected_total != self.metadata.total_processed {
             error!(
                 "Count mismatch - Total: {}, Unique: {}, Duplicates: {}", 
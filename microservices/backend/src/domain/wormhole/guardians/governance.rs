@@ -0,0 +1,101 @@
+use std::io::{Cursor, Read};
+use byteorder::{BigEndian, ReadBytesExt};
+use tracing::{info, warn};
+
+use crate::library::errors::Error;
+use crate::domain::wormhole::rest::vaa::DecodedVaa;
+use super::registry::{GuardianSet, GuardianSetRegistry, GuardianSetSource};
+
+// Governance VAAs are emitted by the governance contract on Solana
+pub const GOVERNANCE_CHAIN: u16 = 1;
+pub const GOVERNANCE_EMITTER: &str = "0x0000000000000000000000000000000000000000000000000000000000000004";
+
+// "Core" left-padded to 32 bytes
+const CORE_MODULE: [u8; 32] = {
+    let mut module = [0u8; 32];
+    module[28] = b'C';
+    module[29] = b'o';
+    module[30] = b'r';
+    module[31] = b'e';
+    module
+};
+const ACTION_GUARDIAN_SET_UPGRADE: u8 = 2;
+
+#[derive(Debug, Clone)]
+pub struct GuardianSetUpgrade {
+    pub target_chain: u16,
+    pub new_index: u32,
+    pub addresses: Vec<[u8; 20]>,
+}
+
+/// Parses a Core Bridge governance payload, returning `None` for other actions.
+pub fn parse_guardian_set_upgrade(payload: &[u8]) -> Result<Option<GuardianSetUpgrade>, Error> {
+    let mut cursor = Cursor::new(payload);
+    let parsing_error = |e: std::io::Error| Error::Parsing(format!("Invalid governance payload: {}", e));
+
+    let mut module = [0u8; 32];
+    if cursor.read_exact(&mut module).is_err() || module != CORE_MODULE {
+        return Ok(None);
+    }
+    let action = cursor.read_u8().map_err(parsing_error)?;
+    if action != ACTION_GUARDIAN_SET_UPGRADE {
+        return Ok(None);
+    }
+
+    let target_chain = cursor.read_u16::<BigEndian>().map_err(parsing_error)?;
+    let new_index = cursor.read_u32::<BigEndian>().map_err(parsing_error)?;
+    let num_guardians = cursor.read_u8().map_err(parsing_error)?;
+
+    let mut addresses = Vec::with_capacity(num_guardians as usize);
+    for _ in 0..num_guardians {
+        let mut address = [0u8; 20];
+        cursor.read_exact(&mut address).map_err(parsing_error)?;
+        addresses.push(address);
+    }
+
+    Ok(Some(GuardianSetUpgrade { target_chain, new_index, addresses }))
+}
+
+/// Registers the new guardian set carried by a `GuardianSetUpgrade` governance VAA.
+/// Only upgrades signed by a quorum of the set they replace are accepted.
+/// Returns the new guardian set index when the registry changed.
+pub fn apply_guardian_set_upgrade(
+    registry: &GuardianSetRegistry,
+    vaa: &DecodedVaa,
+) -> Result<Option<u32>, Error> {
+    if vaa.emitter_chain != GOVERNANCE_CHAIN || vaa.emitter_address != GOVERNANCE_EMITTER {
+        return Ok(None);
+    }
+    let Some(payload) = &vaa.payload else {
+        return Ok(None);
+    };
+    let payload = hex::decode(payload).map_err(|e| Error::Parsing(e.to_string()))?;
+    let Some(upgrade) = parse_guardian_set_upgrade(&payload)? else {
+        return Ok(None);
+    };
+
+    if !vaa.verification.quorum_reached {
+        warn!("Ignoring guardian set upgrade to {} without quorum", upgrade.new_index);
+        return Ok(None);
+    }
+    if upgrade.new_index != vaa.guardian_set_index + 1 {
+        warn!(
+            "Ignoring guardian set upgrade to {} signed by guardian set {}",
+            upgrade.new_index, vaa.guardian_set_index
+        );
+        return Ok(None);
+    }
+
+    let addresses = upgrade.addresses
+        .iter()
+        .map(|address| format!("0x{}", hex::encode(address)))
+        .collect();
+    let guardian_set = GuardianSet::new(upgrade.new_index, addresses, GuardianSetSource::Governance);
+
+    if registry.insert(guardian_set, vaa.timestamp) {
+        info!("Applied guardian set upgrade to index {}", upgrade.new_index);
+        Ok(Some(upgrade.new_index))
+    } else {
+        Ok(None)
+    }
+}
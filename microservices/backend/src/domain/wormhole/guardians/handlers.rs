@@ -0,0 +1,149 @@
+use std::sync::Arc;
+use aide::{
+    axum::{routing::get_with, ApiRouter, IntoApiResponse},
+    transform::TransformOperation,
+    OperationOutput,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::AppState;
+use crate::library::config::get_config;
+use crate::library::errors::AppError;
+use super::heartbeats::{HeartbeatSnapshot, HeartbeatSummary, MAX_HEARTBEAT_SNAPSHOTS};
+use super::registry::GuardianSet;
+
+pub fn guardian_routes(state: Arc<AppState>) -> ApiRouter {
+    ApiRouter::new()
+        .api_route(
+            "/observer/guardian-sets",
+            get_with(get_guardian_sets, get_guardian_sets_docs),
+        )
+        .api_route(
+            "/observer/guardian-sets/{index}",
+            get_with(get_guardian_set, get_guardian_set_docs),
+        )
+        .api_route(
+            "/observer/guardians",
+            get_with(get_guardians, get_guardians_docs),
+        )
+        .api_route(
+            "/observer/guardians/history",
+            get_with(get_guardians_history, get_guardians_history_docs),
+        )
+        .with_state(state)
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct GuardianSetsResponse {
+    network: String,
+    current_index: Option<u32>,
+    guardian_sets: Vec<GuardianSet>,
+}
+
+impl OperationOutput for GuardianSetsResponse {
+    type Inner = Self;
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GuardianSetParams {
+    index: u32,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct HeartbeatHistoryParams {
+    /// Only keep the lags on this chain
+    chain_id: Option<u16>,
+    /// Earliest poll (RFC 3339), inclusive
+    #[schemars(with = "Option<String>")]
+    from: Option<DateTime<Utc>>,
+    /// Latest poll (RFC 3339), exclusive
+    #[schemars(with = "Option<String>")]
+    to: Option<DateTime<Utc>>,
+    /// Most recent summaries returned, all of them by default
+    limit: Option<usize>,
+}
+
+async fn get_guardian_sets(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoApiResponse {
+    let registry = state.guardian_sets();
+
+    Json(GuardianSetsResponse {
+        network: registry.network().to_string(),
+        current_index: registry.current().map(|set| set.index),
+        guardian_sets: registry.list(),
+    }).into_response()
+}
+
+async fn get_guardian_set(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<GuardianSetParams>,
+) -> impl IntoApiResponse {
+    match state.guardian_sets().get(params.index) {
+        Some(guardian_set) => Json(guardian_set).into_response(),
+        None => AppError::new(&format!("Unknown guardian set {}", params.index))
+            .with_status(StatusCode::NOT_FOUND)
+            .into_response(),
+    }
+}
+
+async fn get_guardians(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoApiResponse {
+    if get_config().wormhole_guardian_rpc_addrs.is_empty() {
+        return AppError::new("No guardian public RPC endpoint configured, set WORMHOLE_GUARDIAN_RPC_ADDRS")
+            .with_status(StatusCode::SERVICE_UNAVAILABLE)
+            .into_response();
+    }
+    match state.heartbeats().latest().await {
+        Some(snapshot) => Json(snapshot).into_response(),
+        None => AppError::new("No guardian heartbeats polled yet")
+            .with_status(StatusCode::SERVICE_UNAVAILABLE)
+            .into_response(),
+    }
+}
+
+async fn get_guardians_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HeartbeatHistoryParams>,
+) -> impl IntoApiResponse {
+    let limit = params.limit.unwrap_or(MAX_HEARTBEAT_SNAPSHOTS);
+    Json(state.heartbeats().history(params.chain_id, params.from, params.to, limit).await).into_response()
+}
+
+fn get_guardian_sets_docs(op: TransformOperation) -> TransformOperation {
+    op.description("List every known guardian set with its addresses and expiration time")
+        .tag("wormhole-observer")
+        .response::<200, Json<GuardianSetsResponse>>()
+}
+
+fn get_guardian_set_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Get the guardian set registered at a given index")
+        .tag("wormhole-observer")
+        .response::<200, Json<GuardianSet>>()
+        .response::<404, AppError>()
+}
+
+fn get_guardians_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Last heartbeat of every guardian node, with its version, boot time, features and \
+            per-chain heights. Flags stale heartbeats, nodes lagging behind the median height of a chain \
+            and nodes not running the majority version.")
+        .tag("wormhole-observer")
+        .response::<200, Json<HeartbeatSnapshot>>()
+        .response::<503, AppError>()
+}
+
+fn get_guardians_history_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Summaries of the previous heartbeat polls, oldest first, to tell which guardians \
+            were lagging on a chain when a VAA went missing")
+        .tag("wormhole-observer")
+        .response::<200, Json<Vec<HeartbeatSummary>>>()
+}
@@ -0,0 +1,374 @@
+// Guardian heartbeats, polled from the guardians' public RPC
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use schemars::JsonSchema;
+use tokio::sync::RwLock;
+
+use crate::library::config::get_config;
+use crate::domain::wormhole::chains;
+use crate::domain::wormhole::grpc::Heartbeat;
+use super::registry::GuardianSet;
+
+/// Heartbeat summaries kept, a day's worth at the default poll interval
+pub const MAX_HEARTBEAT_SNAPSHOTS: usize = 1_440;
+
+/// A chain watched by a guardian node, as reported in its heartbeat.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct GuardianNetwork {
+    pub chain_id: u16,
+    pub chain_name: Option<String>,
+    pub height: i64,
+    pub safe_height: i64,
+    pub finalized_height: i64,
+    /// Connection errors of the node's watcher
+    pub error_count: u64,
+    pub contract_address: String,
+}
+
+/// The last heartbeat of a guardian node. Only the guardian address is
+/// verified, the rest is as reported by the node.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct GuardianHeartbeat {
+    /// Hex-encoded (leading 0x) address of the guardian that signed the heartbeat
+    pub guardian_addr: String,
+    /// libp2p address of the node, a guardian may run several
+    pub p2p_node_addr: String,
+    pub node_name: String,
+    pub version: String,
+    /// Heartbeats sent since the node booted
+    pub counter: i64,
+    #[schemars(with = "String")]
+    pub timestamp: DateTime<Utc>,
+    #[schemars(with = "String")]
+    pub boot_time: DateTime<Utc>,
+    pub features: Vec<String>,
+    pub networks: Vec<GuardianNetwork>,
+}
+
+impl GuardianHeartbeat {
+    pub fn from_raw(guardian_addr: String, p2p_node_addr: String, heartbeat: Heartbeat) -> Self {
+        Self {
+            guardian_addr: guardian_addr.to_lowercase(),
+            p2p_node_addr,
+            node_name: heartbeat.node_name,
+            version: heartbeat.version,
+            counter: heartbeat.counter,
+            timestamp: unix_time(heartbeat.timestamp),
+            boot_time: unix_time(heartbeat.boot_timestamp),
+            features: heartbeat.features,
+            networks: heartbeat.networks
+                .into_iter()
+                .filter_map(|network| {
+                    let chain_id = u16::try_from(network.id).ok()?;
+                    Some(GuardianNetwork {
+                        chain_id,
+                        chain_name: chains::name(chain_id),
+                        height: network.height,
+                        safe_height: network.safe_height,
+                        finalized_height: network.finalized_height,
+                        error_count: network.error_count,
+                        contract_address: network.contract_address,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+// Guardian nodes report UNIX times in nanoseconds, older ones in seconds
+fn unix_time(value: i64) -> DateTime<Utc> {
+    if value > 1_000_000_000_000 {
+        DateTime::from_timestamp_nanos(value)
+    } else {
+        DateTime::from_timestamp(value, 0).unwrap_or_default()
+    }
+}
+
+/// A guardian node running behind the others on a chain.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ChainLag {
+    pub guardian_addr: String,
+    pub node_name: String,
+    pub chain_id: u16,
+    pub chain_name: Option<String>,
+    pub height: i64,
+    /// Median height reported for the chain across guardians
+    pub median_height: i64,
+    pub blocks_behind: i64,
+}
+
+/// A guardian node's heartbeat along with what stands out in it.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GuardianStatus {
+    /// Position of the guardian in the current guardian set
+    pub guardian_index: Option<usize>,
+    pub heartbeat: GuardianHeartbeat,
+    /// No heartbeat for longer than `HEARTBEAT_STALE_AFTER` seconds
+    pub stale: bool,
+    /// Runs another version than most guardians
+    pub version_drift: bool,
+    /// Chains the node lags on by more than `HEARTBEAT_MAX_LAG` blocks
+    pub lagging_chains: Vec<u16>,
+}
+
+/// What stood out in the heartbeats at some point, kept as history.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct HeartbeatSummary {
+    #[schemars(with = "String")]
+    pub taken_at: DateTime<Utc>,
+    /// Guardian nodes that sent a heartbeat
+    pub nodes: usize,
+    /// Version run by most nodes
+    pub majority_version: Option<String>,
+    /// Number of nodes per version
+    pub versions: BTreeMap<String, usize>,
+    /// Addresses of the guardians whose heartbeat is stale
+    pub stale: Vec<String>,
+    /// Addresses of the guardians running another version than the majority
+    pub version_drift: Vec<String>,
+    pub lagging: Vec<ChainLag>,
+    /// Guardians of the current set that sent no heartbeat
+    pub missing_guardians: Vec<String>,
+}
+
+/// Every guardian node's last heartbeat, as polled from a guardian.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct HeartbeatSnapshot {
+    /// Guardian public RPC endpoint the heartbeats were polled from
+    pub rpc_addr: String,
+    pub summary: HeartbeatSummary,
+    pub guardians: Vec<GuardianStatus>,
+}
+
+/// Thresholds past which heartbeats are flagged.
+#[derive(Debug, Clone, Copy)]
+pub struct HeartbeatThresholds {
+    pub stale_after: Duration,
+    pub max_lag: i64,
+}
+
+impl HeartbeatThresholds {
+    pub fn from_config() -> Self {
+        let config = get_config();
+        Self {
+            stale_after: Duration::from_secs(config.heartbeat_stale_after),
+            max_lag: config.heartbeat_max_lag,
+        }
+    }
+}
+
+/// Flags the stale heartbeats, the nodes lagging behind the median height of a
+/// chain and those not running the majority version. Nodes that report a
+/// chain at height 0 don't watch it and are left out of its median.
+pub fn analyze_heartbeats(
+    rpc_addr: String,
+    heartbeats: Vec<GuardianHeartbeat>,
+    guardian_set: Option<&GuardianSet>,
+    thresholds: HeartbeatThresholds,
+    now: DateTime<Utc>,
+) -> HeartbeatSnapshot {
+    let mut versions: BTreeMap<String, usize> = BTreeMap::new();
+    let mut heights: BTreeMap<u16, Vec<i64>> = BTreeMap::new();
+    for heartbeat in &heartbeats {
+        *versions.entry(heartbeat.version.clone()).or_default() += 1;
+        for network in heartbeat.networks.iter().filter(|network| network.height > 0) {
+            heights.entry(network.chain_id).or_default().push(network.height);
+        }
+    }
+    // Ties go to the highest version string
+    let majority_version = versions
+        .iter()
+        .max_by_key(|(version, count)| (**count, (*version).clone()))
+        .map(|(version, _)| version.clone());
+    let medians: BTreeMap<u16, i64> = heights
+        .into_iter()
+        .map(|(chain_id, mut heights)| {
+            heights.sort_unstable();
+            (chain_id, heights[heights.len() / 2])
+        })
+        .collect();
+
+    let stale_after = chrono::Duration::from_std(thresholds.stale_after).unwrap_or(chrono::Duration::MAX);
+    let mut summary = HeartbeatSummary {
+        taken_at: now,
+        nodes: heartbeats.len(),
+        majority_version: majority_version.clone(),
+        versions,
+        stale: Vec::new(),
+        version_drift: Vec::new(),
+        lagging: Vec::new(),
+        missing_guardians: Vec::new(),
+    };
+    let mut guardians = Vec::new();
+
+    for heartbeat in heartbeats {
+        let stale = now - heartbeat.timestamp > stale_after;
+        let version_drift = majority_version.as_ref().is_some_and(|version| &heartbeat.version != version);
+        let mut lagging_chains = Vec::new();
+        for network in heartbeat.networks.iter().filter(|network| network.height > 0) {
+            let Some(median_height) = medians.get(&network.chain_id).copied() else {
+                continue;
+            };
+            let blocks_behind = median_height - network.height;
+            if blocks_behind > thresholds.max_lag {
+                lagging_chains.push(network.chain_id);
+                summary.lagging.push(ChainLag {
+                    guardian_addr: heartbeat.guardian_addr.clone(),
+                    node_name: heartbeat.node_name.clone(),
+                    chain_id: network.chain_id,
+                    chain_name: network.chain_name.clone(),
+                    height: network.height,
+                    median_height,
+                    blocks_behind,
+                });
+            }
+        }
+        if stale {
+            summary.stale.push(heartbeat.guardian_addr.clone());
+        }
+        if version_drift {
+            summary.version_drift.push(heartbeat.guardian_addr.clone());
+        }
+        guardians.push(GuardianStatus {
+            guardian_index: guardian_set.and_then(|set| set.addresses.iter().position(|addr| addr == &heartbeat.guardian_addr)),
+            heartbeat,
+            stale,
+            version_drift,
+            lagging_chains,
+        });
+    }
+
+    if let Some(guardian_set) = guardian_set {
+        summary.missing_guardians = guardian_set.addresses
+            .iter()
+            .filter(|addr| !guardians.iter().any(|guardian| &guardian.heartbeat.guardian_addr == *addr))
+            .cloned()
+            .collect();
+    }
+    guardians.sort_by(|a, b| {
+        (a.guardian_index, &a.heartbeat.guardian_addr).cmp(&(b.guardian_index, &b.heartbeat.guardian_addr))
+    });
+    HeartbeatSnapshot { rpc_addr, summary, guardians }
+}
+
+/// The latest heartbeat snapshot, and the summaries of the previous ones.
+#[derive(Debug, Default)]
+pub struct Heartbeats {
+    latest: RwLock<Option<HeartbeatSnapshot>>,
+    history: RwLock<VecDeque<HeartbeatSummary>>,
+}
+
+impl Heartbeats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn record(&self, snapshot: HeartbeatSnapshot) {
+        let mut history = self.history.write().await;
+        history.push_back(snapshot.summary.clone());
+        if history.len() > MAX_HEARTBEAT_SNAPSHOTS {
+            history.pop_front();
+        }
+        *self.latest.write().await = Some(snapshot);
+    }
+
+    pub async fn latest(&self) -> Option<HeartbeatSnapshot> {
+        self.latest.read().await.clone()
+    }
+
+    /// Summaries taken within `from..to`, oldest first and at most `limit`
+    /// starting from the newest. With `chain_id`, only lags on that chain are kept.
+    pub async fn history(
+        &self,
+        chain_id: Option<u16>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Vec<HeartbeatSummary> {
+        let history = self.history.read().await;
+        let mut summaries: Vec<HeartbeatSummary> = history
+            .iter()
+            .rev()
+            .filter(|summary| from.is_none_or(|from| summary.taken_at >= from) && to.is_none_or(|to| summary.taken_at < to))
+            .take(limit)
+            .cloned()
+            .collect();
+        summaries.reverse();
+        if let Some(chain_id) = chain_id {
+            for summary in &mut summaries {
+                summary.lagging.retain(|lag| lag.chain_id == chain_id);
+            }
+        }
+        summaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::registry::GuardianSetSource;
+
+    fn heartbeat(guardian: u8, version: &str, age_secs: i64, heights: &[(u16, i64)]) -> GuardianHeartbeat {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        GuardianHeartbeat {
+            guardian_addr: format!("0x{}", hex::encode([guardian; 20])),
+            p2p_node_addr: String::new(),
+            node_name: format!("guardian-{}", guardian),
+            version: version.to_string(),
+            counter: 1,
+            timestamp: now - chrono::Duration::seconds(age_secs),
+            boot_time: now - chrono::Duration::days(1),
+            features: Vec::new(),
+            networks: heights
+                .iter()
+                .map(|(chain_id, height)| GuardianNetwork {
+                    chain_id: *chain_id,
+                    chain_name: chains::name(*chain_id),
+                    height: *height,
+                    safe_height: 0,
+                    finalized_height: 0,
+                    error_count: 0,
+                    contract_address: String::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_analyze_heartbeats() {
+        let guardian_set = GuardianSet::new(
+            4,
+            (1..=4).map(|guardian| format!("0x{}", hex::encode([guardian; 20]))).collect(),
+            GuardianSetSource::Bundled,
+        );
+        let heartbeats = vec![
+            heartbeat(1, "v2.24.0", 10, &[(2, 1_000), (30, 500)]),
+            heartbeat(2, "v2.24.0", 10, &[(2, 1_001), (30, 0)]),
+            heartbeat(3, "v2.23.0", 600, &[(2, 700), (30, 501)]),
+        ];
+        let thresholds = HeartbeatThresholds { stale_after: Duration::from_secs(120), max_lag: 100 };
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let snapshot = analyze_heartbeats("guardian:7070".to_string(), heartbeats, Some(&guardian_set), thresholds, now);
+        let summary = &snapshot.summary;
+
+        let guardian = |index: u8| format!("0x{}", hex::encode([index; 20]));
+        assert_eq!(summary.majority_version.as_deref(), Some("v2.24.0"));
+        assert_eq!(summary.stale, vec![guardian(3)]);
+        assert_eq!(summary.version_drift, vec![guardian(3)]);
+        // Guardian 2 doesn't watch chain 30, which doesn't count as lagging
+        let lags: Vec<(String, u16, i64)> = summary.lagging.iter().map(|lag| (lag.guardian_addr.clone(), lag.chain_id, lag.blocks_behind)).collect();
+        assert_eq!(lags, vec![(guardian(3), 2, 300)]);
+        assert_eq!(summary.missing_guardians, vec![guardian(4)]);
+        assert_eq!(snapshot.guardians[2].guardian_index, Some(2));
+        assert_eq!(snapshot.guardians[2].lagging_chains, vec![2]);
+    }
+
+    #[test]
+    fn test_unix_time_accepts_nanoseconds() {
+        assert_eq!(unix_time(1_700_000_000).timestamp(), 1_700_000_000);
+        assert_eq!(unix_time(1_700_000_000_123_456_789).timestamp(), 1_700_000_000);
+    }
+}
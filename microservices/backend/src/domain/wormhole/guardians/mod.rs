@@ -0,0 +1,11 @@
+pub mod governance;
+pub mod handlers;
+pub mod heartbeats;
+pub mod registry;
+pub mod sync;
+
+// Re-export scoped routes
+pub use handlers::guardian_routes;
+
+pub use heartbeats::Heartbeats;
+pub use registry::{GuardianSet, GuardianSetRegistry, GuardianSetSource};
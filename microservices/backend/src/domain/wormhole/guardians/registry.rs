@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use tracing::{info, warn};
+
+use crate::library::errors::Error;
+
+/// Replaced guardian sets keep signing valid VAAs for 24h, as in the Core Bridge contracts.
+pub const GUARDIAN_SET_EXPIRY_SECS: u32 = 86_400;
+
+// Known guardian sets per network, used to seed the registry
+const BUNDLED_GUARDIAN_SETS: &str = include_str!("guardian_sets.json");
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardianSetSource {
+    Bundled,
+    Governance,
+    PublicRpc,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct GuardianSet {
+    pub index: u32,
+    /// Hex-encoded (leading 0x) addresses, ordered by guardian index
+    pub addresses: Vec<String>,
+    /// Unix time after which the set stops being valid, once it has been replaced
+    pub expiration_time: Option<u32>,
+    pub source: GuardianSetSource,
+}
+
+impl GuardianSet {
+    pub fn new(index: u32, addresses: Vec<String>, source: GuardianSetSource) -> Self {
+        Self {
+            index,
+            addresses: addresses.iter().map(|addr| addr.to_lowercase()).collect(),
+            expiration_time: None,
+            source,
+        }
+    }
+
+    /// Minimum number of valid signatures required (2/3 + 1 of the set).
+    pub fn quorum(&self) -> usize {
+        self.addresses.len() * 2 / 3 + 1
+    }
+
+    pub fn address(&self, guardian_index: u8) -> Option<[u8; 20]> {
+        self.addresses
+            .get(guardian_index as usize)
+            .and_then(|addr| parse_address(addr))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BundledGuardianSet {
+    index: u32,
+    addresses: Vec<String>,
+    expiration_time: Option<u32>,
+}
+
+/// Every guardian set known to the observer for one network, keyed by index.
+pub struct GuardianSetRegistry {
+    network: String,
+    sets: RwLock<BTreeMap<u32, GuardianSet>>,
+}
+
+impl GuardianSetRegistry {
+    pub fn new(network: &str) -> Self {
+        Self {
+            network: network.to_string(),
+            sets: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Creates a registry seeded with the bundled guardian sets of `network`.
+    pub fn bundled(network: &str) -> Result<Self, Error> {
+        let mut bundled: HashMap<String, Vec<BundledGuardianSet>> =
+            serde_json::from_str(BUNDLED_GUARDIAN_SETS)
+                .map_err(|e| Error::Parsing(format!("Invalid bundled guardian sets: {}", e)))?;
+
+        let sets = bundled
+            .remove(network)
+            .ok_or_else(|| Error::Parsing(format!("Unknown Wormhole network: {}", network)))?;
+
+        let registry = Self::new(network);
+        {
+            let mut registered = registry.sets.write().unwrap();
+            for set in sets {
+                let mut guardian_set = GuardianSet::new(set.index, set.addresses, GuardianSetSource::Bundled);
+                guardian_set.expiration_time = set.expiration_time;
+                registered.insert(set.index, guardian_set);
+            }
+        }
+
+        info!("Loaded {} bundled {} guardian sets", registry.list().len(), network);
+        Ok(registry)
+    }
+
+    pub fn network(&self) -> &str {
+        &self.network
+    }
+
+    pub fn get(&self, index: u32) -> Option<GuardianSet> {
+        self.sets.read().unwrap().get(&index).cloned()
+    }
+
+    pub fn current(&self) -> Option<GuardianSet> {
+        self.sets.read().unwrap().values().next_back().cloned()
+    }
+
+    pub fn list(&self) -> Vec<GuardianSet> {
+        self.sets.read().unwrap().values().cloned().collect()
+    }
+
+    /// Whether guardian set `index` could sign valid VAAs at unix time `timestamp`.
+    /// Replaced sets without a recorded expiration, like bundled ones replaced at
+    /// an unknown time, can't be judged and count as valid.
+    pub fn is_valid_at(&self, index: u32, timestamp: u32) -> bool {
+        let sets = self.sets.read().unwrap();
+        let Some(set) = sets.get(&index) else {
+            return false;
+        };
+        set.expiration_time.is_none_or(|expiration| timestamp < expiration)
+    }
+
+    /// Registers a guardian set observed at unix time `now`. A newer set starts the
+    /// expiration window of the previous one. Known sets are only replaced by a
+    /// governance upgrade, and other sources may only add sets above the current
+    /// one. Returns whether the registry changed.
+    pub fn insert(&self, mut set: GuardianSet, now: u32) -> bool {
+        let mut sets = self.sets.write().unwrap();
+        let previous_index = sets.keys().next_back().copied();
+        let governance = set.source == GuardianSetSource::Governance;
+
+        if let Some(existing) = sets.get(&set.index) {
+            if existing.addresses == set.addresses {
+                return false;
+            }
+            if !governance {
+                warn!(
+                    "Ignoring guardian set {} from {:?}, it differs from the {:?} one",
+                    set.index, set.source, existing.source
+                );
+                return false;
+            }
+            warn!(
+                "Guardian set {} from {:?} differs from the {:?} one, replacing it",
+                set.index, set.source, existing.source
+            );
+            set.expiration_time = existing.expiration_time;
+        } else if !governance && previous_index.is_some_and(|previous| set.index < previous) {
+            warn!("Ignoring guardian set {} from {:?}, older than the current one", set.index, set.source);
+            return false;
+        }
+
+        if let Some(previous_index) = previous_index.filter(|previous| *previous < set.index) {
+            if let Some(previous) = sets.get_mut(&previous_index) {
+                previous.expiration_time.get_or_insert(now + GUARDIAN_SET_EXPIRY_SECS);
+            }
+        }
+
+        info!("Registered guardian set {} ({} guardians) from {:?}", set.index, set.addresses.len(), set.source);
+        sets.insert(set.index, set);
+        true
+    }
+}
+
+pub fn parse_address(address: &str) -> Option<[u8; 20]> {
+    let bytes = hex::decode(address.trim_start_matches("0x")).ok()?;
+    bytes.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_newer_set_expires_previous() {
+        let registry = GuardianSetRegistry::bundled("devnet").unwrap();
+        assert_eq!(registry.current().map(|set| set.index), Some(0));
+        assert!(registry.is_valid_at(0, 1_000));
+
+        let upgrade = GuardianSet::new(
+            1,
+            vec!["0x0000000000000000000000000000000000000001".to_string()],
+            GuardianSetSource::Governance,
+        );
+        assert!(registry.insert(upgrade.clone(), 1_000));
+        // Re-observing the same set is a no-op
+        assert!(!registry.insert(upgrade, 2_000));
+
+        assert_eq!(registry.current().map(|set| set.index), Some(1));
+        assert_eq!(registry.get(0).and_then(|set| set.expiration_time), Some(1_000 + GUARDIAN_SET_EXPIRY_SECS));
+        assert!(registry.is_valid_at(0, 1_000 + GUARDIAN_SET_EXPIRY_SECS - 1));
+        assert!(!registry.is_valid_at(0, 1_000 + GUARDIAN_SET_EXPIRY_SECS));
+        assert!(registry.is_valid_at(1, u32::MAX));
+    }
+
+    #[test]
+    fn test_public_rpc_cannot_replace_known_sets() {
+        let registry = GuardianSetRegistry::bundled("devnet").unwrap();
+        let bundled = registry.get(0).unwrap();
+        let addresses = vec!["0x0000000000000000000000000000000000000001".to_string()];
+
+        let conflicting = GuardianSet::new(0, addresses.clone(), GuardianSetSource::PublicRpc);
+        assert!(!registry.insert(conflicting, 1_000));
+        assert_eq!(registry.get(0), Some(bundled));
+
+        assert!(registry.insert(GuardianSet::new(2, addresses.clone(), GuardianSetSource::PublicRpc), 1_000));
+        // Nor fill in sets older than the current one
+        assert!(!registry.insert(GuardianSet::new(1, addresses.clone(), GuardianSetSource::PublicRpc), 1_000));
+        assert!(registry.get(1).is_none());
+
+        // Governance upgrades still can
+        let upgrade = GuardianSet::new(2, vec!["0x0000000000000000000000000000000000000002".to_string()], GuardianSetSource::Governance);
+        assert!(registry.insert(upgrade.clone(), 2_000));
+        assert_eq!(registry.get(2).map(|set| set.addresses), Some(upgrade.addresses));
+    }
+
+    #[test]
+    fn test_bundled_networks() {
+        for network in ["mainnet", "testnet", "devnet"] {
+            let registry = GuardianSetRegistry::bundled(network).unwrap();
+            assert!(registry.list().iter().all(|set| set.addresses.iter().all(|addr| parse_address(addr).is_some())));
+        }
+        assert!(GuardianSetRegistry::bundled("unknown").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use tracing::{debug, info, warn};
+
+use crate::AppState;
+use crate::domain::wormhole::events::{record, ObserverEvent};
+use crate::domain::wormhole::grpc::public_rpc::PublicRpcClient;
+use super::heartbeats::{analyze_heartbeats, HeartbeatThresholds};
+
+/// Periodically asks the guardian public RPC endpoints for the current guardian
+/// set, stopping at the first endpoint that answers on each round. Changes are
+/// recorded as `GuardianSetChanged` events.
+pub fn spawn_guardian_set_sync(
+    state: Arc<AppState>,
+    rpc_addrs: Vec<String>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            for addr in &rpc_addrs {
+                let result = match PublicRpcClient::connect(addr.clone()).await {
+                    Ok(mut client) => client.get_current_guardian_set().await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(guardian_set) => {
+                        debug!("Guardian {} reports guardian set {}", addr, guardian_set.index);
+                        let registry = state.guardian_sets();
+                        if registry.insert(guardian_set.clone(), Utc::now().timestamp() as u32) {
+                            let guardian_set = registry.get(guardian_set.index).unwrap_or(guardian_set);
+                            record(&state, [ObserverEvent::GuardianSetChanged { guardian_set }]).await;
+                        }
+                        break;
+                    }
+                    Err(e) => warn!("Failed to fetch guardian set from {}: {}", addr, e),
+                }
+            }
+        }
+    })
+}
+
+/// Periodically polls every guardian node's last heartbeat from the first
+/// guardian public RPC endpoint that answers, flagging stale heartbeats,
+/// lagging chains and version drift.
+pub fn spawn_heartbeat_poller(
+    state: Arc<AppState>,
+    rpc_addrs: Vec<String>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let thresholds = HeartbeatThresholds::from_config();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            for addr in &rpc_addrs {
+                let result = match PublicRpcClient::connect(addr.clone()).await {
+                    Ok(mut client) => client.get_last_heartbeats().await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(heartbeats) => {
+                        debug!("Guardian {} reports {} heartbeats", addr, heartbeats.len());
+                        let guardian_set = state.guardian_sets().current();
+                        let snapshot = analyze_heartbeats(addr.clone(), heartbeats, guardian_set.as_ref(), thresholds, Utc::now());
+                        let summary = &snapshot.summary;
+                        if !summary.stale.is_empty() || !summary.lagging.is_empty() || !summary.version_drift.is_empty() {
+                            info!(
+                                "Guardian heartbeats: {} stale, {} chain lags, {} not on version {}",
+                                summary.stale.len(),
+                                summary.lagging.len(),
+                                summary.version_drift.len(),
+                                summary.majority_version.as_deref().unwrap_or("unknown")
+                            );
+                        }
+                        state.heartbeats().record(snapshot).await;
+                        break;
+                    }
+                    Err(e) => warn!("Failed to fetch heartbeats from {}: {}", addr, e),
+                }
+            }
+        }
+    })
+}
@@ -1,7 +1,23 @@
+pub mod backfill;
+pub mod builder;
+pub mod chains;
+pub mod events;
 pub mod models;
 pub mod rest;
 pub mod grpc;
+pub mod governor;
+pub mod guardians;
+pub mod observer;
+pub mod payloads;
+pub mod projections;
+pub mod reconcile;
+pub mod sequences;
+pub mod signatures;
+pub mod vaa;
 pub use rest::scan_routes;
 pub use grpc::spy_routes;
+pub use guardians::guardian_routes;
+pub use governor::governor_routes;
+pub use observer::observer_routes;
 pub use rest::client::RestClient;
-pub use models::{VaaRequest, VaaResponse};
\ No newline at end of file
+pub use models::{VaaRequest, VaaResponse};
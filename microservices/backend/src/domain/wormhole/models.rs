@@ -1,10 +1,16 @@
 // TODO: Scope models for each wormhole client (grpc, rest)
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 use uuid::Uuid;
 
 use crate::storage::{ReadModel, HasId};
+use crate::storage::query::{QueryFields, Queryable};
+use crate::library::errors::Error;
+use super::chains;
+use super::payloads::decode_payload;
+use super::vaa::Vaa;
 
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -32,11 +38,26 @@ pub struct VaaMetadata {
     pub total_gaps: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct SequenceGap {
     pub from: u64,
     pub to: u64,
     pub size: u64,
+    /// Sequences of the gap held by the Governor, awaiting release rather than missing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub governed: Vec<GovernedSequence>,
+}
+
+/// A sequence enqueued by the Governor, as last reported by a guardian.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct GovernedSequence {
+    pub sequence: u64,
+    /// When the guardians expect to release the VAA
+    #[schemars(schema_with = "schema_for_datetime")]
+    pub release_time: chrono::DateTime<chrono::Utc>,
+    /// Notional value of the transfer, in USD
+    pub notional_value: u64,
+    pub tx_hash: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -51,6 +72,8 @@ pub struct VaaDoc {
     pub version: u8,
     #[serde(rename = "emitterChain")]
     pub emitter_chain: u16,
+    #[serde(rename = "emitterChainName", default)]
+    pub emitter_chain_name: Option<String>,
     #[serde(rename = "emitterAddr")]
     pub emitter_addr: String,
     #[serde(rename = "emitterNativeAddr")]
@@ -68,6 +91,23 @@ pub struct VaaDoc {
     pub digest: Option<String>,
     #[serde(rename = "isDuplicated")]
     pub is_duplicated: Option<bool>,
+    /// Result of checking `digest` against the VAA bytes, unset until checked
+    #[serde(rename = "digestCheck", default, skip_serializing_if = "Option::is_none")]
+    pub digest_check: Option<DigestCheck>,
+}
+
+/// Outcome of checking the digest indexed by Wormholescan against the VAA it returned.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestCheck {
+    /// The indexed digest matches the VAA bytes
+    Verified,
+    /// No digest was indexed, the one computed from the VAA bytes was filled in
+    Computed,
+    /// The indexed digest differs from the one computed from the VAA bytes
+    Mismatch,
+    /// The VAA bytes could not be decoded, so the digest was not checked
+    Unparseable,
 }
 
 impl HasId for VaaRequest {
@@ -100,9 +140,59 @@ impl ReadModel for VaaResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaaRecord {
     pub id: Uuid,
-    pub hash: String,
+    /// keccak256(keccak256(body)), hex-encoded
+    pub digest: String,
+    /// Canonical `chain/emitter/sequence` message ID
+    pub message_id: String,
+    pub emitter_chain: u16,
+    /// Hex-encoded 32-byte emitter address, without leading 0x
+    pub emitter_address: String,
+    pub sequence: u64,
+    pub guardian_set_index: u32,
+    pub consistency_level: u8,
+    pub signature_count: u8,
+    /// `type` of the decoded payload, e.g. `token_transfer`, unset when no decoder recognizes it
+    pub payload_type: Option<String>,
+    /// Timestamp of the VAA body, when the guardians observed the message
+    pub vaa_timestamp: DateTime<Utc>,
+    /// When the observer received the VAA
+    pub observed_at: DateTime<Utc>,
     pub bytes: Vec<u8>,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl VaaRecord {
+    /// Record of a VAA received now, `network` telling which emitters the
+    /// payload decoders know.
+    pub fn from_bytes(bytes: Vec<u8>, network: &str) -> Result<Self, Error> {
+        let vaa = Vaa::parse(&bytes)?;
+        Ok(Self::from_vaa(&vaa, bytes, network))
+    }
+
+    /// `from_bytes` for a VAA already parsed from `bytes`.
+    pub fn from_vaa(vaa: &Vaa, bytes: Vec<u8>, network: &str) -> Self {
+        let digest = hex::encode(vaa.digest());
+        Self {
+            id: Self::id_for_digest(&digest),
+            digest,
+            message_id: vaa.message_id().to_string(),
+            emitter_chain: vaa.emitter_chain,
+            emitter_address: hex::encode(vaa.emitter_address),
+            sequence: vaa.sequence,
+            guardian_set_index: vaa.guardian_set_index,
+            consistency_level: vaa.consistency_level,
+            signature_count: vaa.signatures.len() as u8,
+            payload_type: decode_payload(network, vaa.emitter_chain, &vaa.emitter_address, &vaa.payload)
+                .map(|decoded| decoded.payload.kind().to_string()),
+            vaa_timestamp: DateTime::from_timestamp(i64::from(vaa.timestamp), 0).unwrap_or_default(),
+            observed_at: Utc::now(),
+            bytes,
+        }
+    }
+
+    /// Records are keyed by their digest, so the same VAA is only stored once
+    pub fn id_for_digest(digest: &str) -> Uuid {
+        Uuid::new_v5(&Uuid::NAMESPACE_OID, digest.to_lowercase().as_bytes())
+    }
 }
 
 impl HasId for VaaRecord {
@@ -111,13 +201,41 @@ impl HasId for VaaRecord {
     }
 }
 
+impl Queryable for VaaRecord {
+    fn query_fields(&self) -> Option<QueryFields> {
+        Some(QueryFields {
+            emitter_chain: self.emitter_chain,
+            emitter_address: self.emitter_address.clone(),
+            sequence: self.sequence,
+            timestamp: self.vaa_timestamp,
+            guardian_set_index: self.guardian_set_index,
+            observed_at: self.observed_at,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct VaaRecordView {
     pub id: Uuid,
-    pub hash: String,
-    pub base64_bytes: String,
+    pub digest: String,
+    pub message_id: String,
+    pub emitter_chain: u16,
+    pub emitter_chain_name: Option<String>,
+    /// Hex-encoded 32-byte emitter address, without leading 0x
+    pub emitter_address: String,
+    pub sequence: u64,
+    pub guardian_set_index: u32,
+    pub consistency_level: u8,
+    pub signature_count: u8,
+    /// `type` of the decoded payload, e.g. `token_transfer`, unset when no decoder recognizes it
+    pub payload_type: Option<String>,
+    /// Timestamp of the VAA body, when the guardians observed the message
+    #[schemars(schema_with = "schema_for_datetime")]
+    pub vaa_timestamp: DateTime<Utc>,
+    /// When the observer received the VAA
     #[schemars(schema_with = "schema_for_datetime")]
-    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub observed_at: DateTime<Utc>,
+    pub base64_bytes: String,
 }
 
 impl ReadModel for VaaRecordView {
@@ -126,9 +244,19 @@ impl ReadModel for VaaRecordView {
     fn from_write_model(model: &Self::WriteModel) -> Self {
         Self {
             id: model.id,
-            hash: model.hash.clone(),
+            digest: model.digest.clone(),
+            message_id: model.message_id.clone(),
+            emitter_chain: model.emitter_chain,
+            emitter_chain_name: chains::name(model.emitter_chain),
+            emitter_address: model.emitter_address.clone(),
+            sequence: model.sequence,
+            guardian_set_index: model.guardian_set_index,
+            consistency_level: model.consistency_level,
+            signature_count: model.signature_count,
+            payload_type: model.payload_type.clone(),
+            vaa_timestamp: model.vaa_timestamp,
+            observed_at: model.observed_at,
             base64_bytes: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &model.bytes),
-            timestamp: model.timestamp,
         }
     }
 }
@@ -0,0 +1,455 @@
+use std::sync::Arc;
+use aide::{
+    axum::{routing::{get_with, post_with}, ApiRouter, IntoApiResponse},
+    transform::TransformOperation,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::AppState;
+use crate::library::config::get_config;
+use crate::library::errors::{AppError, Error};
+use crate::storage::events::{EventQuery, StoredEvent};
+use crate::storage::query::{Direction, Page, QueryOrder, VaaQuery, MAX_QUERY_LIMIT};
+use crate::storage::redis::LatestSequence;
+use crate::storage::RepositoryImpl;
+use crate::domain::wormhole::backfill::{start_backfill, BackfillStatus};
+use crate::domain::wormhole::chains::{self, ChainInfo};
+use crate::domain::wormhole::events::ObserverEvent;
+use crate::domain::wormhole::models::{VaaRecord, VaaRecordView};
+use crate::domain::wormhole::projections::{rebuild_vaa_records, EmitterStats};
+use crate::domain::wormhole::reconcile::{reconcile, ReconcileReport};
+use crate::domain::wormhole::rest::RestClient;
+use crate::domain::wormhole::vaa::MessageId;
+
+pub fn observer_routes(state: Arc<AppState>) -> ApiRouter {
+    ApiRouter::new()
+        .api_route(
+            "/observer/vaas",
+            get_with(query_vaas, query_vaas_docs),
+        )
+        .api_route(
+            "/observer/vaas/{digest}",
+            get_with(get_vaa_by_digest, get_vaa_by_digest_docs),
+        )
+        .api_route(
+            "/observer/vaas/{chain_id}/{emitter}/{sequence}",
+            get_with(get_vaa_by_message_id, get_vaa_by_message_id_docs),
+        )
+        .api_route(
+            "/observer/reconcile/{chain_id}/{emitter}",
+            get_with(reconcile_emitter, reconcile_emitter_docs),
+        )
+        .api_route(
+            "/observer/backfill",
+            post_with(start_backfill_handler, start_backfill_docs)
+                .get_with(get_backfill, get_backfill_docs),
+        )
+        .api_route(
+            "/observer/chains",
+            get_with(get_chains, get_chains_docs),
+        )
+        .api_route(
+            "/observer/events",
+            get_with(get_events, get_events_docs),
+        )
+        .api_route(
+            "/observer/emitters",
+            get_with(get_emitters, get_emitters_docs),
+        )
+        .api_route(
+            "/observer/emitters/latest",
+            get_with(get_latest_sequences, get_latest_sequences_docs),
+        )
+        .api_route(
+            "/observer/projections/rebuild",
+            post_with(rebuild_projections, rebuild_projections_docs),
+        )
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct VaaQueryParams {
+    /// Only VAAs emitted from this chain
+    chain_id: Option<u16>,
+    /// Only VAAs from this hex-encoded 32-byte emitter address
+    emitter: Option<String>,
+    /// Lowest sequence, inclusive
+    sequence_from: Option<u64>,
+    /// Highest sequence, inclusive
+    sequence_to: Option<u64>,
+    /// Earliest VAA timestamp (RFC 3339), inclusive
+    #[schemars(with = "Option<String>")]
+    from: Option<DateTime<Utc>>,
+    /// Latest VAA timestamp (RFC 3339), exclusive
+    #[schemars(with = "Option<String>")]
+    to: Option<DateTime<Utc>>,
+    guardian_set_index: Option<u32>,
+    /// `observed_at` (default), `sequence` or `timestamp`
+    order: Option<QueryOrder>,
+    /// `asc` (default) or `desc`
+    direction: Option<Direction>,
+    /// Page size, 100 by default and at most 1000
+    limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+}
+
+impl VaaQueryParams {
+    fn into_query(self) -> Result<VaaQuery, Error> {
+        if let Some(chain_id) = self.chain_id {
+            chains::get(chain_id).ok_or_else(|| Error::Request(format!("Unknown chain ID {}", chain_id)))?;
+        }
+        let emitter_address = self.emitter
+            .map(|emitter| {
+                let emitter = emitter.trim_start_matches("0x").to_lowercase();
+                match hex::decode(&emitter) {
+                    Ok(bytes) if bytes.len() == 32 => Ok(emitter),
+                    _ => Err(Error::Request(format!("Invalid emitter address {}: expected 32 hex-encoded bytes", emitter))),
+                }
+            })
+            .transpose()?;
+
+        Ok(VaaQuery {
+            emitter_chain: self.chain_id,
+            emitter_address,
+            sequence_from: self.sequence_from,
+            sequence_to: self.sequence_to,
+            time_from: self.from,
+            time_to: self.to,
+            guardian_set_index: self.guardian_set_index,
+            order: self.order.unwrap_or_default(),
+            direction: self.direction.unwrap_or_default(),
+            limit: self.limit,
+            cursor: self.cursor,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct EventParams {
+    /// Only events recorded after this position
+    after: Option<i64>,
+    /// Only events of this stream: a VAA digest, `chain/emitter` or `guardian_set/index`
+    stream_id: Option<String>,
+    /// At most this many events, 100 by default and at most 1000
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct RebuildResponse {
+    /// Position of the last event replayed
+    position: i64,
+    /// VAA records missing from storage that were restored from the log
+    restored_vaas: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DigestParams {
+    /// Hex-encoded VAA digest, as returned by Wormholescan
+    digest: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct EmitterParams {
+    chain_id: u16,
+    emitter: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ReconcileParams {
+    /// First sequence to reconcile, inclusive
+    sequence_from: Option<u64>,
+    /// Last sequence to reconcile, inclusive. At most 1000 sequences are reconciled at once
+    sequence_to: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct MessageIdParams {
+    chain_id: u16,
+    emitter: String,
+    sequence: u64,
+}
+
+async fn query_vaas(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<VaaQueryParams>,
+) -> impl IntoApiResponse {
+    let query = match params.into_query() {
+        Ok(query) => query,
+        Err(e) => return AppError::new(&e.to_string()).into_response(),
+    };
+
+    match state.vaas_repository().query(&query).await {
+        Ok(page) => Json(page).into_response(),
+        Err(e @ Error::Request(_)) => AppError::new(&e.to_string()).into_response(),
+        Err(e) => AppError::new(&e.to_string())
+            .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+            .into_response(),
+    }
+}
+
+async fn get_vaa_by_digest(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<DigestParams>,
+) -> impl IntoApiResponse {
+    let digest = params.digest.trim_start_matches("0x");
+    let id = VaaRecord::id_for_digest(digest);
+    // Recent VAAs are served from the cache when there is one
+    let cached = match state.cache() {
+        Some(cache) => cache.get(id).await,
+        None => None,
+    };
+    let vaa = match cached {
+        Some(vaa) => Some(vaa),
+        None => state.vaas_repository().get(id).await,
+    };
+    match vaa {
+        Some(vaa) => Json(vaa).into_response(),
+        None => AppError::new(&format!("No VAA stored with digest {}", digest))
+            .with_status(StatusCode::NOT_FOUND)
+            .into_response(),
+    }
+}
+
+async fn get_vaa_by_message_id(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<MessageIdParams>,
+) -> impl IntoApiResponse {
+    if let Err(e) = chains::resolve(params.chain_id, state.guardian_sets().network()) {
+        return AppError::new(&e.to_string()).into_response();
+    }
+
+    let emitter_address = params.emitter.trim_start_matches("0x").to_lowercase();
+    let message_id = MessageId {
+        emitter_chain: params.chain_id,
+        emitter_address: emitter_address.clone(),
+        sequence: params.sequence,
+    }
+    .to_string();
+
+    // Distinct digests under the same message ID are all returned
+    let query = VaaQuery {
+        emitter_chain: Some(params.chain_id),
+        emitter_address: Some(emitter_address),
+        sequence_from: Some(params.sequence),
+        sequence_to: Some(params.sequence),
+        limit: Some(MAX_QUERY_LIMIT),
+        ..Default::default()
+    };
+    let vaas = match state.vaas_repository().query(&query).await {
+        Ok(page) => page.items,
+        Err(e) => {
+            return AppError::new(&e.to_string())
+                .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+                .into_response()
+        }
+    };
+
+    if vaas.is_empty() {
+        return AppError::new(&format!("No VAA stored with message ID {}", message_id))
+            .with_status(StatusCode::NOT_FOUND)
+            .into_response();
+    }
+    Json(vaas).into_response()
+}
+
+async fn reconcile_emitter(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<EmitterParams>,
+    Query(range): Query<ReconcileParams>,
+) -> impl IntoApiResponse {
+    if let Err(e) = chains::resolve(params.chain_id, state.guardian_sets().network()) {
+        return AppError::new(&e.to_string()).into_response();
+    }
+    let emitter_address = params.emitter.trim_start_matches("0x").to_lowercase();
+    if !hex::decode(&emitter_address).is_ok_and(|bytes| bytes.len() == 32) {
+        return AppError::new(&format!("Invalid emitter address {}: expected 32 hex-encoded bytes", emitter_address))
+            .into_response();
+    }
+
+    let client = RestClient::new();
+    match reconcile(&state, &client, params.chain_id, &emitter_address, range.sequence_from, range.sequence_to).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e @ Error::Request(_)) => AppError::new(&e.to_string()).into_response(),
+        Err(e @ (Error::External(_) | Error::Parsing(_))) => AppError::new(&e.to_string())
+            .with_status(StatusCode::BAD_GATEWAY)
+            .into_response(),
+        Err(e) => AppError::new(&e.to_string())
+            .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+            .into_response(),
+    }
+}
+
+async fn start_backfill_handler(State(state): State<Arc<AppState>>) -> impl IntoApiResponse {
+    let rpc_addrs = get_config().wormhole_guardian_rpc_addrs.clone();
+    if rpc_addrs.is_empty() {
+        return AppError::new("No guardian public RPC endpoint configured, set WORMHOLE_GUARDIAN_RPC_ADDRS")
+            .with_status(StatusCode::SERVICE_UNAVAILABLE)
+            .into_response();
+    }
+    match start_backfill(state.clone(), rpc_addrs).await {
+        Ok(status) => (StatusCode::ACCEPTED, Json(status)).into_response(),
+        Err(e @ Error::Request(_)) => AppError::new(&e.to_string())
+            .with_status(StatusCode::CONFLICT)
+            .into_response(),
+        Err(e) => AppError::new(&e.to_string())
+            .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+            .into_response(),
+    }
+}
+
+async fn get_backfill(State(state): State<Arc<AppState>>) -> impl IntoApiResponse {
+    Json(state.backfills().status().await).into_response()
+}
+
+async fn get_events(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<EventParams>,
+) -> impl IntoApiResponse {
+    let query = EventQuery {
+        after: params.after.unwrap_or(0),
+        // Digests are stored without the leading 0x
+        stream_ids: params.stream_id.map(|stream_id| stream_id.trim_start_matches("0x").to_lowercase()).into_iter().collect(),
+        limit: params.limit,
+        ..Default::default()
+    };
+    match state.events().read(&query).await {
+        Ok(events) => Json(events).into_response(),
+        Err(e) => AppError::new(&e.to_string())
+            .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+            .into_response(),
+    }
+}
+
+async fn get_emitters(State(state): State<Arc<AppState>>) -> impl IntoApiResponse {
+    Json(state.projections().emitters().await).into_response()
+}
+
+async fn get_latest_sequences(State(state): State<Arc<AppState>>) -> impl IntoApiResponse {
+    let Some(cache) = state.cache() else {
+        return AppError::new("No Redis cache configured, set REDIS_URL")
+            .with_status(StatusCode::SERVICE_UNAVAILABLE)
+            .into_response();
+    };
+    match cache.latest_sequences().await {
+        Ok(latest) => Json(latest).into_response(),
+        Err(e) => AppError::new(&e.to_string())
+            .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+            .into_response(),
+    }
+}
+
+async fn rebuild_projections(State(state): State<Arc<AppState>>) -> impl IntoApiResponse {
+    let rebuilt = async {
+        let position = state.projections().rebuild(state.events()).await?;
+        let restored_vaas = rebuild_vaa_records(state.events(), state.vaas_repository(), state.guardian_sets().network()).await?;
+        Ok::<_, Error>(RebuildResponse { position, restored_vaas })
+    };
+    match rebuilt.await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => AppError::new(&e.to_string())
+            .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+            .into_response(),
+    }
+}
+
+async fn get_chains() -> impl IntoApiResponse {
+    Json(chains::all()).into_response()
+}
+
+fn query_vaas_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Page through stored VAAs, filtered by emitter, sequence range, VAA timestamp \
+            range or guardian set. Pass the returned `next_cursor` as `cursor` to get the next page.")
+        .tag("wormhole-observer")
+        .response::<200, Json<Page<VaaRecordView>>>()
+        .response::<400, AppError>()
+        .response::<500, AppError>()
+}
+
+fn get_vaa_by_digest_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Get a stored VAA by its digest (keccak256 of keccak256(body))")
+        .tag("wormhole-observer")
+        .response::<200, Json<VaaRecordView>>()
+        .response::<404, AppError>()
+}
+
+fn get_vaa_by_message_id_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Get the stored VAAs for a chain/emitter/sequence message ID")
+        .tag("wormhole-observer")
+        .response::<200, Json<Vec<VaaRecordView>>>()
+        .response::<400, AppError>()
+        .response::<404, AppError>()
+}
+
+fn reconcile_emitter_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Compare, sequence by sequence, what the Spy delivered, what is stored and what \
+            Wormholescan indexed for an emitter. Every sequence is present everywhere, missing from \
+            Scan, missing from the Spy, missing everywhere or held with different digests. Without a \
+            range, the latest 1000 sequences are reconciled.")
+        .tag("wormhole-observer")
+        .response::<200, Json<ReconcileReport>>()
+        .response::<400, AppError>()
+        .response::<500, AppError>()
+        .response::<502, AppError>()
+}
+
+fn start_backfill_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Start a backfill round in the background: the sequences of the open gaps are \
+            requested from the guardians' public RPC (`GetSignedVAA`), up to 1000 per round. Recovered \
+            VAAs are stored and fill their gap, sequences the guardians answer NotFound for are \
+            confirmed missing. Rounds also run every `GAP_BACKFILL_INTERVAL` seconds.")
+        .tag("wormhole-observer")
+        .response::<202, Json<BackfillStatus>>()
+        .response::<409, AppError>()
+        .response::<503, AppError>()
+}
+
+fn get_backfill_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Progress of the gap backfill, with the latest outcome of every gap attempted")
+        .tag("wormhole-observer")
+        .response::<200, Json<BackfillStatus>>()
+}
+
+fn get_events_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Read the append-only event log, oldest first. The `vaa_observed` event of a \
+            digest records when the observer first saw that VAA.")
+        .tag("wormhole-observer")
+        .response::<200, Json<Vec<StoredEvent<ObserverEvent>>>>()
+        .response::<500, AppError>()
+}
+
+fn get_emitters_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Per-emitter statistics projected from the event log")
+        .tag("wormhole-observer")
+        .response::<200, Json<Vec<EmitterStats>>>()
+}
+
+fn get_latest_sequences_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Highest sequence ingested for every emitter, kept in Redis and shared by all replicas")
+        .tag("wormhole-observer")
+        .response::<200, Json<Vec<LatestSequence>>>()
+        .response::<500, AppError>()
+        .response::<503, AppError>()
+}
+
+fn rebuild_projections_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Rebuild the projections by replaying the whole event log, and restore the \
+            VAA records missing from storage")
+        .tag("wormhole-observer")
+        .response::<200, Json<RebuildResponse>>()
+        .response::<500, AppError>()
+}
+
+fn get_chains_docs(op: TransformOperation) -> TransformOperation {
+    op.description("List every Wormhole chain ID with its name, networks and address format")
+        .tag("wormhole-observer")
+        .response::<200, Json<Vec<ChainInfo>>>()
+}
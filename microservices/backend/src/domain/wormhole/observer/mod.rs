@@ -0,0 +1,4 @@
+pub mod handlers;
+
+// Re-export scoped routes
+pub use handlers::observer_routes;
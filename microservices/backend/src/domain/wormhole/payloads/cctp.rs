@@ -0,0 +1,86 @@
+// Deposits of the Wormhole CCTP integration, burning USDC through Circle's CCTP
+
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::library::errors::Error;
+use crate::domain::wormhole::chains;
+use super::{chain_label, format_units, is_known_emitter, Application, DecodedPayload, Emitter, Payload, PayloadDecoder, PayloadReader};
+
+const PAYLOAD_DEPOSIT: u8 = 1;
+const USDC_DECIMALS: u8 = 6;
+
+// Circle domains of the chains connected through the integration
+const CCTP_DOMAINS: &[(u32, u16)] = &[(0, 2), (1, 6), (2, 24), (3, 23), (5, 1), (6, 30), (7, 5)];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CctpDeposit {
+    /// USDC contract on the emitter chain
+    pub token: String,
+    /// Amount as a decimal string, USDC has 6 decimals
+    pub amount: String,
+    pub source_domain: u32,
+    pub target_domain: u32,
+    /// Wormhole chain of `target_domain`, if known
+    pub target_chain: Option<u16>,
+    pub target_chain_name: Option<String>,
+    /// Nonce of the CCTP burn message
+    pub cctp_nonce: u64,
+    pub from_address: String,
+    pub mint_recipient: String,
+    /// Hex-encoded payload handed over to `mint_recipient`
+    pub payload: String,
+}
+
+/// Decodes the payloads of the Wormhole CCTP integration emitters bundled for the network.
+pub struct CctpDecoder;
+
+impl PayloadDecoder for CctpDecoder {
+    fn name(&self) -> &'static str {
+        "CCTP"
+    }
+
+    fn matches(&self, emitter: &Emitter<'_>, _payload: &[u8]) -> bool {
+        is_known_emitter(Application::Cctp, emitter)
+    }
+
+    fn decode(&self, _emitter: &Emitter<'_>, payload: &[u8]) -> Result<DecodedPayload, Error> {
+        let mut reader = PayloadReader::new(payload, "CCTP");
+        let id = reader.u8()?;
+        if id != PAYLOAD_DEPOSIT {
+            return Err(Error::Parsing(format!("Unknown CCTP payload {}", id)));
+        }
+        let token = reader.address()?;
+        let amount = reader.uint256()?;
+        let source_domain = reader.u32()?;
+        let target_domain = reader.u32()?;
+        let target_chain = CCTP_DOMAINS
+            .iter()
+            .find(|(domain, _)| *domain == target_domain)
+            .map(|(_, chain_id)| *chain_id);
+        let deposit = CctpDeposit {
+            cctp_nonce: reader.u64()?,
+            from_address: reader.address()?,
+            mint_recipient: reader.address()?,
+            payload: hex::encode(reader.sized_bytes()?),
+            target_chain_name: target_chain.and_then(chains::name),
+            token,
+            amount,
+            source_domain,
+            target_domain,
+            target_chain,
+        };
+
+        let target = match target_chain {
+            Some(chain_id) => chain_label(chain_id),
+            None => format!("CCTP domain {}", target_domain),
+        };
+        Ok(DecodedPayload {
+            summary: format!(
+                "CCTP transfer of {} USDC to {} on {}",
+                format_units(&deposit.amount, USDC_DECIMALS), deposit.mint_recipient, target
+            ),
+            payload: Payload::CctpDeposit(deposit),
+        })
+    }
+}
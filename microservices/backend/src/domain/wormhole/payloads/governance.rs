@@ -0,0 +1,228 @@
+// Governance actions of the Core Bridge and of the Wormhole applications
+
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::library::errors::Error;
+use crate::domain::wormhole::chains;
+use crate::domain::wormhole::guardians::governance::{parse_guardian_set_upgrade, GOVERNANCE_CHAIN, GOVERNANCE_EMITTER};
+use super::{chain_label, DecodedPayload, Emitter, Payload, PayloadDecoder, PayloadReader};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct GovernanceAction {
+    /// Contract the action is addressed to, e.g. `Core` or `TokenBridge`
+    pub module: String,
+    /// Chain the action applies to, 0 for every chain
+    pub target_chain: u16,
+    pub target_chain_name: Option<String>,
+    #[serde(flatten)]
+    pub call: GovernanceCall,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum GovernanceCall {
+    ContractUpgrade {
+        new_contract: String,
+    },
+    GuardianSetUpgrade {
+        new_guardian_set_index: u32,
+        addresses: Vec<String>,
+    },
+    SetMessageFee {
+        fee: String,
+    },
+    TransferFees {
+        amount: String,
+        recipient: String,
+    },
+    /// Sets the Wormhole chain ID of an EVM chain after a fork
+    RecoverChainId {
+        evm_chain_id: String,
+        new_chain_id: u16,
+    },
+    /// Registers the application's emitter on another chain
+    RegisterChain {
+        emitter_chain: u16,
+        emitter_chain_name: Option<String>,
+        emitter_address: String,
+    },
+    UpdateDefaultDeliveryProvider {
+        delivery_provider: String,
+    },
+    UpdateWormholeFinality {
+        finality: u8,
+    },
+    RegisterEmitterAndDomain {
+        emitter_chain: u16,
+        emitter_chain_name: Option<String>,
+        emitter_address: String,
+        cctp_domain: u32,
+    },
+    /// Action without a decoder, with its hex-encoded arguments
+    Other {
+        action_id: u8,
+        data: String,
+    },
+}
+
+/// Decodes the payloads of the governance emitter.
+pub struct GovernanceDecoder;
+
+impl PayloadDecoder for GovernanceDecoder {
+    fn name(&self) -> &'static str {
+        "governance"
+    }
+
+    fn matches(&self, emitter: &Emitter<'_>, _payload: &[u8]) -> bool {
+        emitter.chain == GOVERNANCE_CHAIN && format!("0x{}", hex::encode(emitter.address)) == GOVERNANCE_EMITTER
+    }
+
+    fn decode(&self, _emitter: &Emitter<'_>, payload: &[u8]) -> Result<DecodedPayload, Error> {
+        let mut reader = PayloadReader::new(payload, "governance");
+        // Module names are left-padded with zeros to 32 bytes
+        let module = String::from_utf8_lossy(&reader.bytes32()?).trim_start_matches('\0').to_string();
+        let action = reader.u8()?;
+        let recover_chain_id = matches!((module.as_str(), action), ("Core", 5) | ("TokenBridge" | "NFTBridge", 3));
+        // Chain ID recoveries are the only actions without a target chain
+        let target_chain = if recover_chain_id { 0 } else { reader.u16()? };
+
+        let call = match (module.as_str(), action) {
+            ("Core", 1) | ("TokenBridge" | "NFTBridge" | "WormholeRelayer", 2) | ("CircleIntegration", 3) => {
+                GovernanceCall::ContractUpgrade { new_contract: reader.address()? }
+            }
+            ("Core", 2) => {
+                let upgrade = parse_guardian_set_upgrade(payload)?
+                    .ok_or_else(|| Error::Parsing("Invalid guardian set upgrade".to_string()))?;
+                GovernanceCall::GuardianSetUpgrade {
+                    new_guardian_set_index: upgrade.new_index,
+                    addresses: upgrade.addresses
+                        .iter()
+                        .map(|address| format!("0x{}", hex::encode(address)))
+                        .collect(),
+                }
+            }
+            ("Core", 3) => GovernanceCall::SetMessageFee { fee: reader.uint256()? },
+            ("Core", 4) => GovernanceCall::TransferFees {
+                amount: reader.uint256()?,
+                recipient: reader.address()?,
+            },
+            _ if recover_chain_id => GovernanceCall::RecoverChainId {
+                evm_chain_id: reader.uint256()?,
+                new_chain_id: reader.u16()?,
+            },
+            ("TokenBridge" | "NFTBridge" | "WormholeRelayer", 1) => {
+                let emitter_chain = reader.u16()?;
+                GovernanceCall::RegisterChain {
+                    emitter_chain,
+                    emitter_chain_name: chains::name(emitter_chain),
+                    emitter_address: reader.address()?,
+                }
+            }
+            ("WormholeRelayer", 3) => GovernanceCall::UpdateDefaultDeliveryProvider {
+                delivery_provider: reader.address()?,
+            },
+            ("CircleIntegration", 1) => GovernanceCall::UpdateWormholeFinality { finality: reader.u8()? },
+            ("CircleIntegration", 2) => {
+                let emitter_chain = reader.u16()?;
+                GovernanceCall::RegisterEmitterAndDomain {
+                    emitter_chain,
+                    emitter_chain_name: chains::name(emitter_chain),
+                    emitter_address: reader.address()?,
+                    cctp_domain: reader.u32()?,
+                }
+            }
+            _ => GovernanceCall::Other { action_id: action, data: hex::encode(reader.rest()) },
+        };
+
+        let target = if target_chain == 0 { "every chain".to_string() } else { chain_label(target_chain) };
+        let summary = format!("{} governance on {}: {}", module, target, describe(&call));
+        Ok(DecodedPayload {
+            summary,
+            payload: Payload::Governance(GovernanceAction {
+                module,
+                target_chain,
+                target_chain_name: chains::name(target_chain),
+                call,
+            }),
+        })
+    }
+}
+
+fn describe(call: &GovernanceCall) -> String {
+    match call {
+        GovernanceCall::ContractUpgrade { new_contract } => format!("upgrade the contract to {}", new_contract),
+        GovernanceCall::GuardianSetUpgrade { new_guardian_set_index, addresses } => {
+            format!("upgrade to guardian set {} of {} guardians", new_guardian_set_index, addresses.len())
+        }
+        GovernanceCall::SetMessageFee { fee } => format!("set the message fee to {}", fee),
+        GovernanceCall::TransferFees { amount, recipient } => format!("transfer {} of fees to {}", amount, recipient),
+        GovernanceCall::RecoverChainId { evm_chain_id, new_chain_id } => {
+            format!("recover chain ID {} for EVM chain {}", new_chain_id, evm_chain_id)
+        }
+        GovernanceCall::RegisterChain { emitter_chain, emitter_address, .. } => {
+            format!("register emitter {} of {}", emitter_address, chain_label(*emitter_chain))
+        }
+        GovernanceCall::UpdateDefaultDeliveryProvider { delivery_provider } => {
+            format!("set the default delivery provider to {}", delivery_provider)
+        }
+        GovernanceCall::UpdateWormholeFinality { finality } => format!("set the Wormhole finality to {}", finality),
+        GovernanceCall::RegisterEmitterAndDomain { emitter_chain, emitter_address, cctp_domain, .. } => format!(
+            "register emitter {} of {} with CCTP domain {}",
+            emitter_address, chain_label(*emitter_chain), cctp_domain
+        ),
+        GovernanceCall::Other { action_id, .. } => format!("action {}", action_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(name: &str) -> [u8; 32] {
+        let mut module = [0u8; 32];
+        module[32 - name.len()..].copy_from_slice(name.as_bytes());
+        module
+    }
+
+    #[test]
+    fn test_decode_governance_actions() {
+        let mut address = [0u8; 32];
+        address[31] = 4;
+        let emitter = Emitter { network: "mainnet", chain: GOVERNANCE_CHAIN, address: &address };
+        assert!(GovernanceDecoder.matches(&emitter, &[]));
+
+        // Token Bridge RegisterChain of Base's emitter, for every chain
+        let mut payload = module("TokenBridge").to_vec();
+        payload.push(1);
+        payload.extend_from_slice(&0u16.to_be_bytes());
+        payload.extend_from_slice(&30u16.to_be_bytes());
+        payload.extend_from_slice(&[0x8d; 32]);
+        let decoded = GovernanceDecoder.decode(&emitter, &payload).unwrap();
+        let Payload::Governance(action) = &decoded.payload else {
+            panic!("Expected a governance action, got {:?}", decoded.payload);
+        };
+        assert_eq!(action.module, "TokenBridge");
+        assert_eq!(action.target_chain, 0);
+        assert!(matches!(&action.call, GovernanceCall::RegisterChain { emitter_chain: 30, .. }));
+        assert!(decoded.summary.starts_with("TokenBridge governance on every chain: register emitter"));
+
+        let json = serde_json::to_value(&decoded).unwrap();
+        assert_eq!(json["type"], "governance");
+        assert_eq!(json["action"], "register_chain");
+        assert_eq!(serde_json::from_value::<DecodedPayload>(json).unwrap(), decoded);
+
+        // Core SetMessageFee on Ethereum
+        let mut payload = module("Core").to_vec();
+        payload.push(3);
+        payload.extend_from_slice(&2u16.to_be_bytes());
+        let mut fee = [0u8; 32];
+        fee[31] = 100;
+        payload.extend_from_slice(&fee);
+        let decoded = GovernanceDecoder.decode(&emitter, &payload).unwrap();
+        let Payload::Governance(action) = &decoded.payload else {
+            panic!("Expected a governance action, got {:?}", decoded.payload);
+        };
+        assert_eq!(action.call, GovernanceCall::SetMessageFee { fee: "100".to_string() });
+    }
+}
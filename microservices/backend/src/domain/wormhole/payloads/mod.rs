@@ -0,0 +1,319 @@
+// Decoders for the payloads of well-known Wormhole applications
+
+pub mod cctp;
+pub mod governance;
+pub mod ntt;
+pub mod token_bridge;
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::sync::OnceLock;
+use byteorder::{BigEndian, ReadBytesExt};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use tracing::warn;
+
+use crate::library::errors::Error;
+use super::chains;
+
+pub use cctp::{CctpDecoder, CctpDeposit};
+pub use governance::{GovernanceAction, GovernanceCall, GovernanceDecoder};
+pub use ntt::{NttDecoder, NttPeerRegistration, NttTransceiverInit, NttTransceiverMessage, NttTransfer};
+pub use token_bridge::{AssetMeta, TokenBridgeDecoder, TokenTransfer, TokenTransferWithPayload};
+
+const BUNDLED_EMITTERS: &str = include_str!("emitters.json");
+
+/// A payload recognized by one of the decoders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DecodedPayload {
+    /// What the message does, in plain words
+    pub summary: String,
+    #[serde(flatten)]
+    pub payload: Payload,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Payload {
+    /// Token Bridge payload 1
+    TokenTransfer(TokenTransfer),
+    /// Token Bridge payload 2
+    AssetMeta(AssetMeta),
+    /// Token Bridge payload 3
+    TokenTransferWithPayload(TokenTransferWithPayload),
+    /// Governance action of the Core Bridge or of a Wormhole application
+    Governance(GovernanceAction),
+    /// NTT message relayed by a Wormhole transceiver
+    NttTransceiverMessage(NttTransceiverMessage),
+    NttTransceiverInit(NttTransceiverInit),
+    NttPeerRegistration(NttPeerRegistration),
+    /// USDC burnt through CCTP by the Wormhole CCTP integration
+    CctpDeposit(CctpDeposit),
+}
+
+impl Payload {
+    /// The `type` the payload is tagged with, e.g. `token_transfer`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Payload::TokenTransfer(_) => "token_transfer",
+            Payload::AssetMeta(_) => "asset_meta",
+            Payload::TokenTransferWithPayload(_) => "token_transfer_with_payload",
+            Payload::Governance(_) => "governance",
+            Payload::NttTransceiverMessage(_) => "ntt_transceiver_message",
+            Payload::NttTransceiverInit(_) => "ntt_transceiver_init",
+            Payload::NttPeerRegistration(_) => "ntt_peer_registration",
+            Payload::CctpDeposit(_) => "cctp_deposit",
+        }
+    }
+}
+
+/// Emitter of the VAA whose payload is decoded.
+#[derive(Debug, Clone, Copy)]
+pub struct Emitter<'a> {
+    /// Wormhole network the emitter is on, known emitters differ between networks
+    pub network: &'a str,
+    pub chain: u16,
+    pub address: &'a [u8; 32],
+}
+
+/// Decodes the payloads of one Wormhole application.
+pub trait PayloadDecoder: Send + Sync {
+    /// Name of the application, for logs
+    fn name(&self) -> &'static str;
+
+    /// Whether the payload belongs to the application, going by its emitter or
+    /// by its own prefix for applications without well-known emitters
+    fn matches(&self, emitter: &Emitter<'_>, payload: &[u8]) -> bool;
+
+    fn decode(&self, emitter: &Emitter<'_>, payload: &[u8]) -> Result<DecodedPayload, Error>;
+}
+
+/// Decoders tried in turn, the first one matching a payload decoding it.
+pub struct PayloadDecoders {
+    decoders: Vec<Box<dyn PayloadDecoder>>,
+}
+
+impl PayloadDecoders {
+    pub fn empty() -> Self {
+        Self { decoders: Vec::new() }
+    }
+
+    /// Every decoder shipped with the observer
+    pub fn bundled() -> Self {
+        Self::empty()
+            .with(GovernanceDecoder)
+            .with(TokenBridgeDecoder)
+            .with(CctpDecoder)
+            .with(NttDecoder)
+    }
+
+    pub fn with(mut self, decoder: impl PayloadDecoder + 'static) -> Self {
+        self.decoders.push(Box::new(decoder));
+        self
+    }
+
+    /// `None` when no decoder recognizes the payload, or when the matching one
+    /// fails to decode it.
+    pub fn decode(&self, emitter: &Emitter<'_>, payload: &[u8]) -> Option<DecodedPayload> {
+        let decoder = self.decoders.iter().find(|decoder| decoder.matches(emitter, payload))?;
+        match decoder.decode(emitter, payload) {
+            Ok(decoded) => Some(decoded),
+            Err(e) => {
+                warn!(
+                    "Failed to decode {} payload from {}/{}: {}",
+                    decoder.name(), emitter.chain, hex::encode(emitter.address), e
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Decodes a VAA payload with the bundled decoders.
+pub fn decode_payload(
+    network: &str,
+    emitter_chain: u16,
+    emitter_address: &[u8; 32],
+    payload: &[u8],
+) -> Option<DecodedPayload> {
+    static DECODERS: OnceLock<PayloadDecoders> = OnceLock::new();
+    let emitter = Emitter { network, chain: emitter_chain, address: emitter_address };
+    DECODERS.get_or_init(PayloadDecoders::bundled).decode(&emitter, payload)
+}
+
+/// Applications whose emitters are bundled in `emitters.json`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Application {
+    TokenBridge,
+    Cctp,
+}
+
+#[derive(Debug, Deserialize)]
+struct BundledEmitters {
+    token_bridge: HashMap<u16, String>,
+    cctp: HashMap<u16, String>,
+}
+
+fn bundled_emitters() -> &'static HashMap<String, BundledEmitters> {
+    static EMITTERS: OnceLock<HashMap<String, BundledEmitters>> = OnceLock::new();
+    EMITTERS.get_or_init(|| serde_json::from_str(BUNDLED_EMITTERS).expect("Invalid bundled payload emitters"))
+}
+
+/// Whether the emitter is the bundled one of `application` on its chain.
+pub fn is_known_emitter(application: Application, emitter: &Emitter<'_>) -> bool {
+    let Some(emitters) = bundled_emitters().get(emitter.network) else {
+        return false;
+    };
+    let emitters = match application {
+        Application::TokenBridge => &emitters.token_bridge,
+        Application::Cctp => &emitters.cctp,
+    };
+    emitters
+        .get(&emitter.chain)
+        .is_some_and(|address| address.eq_ignore_ascii_case(&hex::encode(emitter.address)))
+}
+
+/// Big-endian reader over a payload, reporting truncated payloads as parsing errors.
+pub(crate) struct PayloadReader<'a> {
+    cursor: Cursor<&'a [u8]>,
+    kind: &'static str,
+}
+
+impl<'a> PayloadReader<'a> {
+    pub fn new(payload: &'a [u8], kind: &'static str) -> Self {
+        Self { cursor: Cursor::new(payload), kind }
+    }
+
+    fn error(&self, e: std::io::Error) -> Error {
+        Error::Parsing(format!("Invalid {} payload: {}", self.kind, e))
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Error> {
+        self.cursor.read_u8().map_err(|e| self.error(e))
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Error> {
+        self.cursor.read_u16::<BigEndian>().map_err(|e| self.error(e))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Error> {
+        self.cursor.read_u32::<BigEndian>().map_err(|e| self.error(e))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Error> {
+        self.cursor.read_u64::<BigEndian>().map_err(|e| self.error(e))
+    }
+
+    pub fn bytes32(&mut self) -> Result<[u8; 32], Error> {
+        let mut bytes = [0u8; 32];
+        self.cursor.read_exact(&mut bytes).map_err(|e| self.error(e))?;
+        Ok(bytes)
+    }
+
+    /// 32-byte Wormhole address, hex-encoded with a leading 0x
+    pub fn address(&mut self) -> Result<String, Error> {
+        Ok(format!("0x{}", hex::encode(self.bytes32()?)))
+    }
+
+    /// uint256 as a decimal string
+    pub fn uint256(&mut self) -> Result<String, Error> {
+        Ok(uint256_to_decimal(&self.bytes32()?))
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![0u8; len];
+        self.cursor.read_exact(&mut bytes).map_err(|e| self.error(e))?;
+        Ok(bytes)
+    }
+
+    /// Bytes prefixed with their u16 length
+    pub fn sized_bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let len = self.u16()?;
+        self.bytes(usize::from(len))
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.cursor.get_ref().len().saturating_sub(self.cursor.position() as usize)
+    }
+
+    pub fn rest(&mut self) -> Vec<u8> {
+        let mut rest = Vec::new();
+        let _ = self.cursor.read_to_end(&mut rest);
+        rest
+    }
+}
+
+/// Decimal representation of a big-endian uint256.
+pub fn uint256_to_decimal(bytes: &[u8; 32]) -> String {
+    let mut value = *bytes;
+    let mut digits = Vec::new();
+    while value.iter().any(|byte| *byte != 0) {
+        // Long division by 10, most significant byte first
+        let mut remainder = 0u16;
+        for byte in value.iter_mut() {
+            let current = (remainder << 8) | u16::from(*byte);
+            *byte = (current / 10) as u8;
+            remainder = current % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+    if digits.is_empty() {
+        return "0".to_string();
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+/// Places the decimal point of an integer amount, e.g. `1500000` with 6 decimals is `1.5`.
+pub fn format_units(amount: &str, decimals: u8) -> String {
+    let decimals = usize::from(decimals);
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let padded = format!("{:0>width$}", amount, width = decimals + 1);
+    let (units, fraction) = padded.split_at(padded.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        units.to_string()
+    } else {
+        format!("{}.{}", units, fraction)
+    }
+}
+
+/// Chain name for summaries, falling back to the chain ID.
+fn chain_label(chain_id: u16) -> String {
+    chains::name(chain_id).unwrap_or_else(|| format!("chain {}", chain_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uint256_and_units() {
+        let mut amount = [0u8; 32];
+        amount[24..].copy_from_slice(&1_500_000_000_000_000_000u64.to_be_bytes());
+        assert_eq!(uint256_to_decimal(&amount), "1500000000000000000");
+        assert_eq!(uint256_to_decimal(&[0xff; 32]), U256_MAX);
+        assert_eq!(uint256_to_decimal(&[0; 32]), "0");
+
+        assert_eq!(format_units("1500000", 6), "1.5");
+        assert_eq!(format_units("42", 8), "0.00000042");
+        assert_eq!(format_units("100000000", 8), "1");
+    }
+
+    const U256_MAX: &str = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+    #[test]
+    fn test_bundled_emitters() {
+        for network in ["mainnet", "testnet", "devnet"] {
+            assert!(bundled_emitters().contains_key(network));
+        }
+        let mut address = [0u8; 32];
+        address[12..].copy_from_slice(&hex::decode("3ee18b2214aff97000d974cf647e7c347e8fa585").unwrap());
+        let emitter = Emitter { network: "mainnet", chain: 2, address: &address };
+        assert!(is_known_emitter(Application::TokenBridge, &emitter));
+        assert!(!is_known_emitter(Application::Cctp, &emitter));
+        assert!(!is_known_emitter(Application::TokenBridge, &Emitter { network: "testnet", ..emitter }));
+    }
+}
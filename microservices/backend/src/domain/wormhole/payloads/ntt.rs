@@ -0,0 +1,222 @@
+// Native Token Transfers (NTT) messages of Wormhole transceivers
+
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::library::errors::Error;
+use crate::domain::wormhole::chains;
+use super::{chain_label, format_units, DecodedPayload, Emitter, Payload, PayloadDecoder, PayloadReader};
+
+// NTT payloads start with a 4-byte prefix instead of coming from well-known emitters
+const TRANSCEIVER_MESSAGE_PREFIX: [u8; 4] = [0x99, 0x45, 0xff, 0x10];
+const TRANSCEIVER_INIT_PREFIX: [u8; 4] = [0x9c, 0x23, 0xbd, 0x3b];
+const PEER_REGISTRATION_PREFIX: [u8; 4] = [0x18, 0xfc, 0x67, 0xc2];
+const NATIVE_TOKEN_TRANSFER_PREFIX: [u8; 4] = [0x99, 0x4e, 0x54, 0x54];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct NttTransceiverMessage {
+    pub source_ntt_manager: String,
+    pub recipient_ntt_manager: String,
+    /// Hex-encoded 32-byte ID the NTT manager gave the message
+    pub message_id: String,
+    pub sender: String,
+    /// Token transfer carried by the message, if any
+    pub transfer: Option<NttTransfer>,
+    /// Hex-encoded NTT manager payload, when it isn't a token transfer
+    pub manager_payload: Option<String>,
+    /// Hex-encoded transceiver-specific payload
+    pub transceiver_payload: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct NttTransfer {
+    /// Amount as a decimal string, in units of `decimals`
+    pub amount: String,
+    pub decimals: u8,
+    /// Address of the token on the emitter chain
+    pub source_token: String,
+    pub to: String,
+    pub to_chain: u16,
+    pub to_chain_name: Option<String>,
+    /// Hex-encoded payload handed over to `to`
+    pub additional_payload: Option<String>,
+}
+
+/// Announces a transceiver and the NTT manager it serves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct NttTransceiverInit {
+    pub ntt_manager: String,
+    /// `locking` on the token's home chain, `burning` elsewhere
+    pub manager_mode: String,
+    pub token_address: String,
+    pub token_decimals: u8,
+}
+
+/// Registers a transceiver of another chain as a peer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct NttPeerRegistration {
+    pub peer_chain: u16,
+    pub peer_chain_name: Option<String>,
+    pub peer_address: String,
+}
+
+/// Decodes the payloads of Wormhole NTT transceivers, recognized by their prefix.
+pub struct NttDecoder;
+
+impl PayloadDecoder for NttDecoder {
+    fn name(&self) -> &'static str {
+        "NTT"
+    }
+
+    fn matches(&self, _emitter: &Emitter<'_>, payload: &[u8]) -> bool {
+        payload.get(..4).is_some_and(|prefix| {
+            [TRANSCEIVER_MESSAGE_PREFIX, TRANSCEIVER_INIT_PREFIX, PEER_REGISTRATION_PREFIX]
+                .iter()
+                .any(|known| prefix == known)
+        })
+    }
+
+    fn decode(&self, _emitter: &Emitter<'_>, payload: &[u8]) -> Result<DecodedPayload, Error> {
+        let mut reader = PayloadReader::new(payload, "NTT");
+        let prefix = reader.bytes(4)?;
+        if prefix == TRANSCEIVER_INIT_PREFIX {
+            let ntt_manager = reader.address()?;
+            let manager_mode = match reader.u8()? {
+                0 => "locking".to_string(),
+                1 => "burning".to_string(),
+                mode => mode.to_string(),
+            };
+            let init = NttTransceiverInit {
+                ntt_manager,
+                manager_mode,
+                token_address: reader.address()?,
+                token_decimals: reader.u8()?,
+            };
+            return Ok(DecodedPayload {
+                summary: format!(
+                    "NTT transceiver initialized for manager {} ({} mode) of token {}",
+                    init.ntt_manager, init.manager_mode, init.token_address
+                ),
+                payload: Payload::NttTransceiverInit(init),
+            });
+        }
+        if prefix == PEER_REGISTRATION_PREFIX {
+            let peer_chain = reader.u16()?;
+            let registration = NttPeerRegistration {
+                peer_chain,
+                peer_chain_name: chains::name(peer_chain),
+                peer_address: reader.address()?,
+            };
+            return Ok(DecodedPayload {
+                summary: format!(
+                    "NTT peer transceiver {} registered for {}",
+                    registration.peer_address, chain_label(peer_chain)
+                ),
+                payload: Payload::NttPeerRegistration(registration),
+            });
+        }
+
+        let source_ntt_manager = reader.address()?;
+        let recipient_ntt_manager = reader.address()?;
+        let manager_message = reader.sized_bytes()?;
+        let transceiver_payload = hex::encode(reader.sized_bytes()?);
+
+        let mut manager = PayloadReader::new(&manager_message, "NTT manager");
+        let message_id = hex::encode(manager.bytes32()?);
+        let sender = manager.address()?;
+        let manager_payload = manager.sized_bytes()?;
+        let transfer = if manager_payload.starts_with(&NATIVE_TOKEN_TRANSFER_PREFIX) {
+            Some(decode_transfer(&manager_payload)?)
+        } else {
+            None
+        };
+
+        let summary = match &transfer {
+            Some(transfer) => format!(
+                "NTT transfer of {} of token {} to {} on {}",
+                format_units(&transfer.amount, transfer.decimals),
+                transfer.source_token,
+                transfer.to,
+                chain_label(transfer.to_chain)
+            ),
+            None => format!("NTT message from manager {} to manager {}", source_ntt_manager, recipient_ntt_manager),
+        };
+        Ok(DecodedPayload {
+            summary,
+            payload: Payload::NttTransceiverMessage(NttTransceiverMessage {
+                source_ntt_manager,
+                recipient_ntt_manager,
+                message_id,
+                sender,
+                manager_payload: transfer.is_none().then(|| hex::encode(&manager_payload)),
+                transfer,
+                transceiver_payload,
+            }),
+        })
+    }
+}
+
+fn decode_transfer(payload: &[u8]) -> Result<NttTransfer, Error> {
+    let mut reader = PayloadReader::new(payload, "NTT transfer");
+    reader.bytes(4)?;
+    let decimals = reader.u8()?;
+    let amount = reader.u64()?.to_string();
+    let source_token = reader.address()?;
+    let to = reader.address()?;
+    let to_chain = reader.u16()?;
+    // Newer managers may append a payload for the recipient
+    let additional_payload = if reader.remaining() > 0 {
+        Some(hex::encode(reader.sized_bytes()?))
+    } else {
+        None
+    };
+    Ok(NttTransfer {
+        amount,
+        decimals,
+        source_token,
+        to,
+        to_chain,
+        to_chain_name: chains::name(to_chain),
+        additional_payload,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_transfer_message() {
+        let mut transfer = NATIVE_TOKEN_TRANSFER_PREFIX.to_vec();
+        transfer.push(8);
+        transfer.extend_from_slice(&250_000_000u64.to_be_bytes());
+        transfer.extend_from_slice(&[0x11; 32]);
+        transfer.extend_from_slice(&[0x22; 32]);
+        transfer.extend_from_slice(&1u16.to_be_bytes());
+
+        let mut manager_message = vec![0x01; 32];
+        manager_message.extend_from_slice(&[0x33; 32]);
+        manager_message.extend_from_slice(&(transfer.len() as u16).to_be_bytes());
+        manager_message.extend_from_slice(&transfer);
+
+        let mut payload = TRANSCEIVER_MESSAGE_PREFIX.to_vec();
+        payload.extend_from_slice(&[0x44; 32]);
+        payload.extend_from_slice(&[0x55; 32]);
+        payload.extend_from_slice(&(manager_message.len() as u16).to_be_bytes());
+        payload.extend_from_slice(&manager_message);
+        payload.extend_from_slice(&0u16.to_be_bytes());
+
+        // Any emitter, NTT transceivers are deployed per token
+        let address = [0x66; 32];
+        let emitter = Emitter { network: "mainnet", chain: 2, address: &address };
+        assert!(NttDecoder.matches(&emitter, &payload));
+        let decoded = NttDecoder.decode(&emitter, &payload).unwrap();
+        let Payload::NttTransceiverMessage(message) = &decoded.payload else {
+            panic!("Expected an NTT message, got {:?}", decoded.payload);
+        };
+        let transfer = message.transfer.as_ref().unwrap();
+        assert_eq!((transfer.amount.as_str(), transfer.decimals, transfer.to_chain), ("250000000", 8, 1));
+        assert!(message.manager_payload.is_none());
+        assert!(decoded.summary.starts_with("NTT transfer of 2.5 of token 0x1111"));
+    }
+}
@@ -0,0 +1,224 @@
+// Token Bridge payloads: transfers and attestations
+
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::library::errors::Error;
+use crate::domain::wormhole::chains;
+use super::{chain_label, is_known_emitter, Application, DecodedPayload, Emitter, Payload, PayloadDecoder, PayloadReader};
+
+const PAYLOAD_TRANSFER: u8 = 1;
+const PAYLOAD_ASSET_META: u8 = 2;
+const PAYLOAD_TRANSFER_WITH_PAYLOAD: u8 = 3;
+
+/// Tokens are bridged with at most 8 decimals, amounts are truncated to them.
+pub const MAX_DECIMALS: u8 = 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TokenTransfer {
+    /// Amount as a decimal string, normalized to at most 8 decimals
+    pub amount: String,
+    /// Address of the token on its origin chain
+    pub token_address: String,
+    pub token_chain: u16,
+    pub token_chain_name: Option<String>,
+    pub to: String,
+    pub to_chain: u16,
+    pub to_chain_name: Option<String>,
+    /// Relayer fee, normalized like `amount`
+    pub fee: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TokenTransferWithPayload {
+    /// Amount as a decimal string, normalized to at most 8 decimals
+    pub amount: String,
+    /// Address of the token on its origin chain
+    pub token_address: String,
+    pub token_chain: u16,
+    pub token_chain_name: Option<String>,
+    /// Contract the transfer is redeemed by
+    pub to: String,
+    pub to_chain: u16,
+    pub to_chain_name: Option<String>,
+    /// Sender of the transfer on the emitter chain
+    pub from_address: String,
+    /// Hex-encoded payload handed over to `to`
+    pub payload: String,
+}
+
+/// Attestation of a token, registering it on other chains.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AssetMeta {
+    pub token_address: String,
+    pub token_chain: u16,
+    pub token_chain_name: Option<String>,
+    pub decimals: u8,
+    pub symbol: String,
+    pub name: String,
+}
+
+/// Decodes the payloads of the Token Bridge emitters bundled for the network.
+pub struct TokenBridgeDecoder;
+
+impl PayloadDecoder for TokenBridgeDecoder {
+    fn name(&self) -> &'static str {
+        "Token Bridge"
+    }
+
+    fn matches(&self, emitter: &Emitter<'_>, _payload: &[u8]) -> bool {
+        is_known_emitter(Application::TokenBridge, emitter)
+    }
+
+    fn decode(&self, _emitter: &Emitter<'_>, payload: &[u8]) -> Result<DecodedPayload, Error> {
+        let mut reader = PayloadReader::new(payload, "Token Bridge");
+        match reader.u8()? {
+            PAYLOAD_TRANSFER => {
+                let amount = reader.uint256()?;
+                let token_address = reader.address()?;
+                let token_chain = reader.u16()?;
+                let to = reader.address()?;
+                let to_chain = reader.u16()?;
+                let transfer = TokenTransfer {
+                    token_chain_name: chains::name(token_chain),
+                    to_chain_name: chains::name(to_chain),
+                    fee: reader.uint256()?,
+                    amount,
+                    token_address,
+                    token_chain,
+                    to,
+                    to_chain,
+                };
+                Ok(DecodedPayload {
+                    summary: transfer_summary(&transfer.amount, &transfer.token_address, transfer.token_chain, &transfer.to, transfer.to_chain),
+                    payload: Payload::TokenTransfer(transfer),
+                })
+            }
+            PAYLOAD_ASSET_META => {
+                let token_address = reader.address()?;
+                let token_chain = reader.u16()?;
+                let decimals = reader.u8()?;
+                let symbol = padded_string(&reader.bytes32()?);
+                let name = padded_string(&reader.bytes32()?);
+                Ok(DecodedPayload {
+                    summary: format!(
+                        "Attestation of {} ({}, {} decimals) native to {}",
+                        name, symbol, decimals, chain_label(token_chain)
+                    ),
+                    payload: Payload::AssetMeta(AssetMeta {
+                        token_address,
+                        token_chain,
+                        token_chain_name: chains::name(token_chain),
+                        decimals,
+                        symbol,
+                        name,
+                    }),
+                })
+            }
+            PAYLOAD_TRANSFER_WITH_PAYLOAD => {
+                let amount = reader.uint256()?;
+                let token_address = reader.address()?;
+                let token_chain = reader.u16()?;
+                let to = reader.address()?;
+                let to_chain = reader.u16()?;
+                let from_address = reader.address()?;
+                let transfer = TokenTransferWithPayload {
+                    token_chain_name: chains::name(token_chain),
+                    to_chain_name: chains::name(to_chain),
+                    payload: hex::encode(reader.rest()),
+                    amount,
+                    token_address,
+                    token_chain,
+                    to,
+                    to_chain,
+                    from_address,
+                };
+                Ok(DecodedPayload {
+                    summary: format!(
+                        "{}, with a payload for the recipient",
+                        transfer_summary(&transfer.amount, &transfer.token_address, transfer.token_chain, &transfer.to, transfer.to_chain)
+                    ),
+                    payload: Payload::TokenTransferWithPayload(transfer),
+                })
+            }
+            id => Err(Error::Parsing(format!("Unknown Token Bridge payload {}", id))),
+        }
+    }
+}
+
+fn transfer_summary(amount: &str, token_address: &str, token_chain: u16, to: &str, to_chain: u16) -> String {
+    format!(
+        "Transfer of {} (normalized to {} decimals) of token {} native to {}, to {} on {}",
+        amount, MAX_DECIMALS, token_address, chain_label(token_chain), to, chain_label(to_chain)
+    )
+}
+
+// Symbols and names are right-padded with zeros to 32 bytes
+fn padded_string(bytes: &[u8; 32]) -> String {
+    String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ethereum_token_bridge() -> [u8; 32] {
+        let mut address = [0u8; 32];
+        address[12..].copy_from_slice(&hex::decode("3ee18b2214aff97000d974cf647e7c347e8fa585").unwrap());
+        address
+    }
+
+    #[test]
+    fn test_decode_transfer() {
+        let mut payload = vec![PAYLOAD_TRANSFER];
+        payload.extend_from_slice(&[0u8; 24]);
+        payload.extend_from_slice(&150_000_000u64.to_be_bytes());
+        payload.extend_from_slice(&[0xaa; 32]);
+        payload.extend_from_slice(&2u16.to_be_bytes());
+        payload.extend_from_slice(&[0xbb; 32]);
+        payload.extend_from_slice(&1u16.to_be_bytes());
+        payload.extend_from_slice(&[0u8; 32]);
+
+        let address = ethereum_token_bridge();
+        let emitter = Emitter { network: "mainnet", chain: 2, address: &address };
+        let decoded = TokenBridgeDecoder.decode(&emitter, &payload).unwrap();
+        let Payload::TokenTransfer(transfer) = &decoded.payload else {
+            panic!("Expected a transfer, got {:?}", decoded.payload);
+        };
+        assert_eq!(transfer.amount, "150000000");
+        assert_eq!(transfer.token_address, format!("0x{}", "aa".repeat(32)));
+        assert_eq!(transfer.to_chain, 1);
+        assert_eq!(transfer.fee, "0");
+        assert!(decoded.summary.contains(&format!("to 0x{} on", "bb".repeat(32))));
+
+        let json = serde_json::to_value(&decoded).unwrap();
+        assert_eq!(json["type"], "token_transfer");
+        assert_eq!(decoded.payload.kind(), "token_transfer");
+        assert_eq!(json["amount"], "150000000");
+
+        // Truncated payloads are rejected
+        assert!(TokenBridgeDecoder.decode(&emitter, &payload[..100]).is_err());
+    }
+
+    #[test]
+    fn test_decode_asset_meta() {
+        let mut payload = vec![PAYLOAD_ASSET_META];
+        payload.extend_from_slice(&[0xcc; 32]);
+        payload.extend_from_slice(&2u16.to_be_bytes());
+        payload.push(18);
+        let mut symbol = [0u8; 32];
+        symbol[..4].copy_from_slice(b"WETH");
+        payload.extend_from_slice(&symbol);
+        let mut name = [0u8; 32];
+        name[..13].copy_from_slice(b"Wrapped Ether");
+        payload.extend_from_slice(&name);
+
+        let address = ethereum_token_bridge();
+        let emitter = Emitter { network: "mainnet", chain: 2, address: &address };
+        let decoded = TokenBridgeDecoder.decode(&emitter, &payload).unwrap();
+        let Payload::AssetMeta(meta) = &decoded.payload else {
+            panic!("Expected an attestation, got {:?}", decoded.payload);
+        };
+        assert_eq!((meta.symbol.as_str(), meta.name.as_str(), meta.decimals), ("WETH", "Wrapped Ether", 18));
+    }
+}
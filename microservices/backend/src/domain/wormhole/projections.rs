@@ -0,0 +1,297 @@
+// Read models projected from the event log, rebuilt by replaying it
+
+use std::collections::{BTreeMap, HashSet};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use schemars::JsonSchema;
+use tokio::sync::RwLock;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::library::errors::Error;
+use crate::storage::events::{EventStore, StoredEvent};
+use crate::storage::Repository;
+use super::chains;
+use super::events::ObserverEvent;
+use super::models::{VaaRecord, VaaRecordView};
+
+/// A read model built by applying events in log order.
+pub trait Projection {
+    fn apply(&mut self, event: &StoredEvent<ObserverEvent>);
+}
+
+/// The VAA records of the `vaas` repository, first observation time included.
+/// Backfilled VAAs count as observed when they were recovered.
+#[derive(Debug)]
+pub struct VaaRecordProjection {
+    // Network whose payload decoders type the payloads
+    network: String,
+    seen: HashSet<Uuid>,
+    records: Vec<VaaRecord>,
+}
+
+impl VaaRecordProjection {
+    pub fn new(network: &str) -> Self {
+        Self { network: network.to_string(), seen: HashSet::new(), records: Vec::new() }
+    }
+
+    /// Records in the order they were first observed
+    pub fn records(&self) -> &[VaaRecord] {
+        &self.records
+    }
+}
+
+impl Projection for VaaRecordProjection {
+    fn apply(&mut self, stored: &StoredEvent<ObserverEvent>) {
+        let (ObserverEvent::VaaObserved { digest, vaa, .. }
+        | ObserverEvent::VaaBackfilled { digest, vaa, .. }) = &stored.event else {
+            return;
+        };
+        let id = VaaRecord::id_for_digest(digest);
+        if !self.seen.insert(id) {
+            return;
+        }
+        let record = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, vaa)
+            .map_err(|e| Error::Parsing(e.to_string()))
+            .and_then(|bytes| VaaRecord::from_bytes(bytes, &self.network));
+        match record {
+            Ok(record) => self.records.push(VaaRecord { observed_at: stored.recorded_at, ..record }),
+            Err(e) => error!("Skipping undecodable VAA in event {}: {}", stored.position, e),
+        }
+    }
+}
+
+/// What the log says about a single emitter.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EmitterStats {
+    pub emitter_chain: u16,
+    pub emitter_chain_name: Option<String>,
+    pub emitter_address: String,
+    pub vaas: u64,
+    pub duplicates: u64,
+    pub lowest_sequence: Option<u64>,
+    pub highest_sequence: Option<u64>,
+    pub gaps_detected: u64,
+    /// Gaps that opened across an ingestion outage
+    pub outage_gaps_detected: u64,
+    pub gaps_filled: u64,
+    /// Sequences still missing from the detected gaps
+    pub missing_sequences: u64,
+    /// VAAs recovered from the guardians' public RPC
+    pub backfilled: u64,
+    /// Missing sequences the guardians answered NotFound for
+    pub confirmed_missing: u64,
+    #[schemars(with = "Option<String>")]
+    pub first_observed_at: Option<DateTime<Utc>>,
+    #[schemars(with = "Option<String>")]
+    pub last_observed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+pub struct EmitterStatsProjection {
+    emitters: BTreeMap<(u16, String), EmitterStats>,
+}
+
+impl EmitterStatsProjection {
+    pub fn stats(&self) -> Vec<EmitterStats> {
+        self.emitters.values().cloned().collect()
+    }
+
+    fn emitter(&mut self, emitter_chain: u16, emitter_address: &str) -> &mut EmitterStats {
+        self.emitters
+            .entry((emitter_chain, emitter_address.to_string()))
+            .or_insert_with(|| EmitterStats {
+                emitter_chain,
+                emitter_chain_name: chains::name(emitter_chain),
+                emitter_address: emitter_address.to_string(),
+                vaas: 0,
+                duplicates: 0,
+                lowest_sequence: None,
+                highest_sequence: None,
+                gaps_detected: 0,
+                outage_gaps_detected: 0,
+                gaps_filled: 0,
+                missing_sequences: 0,
+                backfilled: 0,
+                confirmed_missing: 0,
+                first_observed_at: None,
+                last_observed_at: None,
+            })
+    }
+}
+
+impl Projection for EmitterStatsProjection {
+    fn apply(&mut self, stored: &StoredEvent<ObserverEvent>) {
+        match &stored.event {
+            ObserverEvent::VaaObserved { emitter_chain, emitter_address, sequence, .. } => {
+                let stats = self.emitter(*emitter_chain, emitter_address);
+                stats.vaas += 1;
+                stats.lowest_sequence = Some(stats.lowest_sequence.map_or(*sequence, |lowest| lowest.min(*sequence)));
+                stats.highest_sequence = Some(stats.highest_sequence.map_or(*sequence, |highest| highest.max(*sequence)));
+                stats.first_observed_at.get_or_insert(stored.recorded_at);
+                stats.last_observed_at = Some(stored.recorded_at);
+            }
+            ObserverEvent::VaaBackfilled { emitter_chain, emitter_address, sequence, .. } => {
+                let stats = self.emitter(*emitter_chain, emitter_address);
+                stats.backfilled += 1;
+                stats.lowest_sequence = Some(stats.lowest_sequence.map_or(*sequence, |lowest| lowest.min(*sequence)));
+                stats.highest_sequence = Some(stats.highest_sequence.map_or(*sequence, |highest| highest.max(*sequence)));
+            }
+            ObserverEvent::GapDetected { emitter_chain, emitter_address, gap, outage } => {
+                let stats = self.emitter(*emitter_chain, emitter_address);
+                if *outage {
+                    stats.outage_gaps_detected += 1;
+                } else {
+                    stats.gaps_detected += 1;
+                }
+                stats.missing_sequences += gap.size;
+            }
+            ObserverEvent::GapFilled { emitter_chain, emitter_address, .. } => {
+                let stats = self.emitter(*emitter_chain, emitter_address);
+                stats.gaps_filled += 1;
+                stats.missing_sequences = stats.missing_sequences.saturating_sub(1);
+            }
+            ObserverEvent::GapBackfilled { emitter_chain, emitter_address, not_found, .. } => {
+                self.emitter(*emitter_chain, emitter_address).confirmed_missing += not_found.len() as u64;
+            }
+            ObserverEvent::DuplicateSeen { emitter_chain, emitter_address, .. } => {
+                self.emitter(*emitter_chain, emitter_address).duplicates += 1;
+            }
+            ObserverEvent::GuardianSetChanged { .. } | ObserverEvent::CrawlCheckpoint { .. } => {}
+        }
+    }
+}
+
+// Live projection along with the position of the last event applied to it
+#[derive(Debug, Default)]
+struct Projected<P> {
+    position: i64,
+    projection: P,
+}
+
+/// Projections kept up to date as events are recorded.
+#[derive(Debug, Default)]
+pub struct Projections {
+    emitters: RwLock<Projected<EmitterStatsProjection>>,
+}
+
+impl Projections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies recorded events, skipping those a rebuild already replayed.
+    pub async fn apply(&self, events: &[StoredEvent<ObserverEvent>]) {
+        let mut emitters = self.emitters.write().await;
+        for event in events {
+            if event.position > emitters.position {
+                emitters.projection.apply(event);
+                emitters.position = event.position;
+            }
+        }
+    }
+
+    pub async fn emitters(&self) -> Vec<EmitterStats> {
+        self.emitters.read().await.projection.stats()
+    }
+
+    /// Replays the whole log into fresh projections, e.g. after fixing a bug in
+    /// one of them. Returns the position of the last event replayed.
+    pub async fn rebuild(&self, store: &EventStore<ObserverEvent>) -> Result<i64, Error> {
+        // Held throughout, so events recorded meanwhile are applied afterwards
+        let mut emitters = self.emitters.write().await;
+        let mut rebuilt = EmitterStatsProjection::default();
+        let position = store.replay(0, |event| rebuilt.apply(event)).await?;
+        *emitters = Projected { position, projection: rebuilt };
+        info!("Rebuilt projections up to event {}", position);
+        Ok(position)
+    }
+}
+
+/// Stores the VAAs of the log missing from `repository`, returning how many were.
+pub async fn rebuild_vaa_records(
+    store: &EventStore<ObserverEvent>,
+    repository: &Repository<VaaRecord, VaaRecordView>,
+    network: &str,
+) -> Result<usize, Error> {
+    let mut projection = VaaRecordProjection::new(network);
+    store.replay(0, |event| projection.apply(event)).await?;
+
+    let mut restored = 0;
+    for record in projection.records() {
+        if repository.get(record.id).await.is_none() {
+            repository.create(record.clone()).await;
+            restored += 1;
+        }
+    }
+    info!("Restored {} of {} VAA records from the event log", restored, projection.records().len());
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::wormhole::builder::test_vaa_bytes;
+    use crate::storage::events::MemoryEventStore;
+    use crate::storage::memory::MemoryRepository;
+    use crate::domain::wormhole::models::SequenceGap;
+
+    fn observed(sequence: u64) -> ObserverEvent {
+        let bytes = test_vaa_bytes(sequence);
+        let record = VaaRecord::from_bytes(bytes.clone(), "mainnet").unwrap();
+
+        ObserverEvent::VaaObserved {
+            digest: record.digest,
+            message_id: record.message_id,
+            emitter_chain: 2,
+            emitter_address: "11".repeat(32),
+            sequence,
+            guardian_set_index: 0,
+            vaa: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes),
+        }
+    }
+
+    async fn test_store() -> EventStore<ObserverEvent> {
+        let store = EventStore::new(MemoryEventStore::new());
+        for event in [
+            observed(1),
+            observed(4),
+            ObserverEvent::GapDetected {
+                emitter_chain: 2,
+                emitter_address: "11".repeat(32),
+                gap: SequenceGap { from: 2, to: 3, size: 2, governed: Vec::new() },
+                outage: false,
+            },
+            observed(2),
+            ObserverEvent::GapFilled { emitter_chain: 2, emitter_address: "11".repeat(32), sequence: 2 },
+        ] {
+            store.append(event).await.unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_emitter_stats() {
+        let store = test_store().await;
+        let projections = Projections::new();
+        assert_eq!(projections.rebuild(&store).await.unwrap(), 5);
+
+        // Events already replayed are not applied twice
+        let replayed = store.read(&Default::default()).await.unwrap();
+        projections.apply(&replayed).await;
+
+        let stats = &projections.emitters().await[0];
+        assert_eq!((stats.vaas, stats.lowest_sequence, stats.highest_sequence), (3, Some(1), Some(4)));
+        assert_eq!((stats.gaps_detected, stats.gaps_filled, stats.missing_sequences), (1, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_vaa_records() {
+        let store = test_store().await;
+        let repository = Repository::new(MemoryRepository::new());
+
+        assert_eq!(rebuild_vaa_records(&store, &repository, "mainnet").await.unwrap(), 3);
+        assert_eq!(rebuild_vaa_records(&store, &repository, "mainnet").await.unwrap(), 0);
+        assert_eq!(repository.list().await.len(), 3);
+    }
+}
@@ -0,0 +1,293 @@
+// Reconciliation of what the Spy delivered, what is stored and what Wormholescan indexed
+
+use std::collections::{BTreeMap, BTreeSet};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Serialize;
+use schemars::JsonSchema;
+
+use crate::AppState;
+use crate::library::errors::Error;
+use crate::storage::query::{Direction, QueryOrder, VaaQuery, MAX_QUERY_LIMIT};
+use super::chains;
+use super::events::observed_digests;
+use super::models::VaaDoc;
+use super::rest::RestClient;
+use super::vaa::Vaa;
+
+/// Widest sequence range reconciled at once
+pub const MAX_RECONCILE_RANGE: u64 = 1_000;
+const SCAN_PAGE_SIZE: u32 = 1_000;
+// Pages walked back from the newest indexed VAA to reach the range
+const MAX_SCAN_PAGES: u32 = 50;
+
+/// How the sources agree on a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconcileStatus {
+    /// Delivered by the Spy, stored and indexed by Wormholescan, with the same digest
+    PresentEverywhere,
+    /// Delivered by the Spy but not indexed by Wormholescan
+    MissingFromScan,
+    /// Indexed by Wormholescan (or only stored, e.g. crawled) but never delivered by the Spy
+    MissingFromSpy,
+    /// The sources hold different digests for the sequence
+    DigestMismatch,
+    /// No source has the sequence
+    Missing,
+}
+
+/// What each source holds for a sequence.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct SequenceReconciliation {
+    pub sequence: u64,
+    pub status: ReconcileStatus,
+    pub spy_digests: Vec<String>,
+    pub stored_digests: Vec<String>,
+    pub scan_digests: Vec<String>,
+}
+
+/// Number of sequences of the range with each status.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct ReconcileSummary {
+    pub present_everywhere: u64,
+    pub missing_from_scan: u64,
+    pub missing_from_spy: u64,
+    pub digest_mismatch: u64,
+    pub missing: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ReconcileReport {
+    pub emitter_chain: u16,
+    pub emitter_chain_name: Option<String>,
+    pub emitter_address: String,
+    /// First sequence reconciled, inclusive
+    pub sequence_from: u64,
+    /// Last sequence reconciled, inclusive
+    pub sequence_to: u64,
+    pub summary: ReconcileSummary,
+    /// Every sequence that is not present everywhere, in order
+    pub discrepancies: Vec<SequenceReconciliation>,
+}
+
+/// Digests held by each source, by sequence.
+#[derive(Debug, Clone, Default)]
+pub struct SourceDigests {
+    pub spy: BTreeMap<u64, BTreeSet<String>>,
+    pub stored: BTreeMap<u64, BTreeSet<String>>,
+    pub scan: BTreeMap<u64, BTreeSet<String>>,
+}
+
+fn classify(spy: &BTreeSet<String>, stored: &BTreeSet<String>, scan: &BTreeSet<String>) -> ReconcileStatus {
+    let digests: BTreeSet<&String> = spy.iter().chain(stored).chain(scan).collect();
+    match digests.len() {
+        0 => ReconcileStatus::Missing,
+        1 if spy.is_empty() => ReconcileStatus::MissingFromSpy,
+        1 if scan.is_empty() => ReconcileStatus::MissingFromScan,
+        1 => ReconcileStatus::PresentEverywhere,
+        _ => ReconcileStatus::DigestMismatch,
+    }
+}
+
+/// Classifies every sequence of `from..=to`.
+pub fn reconcile_sequences(from: u64, to: u64, sources: &SourceDigests) -> (ReconcileSummary, Vec<SequenceReconciliation>) {
+    let none = BTreeSet::new();
+    let mut summary = ReconcileSummary::default();
+    let mut discrepancies = Vec::new();
+
+    for sequence in from..=to {
+        let spy = sources.spy.get(&sequence).unwrap_or(&none);
+        let stored = sources.stored.get(&sequence).unwrap_or(&none);
+        let scan = sources.scan.get(&sequence).unwrap_or(&none);
+        let status = classify(spy, stored, scan);
+        match status {
+            ReconcileStatus::PresentEverywhere => {
+                summary.present_everywhere += 1;
+                continue;
+            }
+            ReconcileStatus::MissingFromScan => summary.missing_from_scan += 1,
+            ReconcileStatus::MissingFromSpy => summary.missing_from_spy += 1,
+            ReconcileStatus::DigestMismatch => summary.digest_mismatch += 1,
+            ReconcileStatus::Missing => summary.missing += 1,
+        }
+        discrepancies.push(SequenceReconciliation {
+            sequence,
+            status,
+            spy_digests: spy.iter().cloned().collect(),
+            stored_digests: stored.iter().cloned().collect(),
+            scan_digests: scan.iter().cloned().collect(),
+        });
+    }
+    (summary, discrepancies)
+}
+
+/// Compares the emitter's sequences across the Spy, storage and Wormholescan.
+/// Without bounds, the latest `MAX_RECONCILE_RANGE` sequences any source knows
+/// of are reconciled. The Spy only counts for the VAAs it delivered while the
+/// observer was ingesting, as recorded in the event log.
+pub async fn reconcile(
+    state: &AppState,
+    client: &RestClient,
+    emitter_chain: u16,
+    emitter_address: &str,
+    sequence_from: Option<u64>,
+    sequence_to: Option<u64>,
+) -> Result<ReconcileReport, Error> {
+    if let (Some(from), Some(to)) = (sequence_from, sequence_to) {
+        if from > to || to - from >= MAX_RECONCILE_RANGE {
+            return Err(Error::Request(format!(
+                "Invalid sequence range {}..={}: expected at most {} sequences",
+                from, to, MAX_RECONCILE_RANGE
+            )));
+        }
+    }
+
+    // Newest pages first, the range is usually recent
+    let mut pages = client.pages(emitter_chain, emitter_address, SCAN_PAGE_SIZE, 0).newest_first();
+    let mut scan_docs: Vec<VaaDoc> = pages.next().await?.map(|(_, docs)| docs).unwrap_or_default();
+
+    let (from, to) = match (sequence_from, sequence_to) {
+        (Some(from), Some(to)) => (from, to),
+        (from, to) => {
+            let highest = [stored_highest(state, emitter_chain, emitter_address).await?, scan_docs.first().map(|doc| doc.sequence)]
+                .into_iter()
+                .flatten()
+                .max()
+                .ok_or_else(|| Error::Request(format!(
+                    "No VAA stored or indexed for {}/{}, pass a sequence range",
+                    emitter_chain, emitter_address
+                )))?;
+            match (from, to) {
+                (Some(from), _) => (from, highest.min(from.saturating_add(MAX_RECONCILE_RANGE - 1)).max(from)),
+                (None, to) => {
+                    let to = to.unwrap_or(highest);
+                    (to.saturating_sub(MAX_RECONCILE_RANGE - 1), to)
+                }
+            }
+        }
+    };
+
+    // Walk back until the oldest page fetched reaches the range
+    let mut walked = 1;
+    while scan_docs.last().is_some_and(|doc| doc.sequence > from) {
+        if walked >= MAX_SCAN_PAGES {
+            return Err(Error::Request(format!(
+                "Sequence {} is too far behind the latest VAAs indexed by Wormholescan",
+                from
+            )));
+        }
+        match pages.next().await? {
+            Some((_, docs)) => scan_docs.extend(docs),
+            None => break,
+        }
+        walked += 1;
+    }
+
+    let mut sources = SourceDigests::default();
+    for doc in scan_docs.iter().filter(|doc| (from..=to).contains(&doc.sequence)) {
+        if let Some(digest) = doc_digest(doc) {
+            sources.scan.entry(doc.sequence).or_default().insert(digest);
+        }
+    }
+
+    let mut query = VaaQuery {
+        emitter_chain: Some(emitter_chain),
+        emitter_address: Some(emitter_address.to_string()),
+        sequence_from: Some(from),
+        sequence_to: Some(to),
+        order: QueryOrder::Sequence,
+        limit: Some(MAX_QUERY_LIMIT),
+        ..Default::default()
+    };
+    loop {
+        let page = state.vaas_repository().query(&query).await?;
+        for vaa in &page.items {
+            sources.stored.entry(vaa.sequence).or_default().insert(vaa.digest.clone());
+        }
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => break,
+        }
+    }
+
+    let stored_digests = sources.stored.values().flatten().cloned().collect();
+    let observed = observed_digests(state.events(), stored_digests).await?;
+    for (sequence, digests) in &sources.stored {
+        for digest in digests.iter().filter(|digest| observed.contains(*digest)) {
+            sources.spy.entry(*sequence).or_default().insert(digest.clone());
+        }
+    }
+
+    let (summary, discrepancies) = reconcile_sequences(from, to, &sources);
+    Ok(ReconcileReport {
+        emitter_chain,
+        emitter_chain_name: chains::name(emitter_chain),
+        emitter_address: emitter_address.to_string(),
+        sequence_from: from,
+        sequence_to: to,
+        summary,
+        discrepancies,
+    })
+}
+
+async fn stored_highest(state: &AppState, emitter_chain: u16, emitter_address: &str) -> Result<Option<u64>, Error> {
+    let query = VaaQuery {
+        emitter_chain: Some(emitter_chain),
+        emitter_address: Some(emitter_address.to_string()),
+        order: QueryOrder::Sequence,
+        direction: Direction::Desc,
+        limit: Some(1),
+        ..Default::default()
+    };
+    let page = state.vaas_repository().query(&query).await?;
+    Ok(page.items.first().map(|vaa| vaa.sequence))
+}
+
+// Digest computed from the indexed VAA, Wormholescan's own when it can't be parsed
+fn doc_digest(doc: &VaaDoc) -> Option<String> {
+    STANDARD
+        .decode(&doc.vaa)
+        .ok()
+        .and_then(|bytes| Vaa::parse(&bytes).ok())
+        .map(|vaa| hex::encode(vaa.digest()))
+        .or_else(|| doc.digest.as_ref().map(|digest| digest.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digests(entries: &[(u64, &str)]) -> BTreeMap<u64, BTreeSet<String>> {
+        let mut digests: BTreeMap<u64, BTreeSet<String>> = BTreeMap::new();
+        for (sequence, digest) in entries {
+            digests.entry(*sequence).or_default().insert(digest.to_string());
+        }
+        digests
+    }
+
+    #[test]
+    fn test_reconcile_sequences() {
+        let sources = SourceDigests {
+            spy: digests(&[(1, "a"), (2, "b"), (4, "d")]),
+            stored: digests(&[(1, "a"), (2, "b"), (3, "c"), (4, "d")]),
+            scan: digests(&[(1, "a"), (3, "c"), (4, "e")]),
+        };
+        let (summary, discrepancies) = reconcile_sequences(1, 5, &sources);
+
+        assert_eq!(summary, ReconcileSummary {
+            present_everywhere: 1,
+            missing_from_scan: 1,
+            missing_from_spy: 1,
+            digest_mismatch: 1,
+            missing: 1,
+        });
+        let statuses: Vec<(u64, ReconcileStatus)> = discrepancies.iter().map(|entry| (entry.sequence, entry.status)).collect();
+        assert_eq!(statuses, vec![
+            (2, ReconcileStatus::MissingFromScan),
+            (3, ReconcileStatus::MissingFromSpy),
+            (4, ReconcileStatus::DigestMismatch),
+            (5, ReconcileStatus::Missing),
+        ]);
+        assert_eq!(discrepancies[2].scan_digests, vec!["e".to_string()]);
+    }
+}
@@ -5,10 +5,12 @@ use hyper_util::{
     rt::TokioExecutor,
 };
 use hyper_tls::HttpsConnector;
+use serde::Deserialize;
 use tracing::{debug};
 
 use crate::library::config::get_config;
 use crate::library::errors::Error;
+use crate::domain::wormhole::models::{ResponsePagination, VaaDoc};
 
 
 #[derive(Clone)]
@@ -17,18 +19,90 @@ pub struct RestClient {
     base_url: String,
 }
 
+/// Page of `/vaas/{chain}/{emitter}` to request. Unset fields are left to
+/// Wormholescan, which returns its first page, newest VAAs first.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScanPage {
+    /// Zero-based page number
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    /// Oldest VAAs first, so pages stay put as new VAAs are indexed
+    pub ascending: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScanVaaPage {
+    pub data: Vec<VaaDoc>,
+    pub pagination: Option<ResponsePagination>,
+}
+
+impl ScanVaaPage {
+    // Cursor to the next page, when Wormholescan gives one
+    fn next(&self) -> Option<&str> {
+        self.pagination
+            .as_ref()
+            .and_then(|pagination| pagination.next.as_deref())
+            .filter(|next| !next.is_empty())
+    }
+}
+
+impl Default for RestClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RestClient {
     pub fn new() -> Self {
+        Self::with_base_url(&get_config().wormholescan_base_url)
+    }
+
+    pub fn with_base_url(base_url: &str) -> Self {
         let https = HttpsConnector::new();
         let client = Client::builder(TokioExecutor::new())
             .build::<_, Empty<Bytes>>(https);
-        let base_url = get_config().wormholescan_base_url.clone();
 
-        Self { client, base_url }
+        Self { client, base_url: base_url.trim_end_matches('/').to_string() }
+    }
+
+    pub async fn get_vaas(&self, chain_id: u16, emitter_address: &str, page: &ScanPage) -> Result<Bytes, Error> {
+        let mut params = Vec::new();
+        if let Some(number) = page.page {
+            params.push(format!("page={}", number));
+        }
+        if let Some(page_size) = page.page_size {
+            params.push(format!("pageSize={}", page_size));
+        }
+        if page.ascending {
+            params.push("sortOrder=ASC".to_string());
+        }
+
+        let mut url = format!("{}/vaas/{}/{}", self.base_url, chain_id, emitter_address);
+        if !params.is_empty() {
+            url = format!("{}?{}", url, params.join("&"));
+        }
+        self.get(url).await
+    }
+
+    /// Fetches and parses a page of an emitter's VAAs.
+    pub async fn get_vaa_page(&self, chain_id: u16, emitter_address: &str, page: &ScanPage) -> Result<ScanVaaPage, Error> {
+        parse_page(&self.get_vaas(chain_id, emitter_address, page).await?)
     }
 
-    pub async fn get_vaas(&self, chain_id: u16, emitter_address: &str) -> Result<Bytes, Error> {
-        let url = format!("{}/vaas/{}/{}", self.base_url, chain_id, emitter_address);
+    /// Walks an emitter's VAAs page by page, oldest first, from page `start`.
+    pub fn pages(&self, chain_id: u16, emitter_address: &str, page_size: u32, start: u32) -> ScanPages<'_> {
+        ScanPages {
+            client: self,
+            chain_id,
+            emitter_address: emitter_address.to_string(),
+            page_size: page_size.max(1),
+            page: start,
+            ascending: true,
+            next: Some(None),
+        }
+    }
+
+    async fn get(&self, url: String) -> Result<Bytes, Error> {
         let req = Request::builder()
             .method("GET")
             .uri(url.clone())
@@ -59,4 +133,65 @@ impl RestClient {
 
         Ok(bytes)
     }
-}
\ No newline at end of file
+}
+
+fn parse_page(bytes: &[u8]) -> Result<ScanVaaPage, Error> {
+    serde_json::from_slice(bytes).map_err(|e| Error::Parsing(format!("Failed to parse response: {}", e)))
+}
+
+/// Traversal of an emitter's VAAs, see `RestClient::pages`. A non-empty
+/// `pagination.next` is followed as is, an absolute URL or a path under the
+/// API base. Otherwise page numbers are requested until a short page comes back.
+pub struct ScanPages<'a> {
+    client: &'a RestClient,
+    chain_id: u16,
+    emitter_address: String,
+    page_size: u32,
+    // Number of the page `next` points to
+    page: u32,
+    ascending: bool,
+    // None once done, Some(None) for the next page number
+    next: Option<Option<String>>,
+}
+
+impl ScanPages<'_> {
+    /// Walks the pages newest VAAs first instead, e.g. to reach recent sequences quickly
+    pub fn newest_first(mut self) -> Self {
+        self.ascending = false;
+        self
+    }
+
+    /// The next page along with its number, `None` past the last one.
+    pub async fn next(&mut self) -> Result<Option<(u32, Vec<VaaDoc>)>, Error> {
+        let Some(cursor) = self.next.take() else {
+            return Ok(None);
+        };
+
+        let response = match cursor {
+            Some(next) if next.contains("://") => parse_page(&self.client.get(next).await?)?,
+            Some(next) => parse_page(&self.client.get(format!("{}/{}", self.client.base_url, next.trim_start_matches('/'))).await?)?,
+            None => {
+                let page = ScanPage { page: Some(self.page), page_size: Some(self.page_size), ascending: self.ascending };
+                self.client.get_vaa_page(self.chain_id, &self.emitter_address, &page).await?
+            }
+        };
+
+        let number = self.page;
+        if let Some(next) = response.next() {
+            self.next = Some(Some(next.to_string()));
+        } else if response.data.len() >= self.page_size as usize {
+            self.next = Some(None);
+        }
+        self.page += 1;
+
+        if response.data.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some((number, response.data)))
+    }
+
+    /// Number of the page `next` requests, `None` once the last one was returned
+    pub fn next_page(&self) -> Option<u32> {
+        self.next.as_ref().map(|_| self.page)
+    }
+}
@@ -0,0 +1,336 @@
+// Crawls an emitter's whole Wormholescan history into storage
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use schemars::JsonSchema;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::AppState;
+use crate::library::errors::Error;
+use crate::storage::events::EventQuery;
+use crate::storage::query::{QueryOrder, VaaQuery, MAX_QUERY_LIMIT};
+use crate::domain::wormhole::events::{crawl_stream_id, record as record_events, ObserverEvent};
+use crate::domain::wormhole::models::{VaaDoc, VaaMetadata, VaaRecord};
+use crate::domain::wormhole::sequences::SequenceTracker;
+use super::client::RestClient;
+
+/// VAAs requested per Wormholescan page while crawling
+pub const CRAWL_PAGE_SIZE: u32 = 500;
+// Pause between pages, to stay clear of Wormholescan's rate limits
+const CRAWL_PAGE_DELAY: Duration = Duration::from_millis(200);
+
+/// Progress of an emitter's crawl.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CrawlStatus {
+    pub emitter_chain: u16,
+    pub emitter_address: String,
+    pub running: bool,
+    /// Page the crawl resumes from
+    pub next_page: u32,
+    /// VAAs of the emitter's history crawled so far
+    pub vaas_crawled: u64,
+    /// VAAs that were not stored yet
+    pub vaas_stored: u64,
+    /// The last run reached the end of the history
+    pub complete: bool,
+    #[schemars(with = "Option<String>")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[schemars(with = "Option<String>")]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// A crawl along with the gap analysis of every VAA stored for the emitter.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CrawlReport {
+    pub status: CrawlStatus,
+    pub metadata: VaaMetadata,
+}
+
+/// Crawls started since startup, by emitter.
+#[derive(Debug, Default)]
+pub struct Crawls {
+    statuses: Mutex<HashMap<(u16, String), CrawlStatus>>,
+}
+
+impl Crawls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn update(&self, status: &CrawlStatus) {
+        self.statuses
+            .lock()
+            .await
+            .insert((status.emitter_chain, status.emitter_address.clone()), status.clone());
+    }
+}
+
+/// Starts crawling an emitter's history in the background, from the latest
+/// checkpoint in the event log unless `restart` is set. Pages are requested
+/// oldest first, so a finished crawl resumed later only fetches newer VAAs.
+pub async fn start_crawl(
+    state: Arc<AppState>,
+    client: RestClient,
+    emitter_chain: u16,
+    emitter_address: String,
+    restart: bool,
+) -> Result<CrawlStatus, Error> {
+    let mut status = crawl_status(&state, emitter_chain, &emitter_address).await?;
+    if status.running {
+        return Err(Error::Request(format!("A crawl of {}/{} is already running", emitter_chain, emitter_address)));
+    }
+    if restart {
+        status.next_page = 0;
+        status.vaas_crawled = 0;
+        status.vaas_stored = 0;
+    }
+    status.running = true;
+    status.complete = false;
+    status.started_at = Some(Utc::now());
+    status.finished_at = None;
+    status.last_error = None;
+    state.crawls().update(&status).await;
+
+    let started = status.clone();
+    tokio::spawn(async move {
+        let result = crawl(&state, &client, &mut status).await;
+        status.running = false;
+        status.finished_at = Some(Utc::now());
+        match result {
+            Ok(()) => info!(
+                "Crawled {}/{}: {} VAAs, {} newly stored",
+                status.emitter_chain, status.emitter_address, status.vaas_crawled, status.vaas_stored
+            ),
+            Err(e) => {
+                warn!("Crawl of {}/{} stopped: {}", status.emitter_chain, status.emitter_address, e);
+                status.last_error = Some(e.to_string());
+            }
+        }
+        state.crawls().update(&status).await;
+    });
+    Ok(started)
+}
+
+/// Status of the emitter's crawl: the live one if any, else its latest checkpoint.
+pub async fn crawl_status(state: &AppState, emitter_chain: u16, emitter_address: &str) -> Result<CrawlStatus, Error> {
+    if let Some(status) = state.crawls().statuses.lock().await.get(&(emitter_chain, emitter_address.to_string())) {
+        return Ok(status.clone());
+    }
+
+    let mut status = CrawlStatus {
+        emitter_chain,
+        emitter_address: emitter_address.to_string(),
+        running: false,
+        next_page: 0,
+        vaas_crawled: 0,
+        vaas_stored: 0,
+        complete: false,
+        started_at: None,
+        finished_at: None,
+        last_error: None,
+    };
+    let mut query = EventQuery {
+        stream_ids: vec![crawl_stream_id(emitter_chain, emitter_address)],
+        ..Default::default()
+    };
+    loop {
+        let events = state.events().read(&query).await?;
+        let Some(last) = events.last() else { break };
+        query.after = last.position;
+        if let ObserverEvent::CrawlCheckpoint { page_size, next_page, vaas_crawled, vaas_stored, complete, .. } = last.event {
+            // Pages of another size start elsewhere, crawl them all again
+            status.next_page = if page_size == CRAWL_PAGE_SIZE { next_page } else { 0 };
+            status.vaas_crawled = vaas_crawled;
+            status.vaas_stored = vaas_stored;
+            status.complete = complete;
+            status.finished_at = Some(last.recorded_at);
+        }
+    }
+    Ok(status)
+}
+
+// Stores every page from `status.next_page` on, checkpointing after each one
+async fn crawl(state: &AppState, client: &RestClient, status: &mut CrawlStatus) -> Result<(), Error> {
+    let mut pages = client.pages(status.emitter_chain, &status.emitter_address, CRAWL_PAGE_SIZE, status.next_page);
+    while let Some((page, docs)) = pages.next().await? {
+        status.vaas_stored += store_docs(state, &docs).await;
+        status.vaas_crawled = u64::from(page) * u64::from(CRAWL_PAGE_SIZE) + docs.len() as u64;
+        // A short page is fetched again next time, it gets the VAAs indexed meanwhile
+        status.next_page = if docs.len() >= CRAWL_PAGE_SIZE as usize { page + 1 } else { page };
+        checkpoint(state, status).await;
+        tokio::time::sleep(CRAWL_PAGE_DELAY).await;
+    }
+
+    status.complete = true;
+    checkpoint(state, status).await;
+    Ok(())
+}
+
+async fn checkpoint(state: &AppState, status: &CrawlStatus) {
+    record_events(state, [ObserverEvent::CrawlCheckpoint {
+        emitter_chain: status.emitter_chain,
+        emitter_address: status.emitter_address.clone(),
+        page_size: CRAWL_PAGE_SIZE,
+        next_page: status.next_page,
+        vaas_crawled: status.vaas_crawled,
+        vaas_stored: status.vaas_stored,
+        complete: status.complete,
+    }]).await;
+    state.crawls().update(status).await;
+}
+
+// Stores the VAAs not stored yet, returning how many were
+async fn store_docs(state: &AppState, docs: &[VaaDoc]) -> u64 {
+    let mut stored = 0;
+    for doc in docs {
+        let record = match STANDARD.decode(&doc.vaa).map_err(|e| Error::Parsing(e.to_string())).and_then(|bytes| VaaRecord::from_bytes(bytes, state.guardian_sets().network())) {
+            Ok(record) => record,
+            Err(e) => {
+                warn!("Skipping crawled VAA {}: {}", doc.id, e);
+                continue;
+            }
+        };
+        if state.vaas_repository().get(record.id).await.is_none() {
+            state.vaas_repository().create(record).await;
+            stored += 1;
+        }
+    }
+    stored
+}
+
+/// Sequence analysis over every VAA stored for the emitter, crawled or ingested.
+pub async fn analyze_stored_sequences(state: &AppState, emitter_chain: u16, emitter_address: &str) -> Result<VaaMetadata, Error> {
+    let mut tracker = SequenceTracker::new();
+    let mut query = VaaQuery {
+        emitter_chain: Some(emitter_chain),
+        emitter_address: Some(emitter_address.to_string()),
+        order: QueryOrder::Sequence,
+        limit: Some(MAX_QUERY_LIMIT),
+        ..Default::default()
+    };
+    loop {
+        let page = state.vaas_repository().query(&query).await?;
+        for vaa in &page.items {
+            // Message IDs end with the sequence
+            if let Some(sequence) = vaa.message_id.rsplit('/').next().and_then(|sequence| sequence.parse().ok()) {
+                tracker.observe(sequence);
+            }
+        }
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => return Ok(tracker.metadata()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::wormhole::builder::test_vaa_bytes;
+    use axum::{extract::{Path, Query}, routing::get, Json, Router};
+    use serde::Deserialize;
+    use serde_json::json;
+    use crate::state::Repositories;
+    use crate::storage::{Repository, VaaRepository, events::{EventStore, MemoryEventStore}, memory::MemoryRepository};
+    use crate::domain::wormhole::guardians::GuardianSetRegistry;
+
+    const EMITTER: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+
+    #[derive(Deserialize)]
+    struct PageParams {
+        page: u32,
+        #[serde(rename = "pageSize")]
+        page_size: u32,
+    }
+
+    // Wormholescan serving `sequences`, oldest first
+    async fn serve(sequences: Vec<u64>) -> RestClient {
+        let app = Router::new().route(
+            "/vaas/{chain}/{emitter}",
+            get(move |Path((chain, emitter)): Path<(u16, String)>, Query(params): Query<PageParams>| {
+                let sequences = sequences.clone();
+                async move {
+                    let start = (params.page * params.page_size) as usize;
+                    let data: Vec<_> = sequences.iter().skip(start).take(params.page_size as usize).map(|sequence| json!({
+                        "sequence": sequence,
+                        "id": format!("{}/{}/{}", chain, emitter, sequence),
+                        "version": 1,
+                        "emitterChain": chain,
+                        "emitterAddr": emitter,
+                        "emitterNativeAddr": null,
+                        "guardianSetIndex": 0,
+                        "vaa": STANDARD.encode(test_vaa_bytes(*sequence)),
+                        "timestamp": "2023-11-14T22:13:20Z",
+                        "updatedAt": "2023-11-14T22:13:20Z",
+                        "indexedAt": "2023-11-14T22:13:20Z",
+                        "txHash": null,
+                        "digest": null,
+                        "isDuplicated": false,
+                    })).collect();
+                    Json(json!({ "data": data, "pagination": { "next": "" } }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        RestClient::with_base_url(&format!("http://{}", addr))
+    }
+
+    async fn test_state() -> Arc<AppState> {
+        let repositories = Repositories::new(
+            Repository::new(MemoryRepository::new()),
+            VaaRepository::new(MemoryRepository::new()),
+            EventStore::new(MemoryEventStore::new()),
+        );
+        let guardian_sets = Arc::new(GuardianSetRegistry::bundled("devnet").unwrap());
+        Arc::new(AppState::new(repositories, guardian_sets).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_pages_walk_whole_history() {
+        let client = serve((1..=7).collect()).await;
+        let mut pages = client.pages(2, EMITTER, 3, 0);
+        let mut sequences = Vec::new();
+        while let Some((_, docs)) = pages.next().await.unwrap() {
+            sequences.extend(docs.iter().map(|doc| doc.sequence));
+        }
+        assert_eq!(sequences, (1..=7).collect::<Vec<u64>>());
+    }
+
+    #[tokio::test]
+    async fn test_crawl_stores_history_and_resumes() {
+        let history: Vec<u64> = (1..=CRAWL_PAGE_SIZE as u64 + 10).filter(|sequence| *sequence != 42).collect();
+        let state = test_state().await;
+        let client = serve(history.clone()).await;
+
+        start_crawl(state.clone(), client.clone(), 2, EMITTER.to_string(), false).await.unwrap();
+        let status = loop {
+            let status = crawl_status(&state, 2, EMITTER).await.unwrap();
+            if !status.running {
+                break status;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        };
+        assert!(status.complete && status.last_error.is_none());
+        assert_eq!((status.next_page, status.vaas_crawled, status.vaas_stored), (1, history.len() as u64, history.len() as u64));
+
+        let metadata = analyze_stored_sequences(&state, 2, EMITTER).await.unwrap();
+        assert_eq!((metadata.total_items, metadata.total_gaps), (history.len(), 1));
+        assert_eq!(metadata.sequence_gaps[0].from, 42);
+
+        // A restarted observer resumes from the checkpoint in the log
+        let repositories = Repositories::new(
+            state.wormhole_repository().clone(),
+            state.vaas_repository().clone(),
+            state.events().clone(),
+        );
+        let restarted = AppState::new(repositories, Arc::new(GuardianSetRegistry::bundled("devnet").unwrap())).await.unwrap();
+        let resumed = crawl_status(&restarted, 2, EMITTER).await.unwrap();
+        assert_eq!((resumed.next_page, resumed.vaas_crawled, resumed.complete), (1, history.len() as u64, true));
+    }
+}
@@ -4,17 +4,20 @@ use aide::{
     transform::TransformOperation,
 };
 use axum::{
-    extract::Path,
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
 use serde_json::json;
-use tracing::{info, error, debug};
+use tracing::{info, error};
 use serde::Deserialize;
+use schemars::JsonSchema;
 
 use crate::AppState;
-use super::client::RestClient;
+use crate::library::errors::Error;
+use super::client::{RestClient, ScanPage};
+use super::crawl::{analyze_stored_sequences, crawl_status, start_crawl, CrawlReport, CrawlStatus};
 use crate::domain::wormhole::models::{
     VaaRequest, 
     VaaResponse, 
@@ -22,12 +25,42 @@ use crate::domain::wormhole::models::{
     VaaMetadata,
     ResponsePagination,
 };
-use super::vaa::{analyze_sequences, decode_vaa, DecodeVaaRequest};
+use crate::domain::wormhole::chains;
+use super::input::VaaInput;
+use super::vaa::{
+    analyze_sequences, decode_vaa, decode_vaa_batch, decode_vaa_bytes, encode_vaa, name_chains, verify_digests,
+    DecodedVaa, EncodeVaaRequest, EncodedVaa, MAX_DECODE_BATCH,
+};
+
+/// Largest page Wormholescan serves
+const MAX_PAGE_SIZE: u32 = 1_000;
+// Pages walked by a single `all` request, crawls have no such limit
+const MAX_PAGES_PER_REQUEST: u32 = 50;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ScanParams {
+    /// Zero-based page number, the first page by default
+    page: Option<u32>,
+    /// VAAs per page, at most 1000
+    page_size: Option<u32>,
+    /// Walk every page from `page` on, oldest first, up to 50 pages
+    #[serde(default)]
+    all: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CrawlParams {
+    /// Crawl from the first page instead of the latest checkpoint
+    #[serde(default)]
+    restart: bool,
+}
 
-#[derive(Debug, Deserialize)]
-struct ExternalVaaResponse {
-    data: Vec<VaaDoc>,
-    pagination: Option<ResponsePagination>,
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DecodeParams {
+    /// Reject VAAs the Core Bridge would refuse: another version than 1, no
+    /// signatures, or guardian indexes repeated, out of order or outside the set
+    #[serde(default)]
+    pub strict: bool,
 }
 
 pub fn wormhole_routes(state: Arc<AppState>) -> ApiRouter {
@@ -36,72 +69,105 @@ pub fn wormhole_routes(state: Arc<AppState>) -> ApiRouter {
             "/scan/vaas/{chain_id}/{emitter}",
             get_with(get_vaas, get_vaas_docs),
         )
+        .api_route(
+            "/scan/crawl/{chain_id}/{emitter}",
+            post_with(start_crawl_handler, start_crawl_docs)
+                .get_with(get_crawl, get_crawl_docs),
+        )
         .api_route(
             "/observer/vaas/decode",
             post_with(decode_vaa_handler, decode_vaa_docs),
         )
+        .api_route(
+            "/observer/vaas/encode",
+            post_with(encode_vaa_handler, encode_vaa_docs),
+        )
         .with_state(state)
 }
 
-async fn get_vaas(
-    Path(params): Path<VaaRequest>,
-) -> impl IntoApiResponse {
+// Checks the chain ID and emitter address of a Wormholescan request
+fn validate_request(state: &AppState, params: &VaaRequest) -> Result<(), String> {
+    let chain = chains::resolve(params.chain_id, state.guardian_sets().network()).map_err(|e| e.to_string())?;
+
     if params.emitter.len() != 64 {  // 32 bytes in hex = 64 chars
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": "Invalid emitter address length. Expected 64 hex characters (32 bytes)",
-            }))
-        ).into_response();
+        return Err("Invalid emitter address length. Expected 64 hex characters (32 bytes)".to_string());
     }
 
-    if hex::decode(&params.emitter).is_err() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": "Invalid emitter address format. Expected hex string",
-            }))
-        ).into_response();
+    let Some(emitter) = hex::decode(&params.emitter).ok().and_then(|bytes| <[u8; 32]>::try_from(bytes).ok()) else {
+        return Err("Invalid emitter address format. Expected hex string".to_string());
+    };
+
+    chain.validate_emitter(&emitter).map_err(|e| e.to_string())
+}
+
+// Fetches the requested page, or every page from it on when `all` is set.
+// The returned pagination points to the page to request next.
+async fn fetch_vaas(client: &RestClient, params: &VaaRequest, scan: &ScanParams) -> Result<(Vec<VaaDoc>, ResponsePagination), Error> {
+    let page_size = scan.page_size.map(|page_size| page_size.clamp(1, MAX_PAGE_SIZE));
+
+    if !scan.all {
+        let page = ScanPage { page: scan.page, page_size, ascending: false };
+        let response = client.get_vaa_page(params.chain_id, &params.emitter, &page).await?;
+        let has_more = response.pagination.as_ref().and_then(|pagination| pagination.next.as_deref()).is_some_and(|next| !next.is_empty())
+            || page_size.is_some_and(|page_size| response.data.len() >= page_size as usize);
+        let next = has_more.then(|| (scan.page.unwrap_or(0) + 1).to_string());
+        return Ok((response.data, ResponsePagination { next }));
+    }
+
+    let mut pages = client.pages(params.chain_id, &params.emitter, page_size.unwrap_or(MAX_PAGE_SIZE), scan.page.unwrap_or(0));
+    let mut data = Vec::new();
+    for _ in 0..MAX_PAGES_PER_REQUEST {
+        match pages.next().await? {
+            Some((_, docs)) => data.extend(docs),
+            None => break,
+        }
+    }
+    let next = pages.next_page().map(|page| page.to_string());
+    Ok((data, ResponsePagination { next }))
+}
+
+async fn get_vaas(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<VaaRequest>,
+    Query(scan): Query<ScanParams>,
+) -> impl IntoApiResponse {
+    if let Err(e) = validate_request(&state, &params) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
     }
 
     let client = RestClient::new();
     info!("Fetching VAAs for chain {} and emitter {}", params.chain_id, params.emitter);
-    
-    match client.get_vaas(params.chain_id, &params.emitter).await {
-        Ok(bytes) => {
-            let raw_response = String::from_utf8_lossy(&bytes);
-            debug!("Raw API response (truncated): {}...", &raw_response.chars().take(200).collect::<String>());
-            
-            match serde_json::from_slice::<ExternalVaaResponse>(&bytes) {
-                Ok(external_response) => {
-                    let total_items = external_response.data.len();
-                    info!("Successfully retrieved {} VAAs via REST", total_items);
-                    let mut response = VaaResponse {
-                        metadata: VaaMetadata {
-                            total_items,
-                            total_duplicates: 0,
-                            duplicated_sequences: Vec::new(),
-                            lowest_sequence: None,
-                            highest_sequence: None,
-                            sequence_gaps: Vec::new(),
-                            total_gaps: 0,
-                        },
-                        data: external_response.data,
-                        pagination: external_response.pagination,
-                    };
-                    
-                    analyze_sequences(&mut response);
-                    Json(response).into_response()
-                },
-                Err(e) => {
-                    error!("Failed to parse VAA response: {}", e);
-                    debug!("Raw response: {}", raw_response);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({ "error": format!("Failed to parse response: {}", e) }))
-                    ).into_response()
+
+    match fetch_vaas(&client, &params, &scan).await {
+        Ok((data, pagination)) => {
+            let total_items = data.len();
+            info!("Successfully retrieved {} VAAs via REST", total_items);
+            let mut response = VaaResponse {
+                metadata: VaaMetadata {
+                    total_items,
+                    total_duplicates: 0,
+                    duplicated_sequences: Vec::new(),
+                    lowest_sequence: None,
+                    highest_sequence: None,
+                    sequence_gaps: Vec::new(),
+                    total_gaps: 0,
                 },
-            }
+                data,
+                pagination: Some(pagination),
+            };
+
+            name_chains(&mut response);
+            verify_digests(&mut response);
+            analyze_sequences(&mut response);
+            state.governor().mark_gaps(params.chain_id, &params.emitter, &mut response.metadata.sequence_gaps).await;
+            Json(response).into_response()
+        },
+        Err(e @ Error::Parsing(_)) => {
+            error!("Failed to parse VAA response: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() }))
+            ).into_response()
         },
         Err(e) => {
             error!("Failed to fetch VAAs: {}", e);
@@ -113,15 +179,98 @@ async fn get_vaas(
     }
 }
 
+async fn start_crawl_handler(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<VaaRequest>,
+    Query(crawl): Query<CrawlParams>,
+) -> impl IntoApiResponse {
+    if let Err(e) = validate_request(&state, &params) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+    }
+
+    let emitter = params.emitter.to_lowercase();
+    match start_crawl(state.clone(), RestClient::new(), params.chain_id, emitter, crawl.restart).await {
+        Ok(status) => (StatusCode::ACCEPTED, Json(status)).into_response(),
+        Err(e @ Error::Request(_)) => (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": e.to_string() }))
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": e.to_string() }))
+        ).into_response(),
+    }
+}
+
+async fn get_crawl(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<VaaRequest>,
+) -> impl IntoApiResponse {
+    if let Err(e) = validate_request(&state, &params) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+    }
+
+    let emitter = params.emitter.to_lowercase();
+    let report = async {
+        let mut metadata = analyze_stored_sequences(&state, params.chain_id, &emitter).await?;
+        state.governor().mark_gaps(params.chain_id, &emitter, &mut metadata.sequence_gaps).await;
+        Ok::<_, Error>(CrawlReport {
+            status: crawl_status(&state, params.chain_id, &emitter).await?,
+            metadata,
+        })
+    };
+    match report.await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": e.to_string() }))
+        ).into_response(),
+    }
+}
+
 pub async fn decode_vaa_handler(
-    Json(request): Json<DecodeVaaRequest>,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DecodeParams>,
+    input: VaaInput,
 ) -> impl IntoApiResponse {
-    match decode_vaa(&request.vaa) {
+    let strict = params.strict;
+    let decoded = match input {
+        VaaInput::Text { vaa, format } => decode_vaa(&vaa, format, strict, state.guardian_sets()),
+        VaaInput::Binary(bytes) => decode_vaa_bytes(&bytes, strict, state.guardian_sets()),
+        VaaInput::Batch { vaas, format } => {
+            if vaas.len() > MAX_DECODE_BATCH {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({
+                        "error": format!("Too many VAAs: {}, at most {}", vaas.len(), MAX_DECODE_BATCH)
+                    }))
+                ).into_response();
+            }
+            return Json(decode_vaa_batch(&vaas, format, strict, state.guardian_sets())).into_response();
+        }
+    };
+    match decoded {
         Ok(decoded) => Json(decoded).into_response(),
         Err(e) => (
             StatusCode::BAD_REQUEST,
             Json(json!({
-                "error": format!("Failed to decode VAA: {}", e)
+                "error": format!("Failed to decode VAA: {}", e),
+                "details": e,
+            }))
+        ).into_response(),
+    }
+}
+
+pub async fn encode_vaa_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<EncodeVaaRequest>,
+) -> impl IntoApiResponse {
+    match encode_vaa(&request, state.guardian_sets()) {
+        Ok(encoded) => Json(encoded).into_response(),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!("Failed to encode VAA: {}", e)
             }))
         ).into_response(),
     }
@@ -134,19 +283,56 @@ fn get_vaas_docs(op: TransformOperation) -> TransformOperation {
             - 2: Ethereum\n\
             - 4: BSC\n\
             - 6: Avalanche\n\
-            - 30: Optimism\n\
-            \n\nSee https://docs.wormhole.com/wormhole/reference/constants for all chain IDs\n\n\
+            - 24: Optimism\n\
+            - 30: Base\n\
+            \n\nSee `GET /observer/chains` for every supported chain ID\n\n\
             Emitter address format: 32 bytes in hex (64 characters)\n\
-            Example: 000000000000000000000000706f82e9bb5b0813501714ab5974216704980e31")
+            Example: 000000000000000000000000706f82e9bb5b0813501714ab5974216704980e31\n\n\
+            Use `page` and `page_size` to page through the results, `pagination.next` holding the \
+            page to request next, or `all` to analyze every page at once.")
         .response::<200, ()>()
         .response::<400, ()>()
         .response::<500, ()>()
 }
 
+fn start_crawl_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Crawl the emitter's whole Wormholescan history into storage in the background, \
+            oldest VAAs first. Progress is checkpointed in the event log after every page, so a \
+            stopped or finished crawl resumes where it left off unless `restart` is set.")
+        .tag("wormhole-scan")
+        .response::<202, Json<CrawlStatus>>()
+        .response::<400, ()>()
+        .response::<409, ()>()
+        .response::<500, ()>()
+}
+
+fn get_crawl_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Progress of the emitter's crawl, along with the sequence gap analysis of every \
+            VAA stored for it")
+        .tag("wormhole-scan")
+        .response::<200, Json<CrawlReport>>()
+        .response::<400, ()>()
+        .response::<500, ()>()
+}
+
 fn decode_vaa_docs(op: TransformOperation) -> TransformOperation {
-    op.description("Decode a base64-encoded VAA")
+    op.description("Decode a VAA")
         .tag("wormhole-observer")
-        .response::<200, ()>()
+        .response::<200, Json<DecodedVaa>>()
         .response::<400, ()>()
-        .description("Submit a base64/binary-encoded VAA to decode and analyze its contents.")
-}
\ No newline at end of file
+        .description("Submit a VAA to decode, analyze its contents and verify its guardian signatures. \
+            VAAs can be sent as JSON or text in base64, URL-safe base64 or hex (encoding detected unless \
+            `format` is set), or as raw `application/octet-stream` bytes. A JSON list of VAAs, `vaas`, \
+            or several lines of text are decoded one by one, each with its own result or error \
+            (`DecodeBatchResponse`). Errors carry `details`: their kind and, for malformed VAAs, the \
+            byte offset and field at fault. With `strict`, VAAs that parse but that the Core Bridge \
+            would refuse are rejected too.")
+}
+fn encode_vaa_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Assemble a VAA from its fields, the inverse of the decode endpoint. Signatures are \
+            included as given, and the body can be signed with the devnet guardian keys to produce \
+            synthetic fixtures. A decoded VAA can be submitted back as is.")
+        .tag("wormhole-observer")
+        .response::<200, Json<EncodedVaa>>()
+        .response::<400, ()>()
+}
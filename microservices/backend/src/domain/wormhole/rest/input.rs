@@ -0,0 +1,104 @@
+// Body of the decode endpoint: JSON, pasted text or a raw binary VAA
+
+use aide::{
+    generate::GenContext,
+    openapi::{MediaType, Operation, RequestBody, SchemaObject},
+    OperationInput,
+};
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use super::vaa::{DecodeVaaBody, VaaFormat};
+
+/// VAAs to decode, read according to the request's content type.
+#[derive(Debug)]
+pub enum VaaInput {
+    /// One VAA as text, in `format` or detected
+    Text { vaa: String, format: Option<VaaFormat> },
+    /// Several VAAs as text, decoded one by one
+    Batch { vaas: Vec<String>, format: Option<VaaFormat> },
+    /// One VAA uploaded as is
+    Binary(Bytes),
+}
+
+impl<S: Send + Sync> FromRequest<S> for VaaInput {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase());
+        let body = Bytes::from_request(req, state).await.map_err(IntoResponse::into_response)?;
+
+        match content_type.as_deref() {
+            Some("application/octet-stream") => Ok(Self::Binary(body)),
+            Some("text/plain") => from_text(&body),
+            Some(content_type) if content_type.ends_with("json") => from_json(&body),
+            // Pasted without a content type, e.g. `curl --data-binary @vaa.txt`
+            _ => from_json(&body).or_else(|_| from_text(&body)),
+        }
+        .map_err(bad_request)
+    }
+}
+
+fn from_json(body: &[u8]) -> Result<VaaInput, String> {
+    let body: DecodeVaaBody = serde_json::from_slice(body)
+        .map_err(|e| format!("Invalid decode request: {}", e))?;
+    Ok(match body {
+        DecodeVaaBody::Single(request) => VaaInput::Text { vaa: request.vaa, format: request.format },
+        DecodeVaaBody::Batch(request) => VaaInput::Batch { vaas: request.vaas, format: request.format },
+        DecodeVaaBody::List(vaas) => VaaInput::Batch { vaas, format: None },
+    })
+}
+
+// One VAA per line, several lines making a batch
+fn from_text(body: &[u8]) -> Result<VaaInput, String> {
+    let text = std::str::from_utf8(body)
+        .map_err(|_| "Body is not text, upload binary VAAs as application/octet-stream".to_string())?;
+    let mut vaas: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    match vaas.len() {
+        0 => Err("Empty VAA".to_string()),
+        1 => Ok(VaaInput::Text { vaa: vaas.remove(0), format: None }),
+        _ => Ok(VaaInput::Batch { vaas, format: None }),
+    }
+}
+
+fn bad_request(error: String) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response()
+}
+
+impl OperationInput for VaaInput {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        let schema = ctx.schema.subschema_for::<DecodeVaaBody>();
+        let text = ctx.schema.subschema_for::<String>();
+        let media_type = |schema| MediaType {
+            schema: Some(SchemaObject { json_schema: schema, example: None, external_docs: None }),
+            ..Default::default()
+        };
+        operation.request_body = Some(aide::openapi::ReferenceOr::Item(RequestBody {
+            description: Some("A VAA or a list of VAAs as JSON, VAAs as text (one per line) or a raw binary VAA".to_string()),
+            content: [
+                ("application/json".to_string(), media_type(schema)),
+                ("text/plain".to_string(), media_type(text)),
+                ("application/octet-stream".to_string(), MediaType::default()),
+            ]
+            .into_iter()
+            .collect(),
+            required: true,
+            extensions: Default::default(),
+        }));
+    }
+}
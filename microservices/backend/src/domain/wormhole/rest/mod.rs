@@ -1,5 +1,7 @@
 pub mod client;
+pub mod crawl;
 pub mod handlers;
+pub mod input;
 pub mod vaa;
 
 pub use client::RestClient;
@@ -1,12 +1,19 @@
-use std::collections::HashMap;
-use std::io::{Cursor, Read};
-use base64::engine::general_purpose::STANDARD;
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD};
+use base64::engine::DecodePaddingMode;
 use base64::Engine;
-use byteorder::{BigEndian, ReadBytesExt};
 use serde::{Serialize, Deserialize};
-use super::super::models::{VaaResponse, VaaMetadata, SequenceGap};
+use super::super::builder::VaaBuilder;
+use super::super::chains;
+use super::super::models::{DigestCheck, VaaResponse};
+use super::super::sequences::SequenceTracker;
+use super::super::guardians::{GuardianSet, GuardianSetRegistry};
+use super::super::payloads::{decode_payload, DecodedPayload};
+use super::super::signatures::recover_address;
+use super::super::vaa::{BatchVaa, ParsedVaa, Vaa, VaaDecodeError, VaaSignature};
 use schemars::JsonSchema;
 use aide::OperationOutput;
+use tracing::warn;
 
 pub fn analyze_sequences(response: &mut VaaResponse) {
     if response.data.is_empty() {
@@ -16,67 +23,57 @@ pub fn analyze_sequences(response: &mut VaaResponse) {
     // Sort VAAs by sequence number
     response.data.sort_by_key(|vaa| vaa.sequence);
 
-    let mut sequence_counts: HashMap<u64, usize> = HashMap::new();
-    let mut duplicated_sequences = Vec::new();
-    let mut sequence_gaps = Vec::new();
-
-    // Ocurrences of each sequence number
+    // Duplicates, gaps and totals
+    let mut tracker = SequenceTracker::new();
     for vaa in &response.data {
-        *sequence_counts.entry(vaa.sequence).or_insert(0) += 1;
+        tracker.observe(vaa.sequence);
     }
 
-    // Duplicates
-    for (&sequence, &count) in &sequence_counts {
-        if count > 1 {
-            duplicated_sequences.push(sequence);
-        }
-    }
+    response.metadata = tracker.metadata();
+}
 
-    // Gaps
-    let lowest_sequence = sequence_counts.keys().min().copied();
-    let highest_sequence = sequence_counts.keys().max().copied();
-
-    if let (Some(lowest), Some(highest)) = (lowest_sequence, highest_sequence) {
-        let mut current = lowest;
-        while current < highest {
-            if !sequence_counts.contains_key(&current) {
-                let mut gap_end = current + 1;
-                while gap_end < highest && !sequence_counts.contains_key(&gap_end) {
-                    gap_end += 1;
-                }
-                
-                sequence_gaps.push(SequenceGap {
-                    from: current,
-                    to: gap_end - 1,
-                    size: gap_end - current,
-                });
-                
-                current = gap_end;
-            } else {
-                current += 1;
-            }
-        }
+/// Fills in the human-readable chain name of every VAA returned by Wormholescan.
+pub fn name_chains(response: &mut VaaResponse) {
+    for doc in &mut response.data {
+        doc.emitter_chain_name = chains::name(doc.emitter_chain);
     }
+}
 
-    // Totals (before moving the vectors)
-    let total_items = response.data.len();
-    let total_duplicates = duplicated_sequences.len();
-    let total_gaps = sequence_gaps.len();
+/// Computes the digest of every VAA returned by Wormholescan, filling in the
+/// missing ones, and records the outcome in each VAA's `digest_check`.
+pub fn verify_digests(response: &mut VaaResponse) {
+    for doc in &mut response.data {
+        let computed = match STANDARD.decode(&doc.vaa).map_err(|e| e.to_string())
+            .and_then(|bytes| Vaa::parse(&bytes).map_err(|e| e.to_string()))
+        {
+            Ok(vaa) => hex::encode(vaa.digest()),
+            Err(e) => {
+                warn!("Failed to parse VAA {}: {}", doc.id, e);
+                doc.digest_check = Some(DigestCheck::Unparseable);
+                continue;
+            }
+        };
 
-    response.metadata = VaaMetadata {
-        total_items,
-        total_duplicates,
-        duplicated_sequences,
-        lowest_sequence,
-        highest_sequence,
-        total_gaps,
-        sequence_gaps,
-    };
+        doc.digest_check = Some(match &doc.digest {
+            Some(digest) if !digest.eq_ignore_ascii_case(&computed) => {
+                warn!("Digest mismatch for VAA {}: indexed {}, computed {}", doc.id, digest, computed);
+                DigestCheck::Mismatch
+            }
+            Some(_) => DigestCheck::Verified,
+            None => {
+                doc.digest = Some(computed);
+                DigestCheck::Computed
+            }
+        });
+    }
 }
 
-#[derive(Debug, Serialize, JsonSchema)]
+/// A decoded VAA. Batch VAAs (version 2) carry no envelope of their own: their
+/// envelope fields are zero, `message_id` is empty and their messages are in `batch`.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct DecodedVaa {
     // Header
+    /// 1 for a single message, 2 for a batch, 3 for an observation of a batch
     pub version: u8,
     pub guardian_set_index: u32,
     pub signatures: Vec<GuardianSignature>,
@@ -84,82 +81,759 @@ pub struct DecodedVaa {
     pub timestamp: u32,
     pub nonce: u32,
     pub emitter_chain: u16,
+    pub emitter_chain_name: Option<String>,
     pub emitter_address: String,
     pub sequence: u64,
     pub consistency_level: u8,
     pub payload: Option<String>,
+    /// Payload decoded by the decoder of a well-known application (Token Bridge,
+    /// governance, NTT or CCTP), next to its raw hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoded_payload: Option<DecodedPayload>,
+    // Identifiers
+    /// keccak256(keccak256(body)), hex-encoded as Wormholescan's `digest`
+    pub digest: String,
+    /// keccak256(body), hex-encoded
+    pub body_hash: String,
+    /// Canonical `chain/emitter/sequence` message ID
+    pub message_id: String,
+    // Signature verification against the guardian set
+    pub verification: SignatureVerification,
+    /// Observation hashes and observations of a batch VAA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<DecodedBatch>,
 }
 
 impl OperationOutput for DecodedVaa {
     type Inner = Self;
 }
 
-#[derive(Debug, Serialize, JsonSchema)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DecodedBatch {
+    /// Hex-encoded digest of every observation of the batch, whose double
+    /// keccak256 is the batch's `digest`
+    pub observation_hashes: Vec<String>,
+    pub observations: Vec<DecodedObservation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DecodedObservation {
+    /// Position of the observation's hash in `observation_hashes`
+    pub index: u8,
+    /// Whether the observation's digest is the hash listed at `index`, which the
+    /// batch signatures then cover
+    pub hash_matches: bool,
+    /// The observation as a headless VAA (version 3), verified through the
+    /// batch signatures, reaching quorum only when `hash_matches`
+    pub vaa: DecodedVaa,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct GuardianSignature {
     pub index: u8,
     pub signature: String,
+    /// Address recovered from the signature, if recoverable
+    pub signer: Option<String>,
+    /// Whether the signer matches the guardian at `index` in the guardian set
+    pub is_valid: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SignatureVerification {
+    /// Whether the guardian set for `guardian_set_index` is known
+    pub guardian_set_known: bool,
+    /// Whether the guardian set had expired by the VAA's timestamp, its
+    /// signatures then never reach quorum
+    pub guardian_set_expired: bool,
+    pub guardian_set_size: usize,
+    pub required_signatures: usize,
+    pub valid_signatures: usize,
+    pub quorum_reached: bool,
+}
+
+/// Checks every signature against `guardian_set`, counting each guardian once.
+/// Signatures of an `expired` set are checked but never reach quorum.
+pub fn verify_signatures(
+    signatures: &mut [GuardianSignature],
+    recovered: &[Option<[u8; 20]>],
+    guardian_set: Option<&GuardianSet>,
+    expired: bool,
+) -> SignatureVerification {
+    let Some(guardian_set) = guardian_set else {
+        return SignatureVerification {
+            guardian_set_known: false,
+            guardian_set_expired: false,
+            guardian_set_size: 0,
+            required_signatures: 0,
+            valid_signatures: 0,
+            quorum_reached: false,
+        };
+    };
+
+    let mut valid_guardians = std::collections::HashSet::new();
+    for (signature, signer) in signatures.iter_mut().zip(recovered) {
+        signature.is_valid = match (signer, guardian_set.address(signature.index)) {
+            (Some(signer), Some(expected)) => *signer == expected,
+            _ => false,
+        };
+        if signature.is_valid {
+            valid_guardians.insert(signature.index);
+        }
+    }
+
+    let required_signatures = guardian_set.quorum();
+    SignatureVerification {
+        guardian_set_known: true,
+        guardian_set_expired: expired,
+        guardian_set_size: guardian_set.addresses.len(),
+        required_signatures,
+        valid_signatures: valid_guardians.len(),
+        quorum_reached: !expired && valid_guardians.len() >= required_signatures,
+    }
+}
+
+/// Most VAAs decoded in one request
+pub const MAX_DECODE_BATCH: usize = 1_000;
+
+// Pasted base64 often loses its padding
+const PAD_INDIFFERENT: GeneralPurposeConfig = GeneralPurposeConfig::new()
+    .with_decode_padding_mode(DecodePaddingMode::Indifferent);
+const BASE64: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, PAD_INDIFFERENT);
+const BASE64_URL: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, PAD_INDIFFERENT);
+
+/// Encoding of a VAA in a decode request.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VaaFormat {
+    Base64,
+    /// URL-safe base64 (`-` and `_`)
+    Base64Url,
+    /// Hex, with or without a leading 0x
+    Hex,
+    /// Raw bytes, uploaded as `application/octet-stream`
+    Binary,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct DecodeVaaRequest {
     pub vaa: String,
+    /// Encoding of `vaa`, detected when unset
+    pub format: Option<VaaFormat>,
 }
 
-pub fn decode_vaa(base64_vaa: &str) -> Result<DecodedVaa, Box<dyn std::error::Error>> {
-    let vaa_bytes = STANDARD.decode(base64_vaa)?;
-    let mut cursor = Cursor::new(&vaa_bytes);
-
-    // 1. Parse Header
-    let version = cursor.read_u8()?;
-    let guardian_set_index = cursor.read_u32::<BigEndian>()?;
-    let num_signatures = cursor.read_u8()?;
-
-    // 2. Parse Signatures
-    let mut signatures = Vec::with_capacity(num_signatures as usize);
-    for _ in 0..num_signatures {
-        let guardian_index = cursor.read_u8()?;
-        let mut signature = [0u8; 65];
-        cursor.read_exact(&mut signature)?;
-        
-        signatures.push(GuardianSignature {
-            index: guardian_index,
-            signature: hex::encode(signature),
-        });
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DecodeVaaBatchRequest {
+    /// At most 1000 VAAs
+    pub vaas: Vec<String>,
+    /// Encoding of every VAA, detected one by one when unset
+    pub format: Option<VaaFormat>,
+}
+
+/// JSON body of the decode endpoint.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum DecodeVaaBody {
+    Single(DecodeVaaRequest),
+    Batch(DecodeVaaBatchRequest),
+    /// VAAs whose encoding is detected one by one
+    List(Vec<String>),
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DecodeBatchResponse {
+    pub decoded: usize,
+    pub failed: usize,
+    /// One result per VAA, in request order
+    pub results: Vec<DecodeBatchItem>,
+}
+
+impl OperationOutput for DecodeBatchResponse {
+    type Inner = Self;
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DecodeBatchItem {
+    pub index: usize,
+    /// Encoding the VAA was decoded from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<VaaFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vaa: Option<DecodedVaa>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where and why the VAA failed to decode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<VaaDecodeError>,
+}
+
+/// Parses a VAA pasted as text, in `format` or else in the first encoding it
+/// looks like that yields a well-formed VAA. Whitespace and surrounding quotes
+/// are ignored.
+pub fn parse_vaa_text(input: &str, format: Option<VaaFormat>) -> Result<(ParsedVaa, VaaFormat), VaaDecodeError> {
+    let compact: String = input
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if compact.is_empty() {
+        return Err(VaaDecodeError::Empty);
     }
 
-    // 3. Parse Body (Envelope)
-    let timestamp = cursor.read_u32::<BigEndian>()?;  // 4 bytes per spec
-    let nonce = cursor.read_u32::<BigEndian>()?;
-    let emitter_chain = cursor.read_u16::<BigEndian>()?;
-    
-    let mut emitter_address = [0u8; 32];
-    cursor.read_exact(&mut emitter_address)?;
-    
-    let sequence = cursor.read_u64::<BigEndian>()?;
-    let consistency_level = cursor.read_u8()?;
-
-    // 4. Get remaining bytes as payload (if any)
-    let mut payload = Vec::new();
-    cursor.read_to_end(&mut payload)?;
-    let payload = if !payload.is_empty() {
-        Some(hex::encode(payload))
+    let candidates = match format {
+        Some(format) => vec![format],
+        None => detect_formats(&compact),
+    };
+    let mut first_error = None;
+    for format in candidates {
+        match decode_text(&compact, format).and_then(|bytes| ParsedVaa::parse(&bytes)) {
+            Ok(vaa) => return Ok((vaa, format)),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(first_error.unwrap_or_else(|| VaaDecodeError::InvalidEncoding { message: "Unrecognized VAA encoding".to_string() }))
+}
+
+// Encodings the text may be in, most likely first
+fn detect_formats(text: &str) -> Vec<VaaFormat> {
+    if text.starts_with("0x") || text.starts_with("0X") {
+        return vec![VaaFormat::Hex];
+    }
+    let mut formats = Vec::new();
+    if text.len().is_multiple_of(2) && text.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        formats.push(VaaFormat::Hex);
+    }
+    if text.contains(['-', '_']) {
+        formats.push(VaaFormat::Base64Url);
     } else {
-        None
+        formats.push(VaaFormat::Base64);
+    }
+    formats
+}
+
+fn decode_text(text: &str, format: VaaFormat) -> Result<Vec<u8>, VaaDecodeError> {
+    match format {
+        VaaFormat::Base64 => BASE64.decode(text).map_err(|e| format!("Invalid base64: {}", e)),
+        VaaFormat::Base64Url => BASE64_URL.decode(text).map_err(|e| format!("Invalid base64url: {}", e)),
+        VaaFormat::Hex => {
+            let text = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+            hex::decode(text).map_err(|e| format!("Invalid hex: {}", e))
+        }
+        VaaFormat::Binary => Err("Binary VAAs must be uploaded as application/octet-stream".to_string()),
+    }
+    .map_err(|message| VaaDecodeError::InvalidEncoding { message })
+}
+
+/// A VAA to assemble, in the shape of a `DecodedVaa`, so decoded VAAs can be
+/// encoded back as they are or after editing some fields.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EncodeVaaRequest {
+    #[serde(default = "default_version")]
+    pub version: u8,
+    #[serde(default)]
+    pub guardian_set_index: u32,
+    /// Signatures included as they are, valid or not
+    #[serde(default)]
+    pub signatures: Vec<EncodeSignature>,
+    #[serde(default)]
+    pub timestamp: u32,
+    #[serde(default)]
+    pub nonce: u32,
+    pub emitter_chain: u16,
+    /// Hex-encoded 32-byte emitter address, the leading 0x is optional
+    pub emitter_address: String,
+    pub sequence: u64,
+    #[serde(default = "default_consistency_level")]
+    pub consistency_level: u8,
+    /// Hex-encoded payload
+    pub payload: Option<String>,
+    /// Also sign the body with the devnet guardian keys, only on devnet
+    #[serde(default)]
+    pub sign_with_devnet_guardians: bool,
+}
+
+fn default_version() -> u8 {
+    1
+}
+
+fn default_consistency_level() -> u8 {
+    1
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EncodeSignature {
+    pub index: u8,
+    /// Hex-encoded 65-byte `r || s || v` signature
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct EncodedVaa {
+    /// Base64-encoded VAA, as accepted by the decode endpoint
+    pub vaa: String,
+    /// The encoded VAA decoded again, with its signatures verified
+    pub decoded: DecodedVaa,
+}
+
+impl OperationOutput for EncodedVaa {
+    type Inner = Self;
+}
+
+pub fn encode_vaa(
+    request: &EncodeVaaRequest,
+    guardian_sets: &GuardianSetRegistry,
+) -> Result<EncodedVaa, Box<dyn std::error::Error>> {
+    if request.sign_with_devnet_guardians && guardian_sets.network() != "devnet" {
+        return Err(format!(
+            "Signing with the devnet guardian keys is only allowed on devnet, not on {}", guardian_sets.network()
+        ).into());
+    }
+    let emitter_address: [u8; 32] = hex::decode(request.emitter_address.trim_start_matches("0x"))?
+        .try_into()
+        .map_err(|_| "Invalid emitter address: expected 32 hex-encoded bytes")?;
+    let payload = match &request.payload {
+        Some(payload) => hex::decode(payload.trim_start_matches("0x"))?,
+        None => Vec::new(),
     };
 
-    // Format emitter_address as hex, preserving all 32 bytes
-    let emitter_address = format!("0x{}", hex::encode(emitter_address));
-
-    Ok(DecodedVaa {
-        version,
-        guardian_set_index,
-        signatures,
-        timestamp,
-        nonce,
-        emitter_chain,
-        emitter_address,
-        sequence,
-        consistency_level,
-        payload,
+    let mut builder = VaaBuilder::new(request.emitter_chain, emitter_address, request.sequence)
+        .version(request.version)
+        .guardian_set_index(request.guardian_set_index)
+        .timestamp(request.timestamp)
+        .nonce(request.nonce)
+        .consistency_level(request.consistency_level)
+        .payload(payload);
+    for signature in &request.signatures {
+        let bytes: [u8; 65] = hex::decode(signature.signature.trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| format!("Invalid signature of guardian {}: expected 65 hex-encoded bytes", signature.index))?;
+        builder = builder.signature(signature.index, bytes);
+    }
+    if request.sign_with_devnet_guardians {
+        builder = builder.devnet_signers();
+    }
+
+    let vaa = builder.build()?;
+    Ok(EncodedVaa {
+        vaa: STANDARD.encode(vaa.to_bytes()),
+        decoded: DecodedVaa::from_vaa(&vaa, guardian_sets),
     })
 }
+
+/// Decodes a VAA encoded as `format`, or in whichever encoding it is when unset.
+/// In strict mode, VAAs the Core Bridge would refuse are rejected, see `Vaa::check_strict`.
+pub fn decode_vaa(
+    vaa: &str,
+    format: Option<VaaFormat>,
+    strict: bool,
+    guardian_sets: &GuardianSetRegistry,
+) -> Result<DecodedVaa, VaaDecodeError> {
+    let (vaa, _) = parse_vaa_text(vaa, format)?;
+    check(&vaa, strict, guardian_sets)?;
+    Ok(DecodedVaa::from_parsed(&vaa, guardian_sets))
+}
+
+/// Decodes every VAA on its own, a malformed one failing alone.
+pub fn decode_vaa_batch(
+    vaas: &[String],
+    format: Option<VaaFormat>,
+    strict: bool,
+    guardian_sets: &GuardianSetRegistry,
+) -> DecodeBatchResponse {
+    let results: Vec<DecodeBatchItem> = vaas
+        .iter()
+        .enumerate()
+        .map(|(index, vaa)| {
+            let decoded = parse_vaa_text(vaa, format)
+                .and_then(|(vaa, format)| check(&vaa, strict, guardian_sets).map(|_| (vaa, format)));
+            match decoded {
+                Ok((vaa, format)) => DecodeBatchItem {
+                    index,
+                    format: Some(format),
+                    vaa: Some(DecodedVaa::from_parsed(&vaa, guardian_sets)),
+                    error: None,
+                    details: None,
+                },
+                Err(e) => DecodeBatchItem {
+                    index,
+                    format: None,
+                    vaa: None,
+                    error: Some(e.to_string()),
+                    details: Some(e),
+                },
+            }
+        })
+        .collect();
+    let decoded = results.iter().filter(|result| result.vaa.is_some()).count();
+    DecodeBatchResponse { decoded, failed: results.len() - decoded, results }
+}
+
+pub fn decode_vaa_bytes(
+    vaa_bytes: &[u8],
+    strict: bool,
+    guardian_sets: &GuardianSetRegistry,
+) -> Result<DecodedVaa, VaaDecodeError> {
+    if vaa_bytes.is_empty() {
+        return Err(VaaDecodeError::Empty);
+    }
+    let vaa = ParsedVaa::parse(vaa_bytes)?;
+    check(&vaa, strict, guardian_sets)?;
+    Ok(DecodedVaa::from_parsed(&vaa, guardian_sets))
+}
+
+// Strict checks, against the VAA's guardian set when it is known
+fn check(vaa: &ParsedVaa, strict: bool, guardian_sets: &GuardianSetRegistry) -> Result<(), VaaDecodeError> {
+    if !strict {
+        return Ok(());
+    }
+    let guardian_set_size = guardian_sets.get(vaa.guardian_set_index()).map(|set| set.addresses.len());
+    vaa.check_strict(guardian_set_size)
+}
+
+// Recovers the signer of every signature over `digest` and checks them against the
+// guardian set, as it stood at unix time `timestamp`
+fn recover_signatures(
+    digest: &[u8; 32],
+    vaa_signatures: &[VaaSignature],
+    guardian_set_index: u32,
+    timestamp: u32,
+    guardian_sets: &GuardianSetRegistry,
+) -> (Vec<GuardianSignature>, SignatureVerification) {
+    let recovered: Vec<Option<[u8; 20]>> = vaa_signatures
+        .iter()
+        .map(|signature| recover_address(digest, &signature.signature).ok())
+        .collect();
+
+    let mut signatures: Vec<GuardianSignature> = vaa_signatures
+        .iter()
+        .zip(&recovered)
+        .map(|(signature, signer)| GuardianSignature {
+            index: signature.guardian_index,
+            signature: hex::encode(signature.signature),
+            signer: signer.map(|address| format!("0x{}", hex::encode(address))),
+            is_valid: false,
+        })
+        .collect();
+
+    let guardian_set = guardian_sets.get(guardian_set_index);
+    let expired = guardian_set.is_some() && !guardian_sets.is_valid_at(guardian_set_index, timestamp);
+    let verification = verify_signatures(&mut signatures, &recovered, guardian_set.as_ref(), expired);
+    (signatures, verification)
+}
+
+impl DecodedVaa {
+    pub fn from_parsed(vaa: &ParsedVaa, guardian_sets: &GuardianSetRegistry) -> Self {
+        match vaa {
+            ParsedVaa::Single(vaa) => Self::from_vaa(vaa, guardian_sets),
+            ParsedVaa::Batch(batch) => Self::from_batch(batch, guardian_sets),
+        }
+    }
+
+    pub fn from_vaa(vaa: &Vaa, guardian_sets: &GuardianSetRegistry) -> Self {
+        // Guardians sign the double keccak256 of the body
+        let digest = vaa.digest();
+        let (signatures, verification) = recover_signatures(
+            &digest,
+            &vaa.signatures,
+            vaa.guardian_set_index,
+            vaa.timestamp,
+            guardian_sets,
+        );
+
+        let payload = if !vaa.payload.is_empty() {
+            Some(hex::encode(&vaa.payload))
+        } else {
+            None
+        };
+
+        let decoded_payload = decode_payload(
+            guardian_sets.network(),
+            vaa.emitter_chain,
+            &vaa.emitter_address,
+            &vaa.payload,
+        );
+
+        Self {
+            version: vaa.version,
+            guardian_set_index: vaa.guardian_set_index,
+            signatures,
+            timestamp: vaa.timestamp,
+            nonce: vaa.nonce,
+            emitter_chain: vaa.emitter_chain,
+            emitter_chain_name: chains::name(vaa.emitter_chain),
+            // Format emitter_address as hex, preserving all 32 bytes
+            emitter_address: format!("0x{}", hex::encode(vaa.emitter_address)),
+            sequence: vaa.sequence,
+            consistency_level: vaa.consistency_level,
+            payload,
+            decoded_payload,
+            digest: hex::encode(digest),
+            body_hash: hex::encode(vaa.body_hash()),
+            message_id: vaa.message_id().to_string(),
+            verification,
+            batch: None,
+        }
+    }
+
+    pub fn from_batch(batch: &BatchVaa, guardian_sets: &GuardianSetRegistry) -> Self {
+        // Guardians sign the double keccak256 of the observation hashes
+        let digest = batch.digest();
+        // The batch was signed after its newest observation
+        let timestamp = batch.observations
+            .iter()
+            .map(|observation| observation.observation.timestamp)
+            .max()
+            .unwrap_or(0);
+        let (signatures, verification) = recover_signatures(
+            &digest,
+            &batch.signatures,
+            batch.guardian_set_index,
+            timestamp,
+            guardian_sets,
+        );
+
+        let observations = batch.observations
+            .iter()
+            .map(|observation| {
+                let hash_matches = batch.is_listed(observation);
+                let mut vaa = Self::from_vaa(&observation.observation, guardian_sets);
+                vaa.verification = SignatureVerification {
+                    quorum_reached: verification.quorum_reached && hash_matches,
+                    ..verification.clone()
+                };
+                DecodedObservation { index: observation.index, hash_matches, vaa }
+            })
+            .collect();
+
+        Self {
+            version: batch.version,
+            guardian_set_index: batch.guardian_set_index,
+            signatures,
+            timestamp: 0,
+            nonce: 0,
+            emitter_chain: 0,
+            emitter_chain_name: None,
+            emitter_address: format!("0x{}", hex::encode([0u8; 32])),
+            sequence: 0,
+            consistency_level: 0,
+            payload: None,
+            decoded_payload: None,
+            digest: hex::encode(digest),
+            body_hash: hex::encode(batch.body_hash()),
+            message_id: String::new(),
+            verification,
+            batch: Some(DecodedBatch {
+                observation_hashes: batch.hashes.iter().map(hex::encode).collect(),
+                observations,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::guardians::GuardianSetSource;
+    use super::super::super::guardians::registry::GUARDIAN_SET_EXPIRY_SECS;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+    fn devnet_vaa() -> Vec<u8> {
+        VaaBuilder::new(2, [0x11; 32], 7)
+            .payload(b"hello".to_vec())
+            .devnet_signers()
+            .to_bytes()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_vaa_text_detects_format() {
+        let bytes = devnet_vaa();
+        let inputs = [
+            (hex::encode(&bytes), VaaFormat::Hex),
+            (format!("0x{}", hex::encode(&bytes)), VaaFormat::Hex),
+            (STANDARD.encode(&bytes), VaaFormat::Base64),
+            (URL_SAFE_NO_PAD.encode(&bytes), VaaFormat::Base64Url),
+            // Wrapped and quoted when pasted
+            (format!("\"{}\"", STANDARD.encode(&bytes).trim_end_matches('=')), VaaFormat::Base64),
+        ];
+        for (input, expected) in inputs {
+            let (ParsedVaa::Single(vaa), format) = parse_vaa_text(&input, None).unwrap() else {
+                panic!("Expected a single VAA: {}", input);
+            };
+            assert_eq!(format, expected, "{}", input);
+            assert_eq!(vaa.to_bytes(), bytes);
+        }
+
+        assert!(parse_vaa_text(&hex::encode(&bytes), Some(VaaFormat::Base64Url)).is_err());
+        assert!(parse_vaa_text("  ", None).is_err());
+    }
+
+    #[test]
+    fn test_encode_vaa_signs_on_devnet_only() {
+        let request: EncodeVaaRequest = serde_json::from_value(serde_json::json!({
+            "emitter_chain": 2,
+            "emitter_address": "11".repeat(32),
+            "sequence": 7,
+            "sign_with_devnet_guardians": true,
+        }))
+        .unwrap();
+
+        let devnet = GuardianSetRegistry::bundled("devnet").unwrap();
+        assert!(encode_vaa(&request, &devnet).unwrap().decoded.verification.quorum_reached);
+        let mainnet = GuardianSetRegistry::bundled("mainnet").unwrap();
+        assert!(encode_vaa(&request, &mainnet).is_err());
+    }
+
+    #[test]
+    fn test_expired_guardian_set_misses_quorum() {
+        let registry = GuardianSetRegistry::bundled("devnet").unwrap();
+        let upgrade = GuardianSet::new(
+            1,
+            vec!["0x0000000000000000000000000000000000000001".to_string()],
+            GuardianSetSource::Governance,
+        );
+        registry.insert(upgrade, 1_000);
+
+        let signed_at = |timestamp| {
+            let vaa = VaaBuilder::new(2, [0x11; 32], 1).timestamp(timestamp).devnet_signers().build().unwrap();
+            DecodedVaa::from_vaa(&vaa, &registry).verification
+        };
+        let verification = signed_at(1_000 + GUARDIAN_SET_EXPIRY_SECS - 1);
+        assert!(verification.quorum_reached && !verification.guardian_set_expired);
+        let verification = signed_at(1_000 + GUARDIAN_SET_EXPIRY_SECS);
+        assert_eq!(verification.valid_signatures, 1);
+        assert!(!verification.quorum_reached && verification.guardian_set_expired);
+    }
+
+    // A batch of two observations signed by the devnet guardian, carrying `carried` of them
+    fn devnet_batch(carried: &[usize]) -> Vec<u8> {
+        use super::super::super::builder::devnet_guardian_keys;
+        use super::super::super::signatures::{sign_digest, vaa_digest};
+
+        let bodies: Vec<Vec<u8>> = (0..2)
+            .map(|sequence| VaaBuilder::new(2, [0x11; 32], sequence).payload(b"hello".to_vec()).body())
+            .collect();
+        let hashes: Vec<[u8; 32]> = bodies.iter().map(|body| vaa_digest(body)).collect();
+        let signature = sign_digest(&devnet_guardian_keys()[0], &vaa_digest(&hashes.concat())).unwrap();
+
+        let mut bytes = vec![2, 0, 0, 0, 0, 1, 0];
+        bytes.extend_from_slice(&signature);
+        bytes.push(hashes.len() as u8);
+        bytes.extend_from_slice(&hashes.concat());
+        bytes.push(carried.len() as u8);
+        for index in carried {
+            let mut observation = vec![3];
+            observation.extend_from_slice(&bodies[*index]);
+            bytes.push(*index as u8);
+            bytes.extend_from_slice(&(observation.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&observation);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_decode_batch_vaa() {
+        let registry = GuardianSetRegistry::bundled("devnet").unwrap();
+
+        let decoded = decode_vaa_bytes(&devnet_batch(&[0, 1]), true, &registry).unwrap();
+        assert_eq!(decoded.version, 2);
+        assert!(decoded.verification.quorum_reached);
+        let batch = decoded.batch.unwrap();
+        assert_eq!(batch.observation_hashes.len(), 2);
+        for (i, observation) in batch.observations.iter().enumerate() {
+            assert_eq!(observation.index as usize, i);
+            assert!(observation.hash_matches);
+            assert_eq!((observation.vaa.version, observation.vaa.sequence), (3, i as u64));
+            assert_eq!(observation.vaa.digest, batch.observation_hashes[i]);
+            assert!(observation.vaa.verification.quorum_reached);
+        }
+
+        // Partial batches carry some of the hashed observations only
+        let decoded = decode_vaa_bytes(&devnet_batch(&[1]), true, &registry).unwrap();
+        assert_eq!(decoded.batch.unwrap().observations[0].vaa.sequence, 1);
+
+        // An observation placed at another hash's index is not covered by the signatures
+        let mut misplaced = devnet_batch(&[1]);
+        let index_offset = 7 + 65 + 1 + 64 + 1;
+        misplaced[index_offset] = 0;
+        let decoded = decode_vaa_bytes(&misplaced, false, &registry).unwrap();
+        let observation = &decoded.batch.unwrap().observations[0];
+        assert!(!observation.hash_matches && !observation.vaa.verification.quorum_reached);
+        assert!(matches!(
+            decode_vaa_bytes(&misplaced, true, &registry),
+            Err(VaaDecodeError::ObservationHashMismatch { index: 0, offset, .. }) if offset == index_offset + 5
+        ));
+
+        // Truncated observations are located within the batch
+        let batch = devnet_batch(&[0]);
+        let e = decode_vaa_bytes(&batch[..batch.len() - 20], false, &registry).unwrap_err();
+        assert_eq!(e.field(), Some("observations[0].observation"));
+        // An observation length cutting the emitter address short
+        let mut truncated = batch.clone();
+        let len_offset = index_offset + 1;
+        truncated[len_offset..len_offset + 4].copy_from_slice(&32u32.to_be_bytes());
+        let e = decode_vaa_bytes(&truncated, false, &registry).unwrap_err();
+        assert_eq!((e.field(), e.offset()), (Some("observations[0].emitter_address"), Some(len_offset + 4 + 11)));
+    }
+
+    #[test]
+    fn test_decode_vaa_batch_fails_items_alone() {
+        let registry = GuardianSetRegistry::bundled("devnet").unwrap();
+        let bytes = devnet_vaa();
+        let vaas = vec![hex::encode(&bytes), "not a vaa".to_string(), STANDARD.encode(&bytes)];
+
+        let response = decode_vaa_batch(&vaas, None, false, &registry);
+        assert_eq!((response.decoded, response.failed), (2, 1));
+        assert_eq!(response.results[0].format, Some(VaaFormat::Hex));
+        assert!(response.results[1].error.is_some());
+        assert_eq!(response.results[2].vaa.as_ref().unwrap().sequence, 7);
+    }
+
+    #[test]
+    fn test_verify_digests_flags_each_vaa() {
+        let bytes = devnet_vaa();
+        let digest = hex::encode(Vaa::parse(&bytes).unwrap().digest());
+        let doc = |sequence: u64, vaa: String, digest: Option<String>| {
+            serde_json::from_value(serde_json::json!({
+                "sequence": sequence,
+                "id": format!("2/{}/{}", "11".repeat(32), sequence),
+                "version": 1,
+                "emitterChain": 2,
+                "emitterAddr": "11".repeat(32),
+                "emitterNativeAddr": null,
+                "guardianSetIndex": 0,
+                "vaa": vaa,
+                "timestamp": "2025-02-01T01:20:33Z",
+                "updatedAt": "2025-02-01T01:45:11Z",
+                "indexedAt": "2025-02-01T01:45:11Z",
+                "txHash": null,
+                "digest": digest,
+                "isDuplicated": false,
+            }))
+            .unwrap()
+        };
+        let mut response = VaaResponse {
+            metadata: SequenceTracker::new().metadata(),
+            data: vec![
+                doc(1, STANDARD.encode(&bytes), Some(digest.to_uppercase())),
+                doc(2, STANDARD.encode(&bytes), None),
+                doc(3, STANDARD.encode(&bytes), Some("00".repeat(32))),
+                doc(4, STANDARD.encode(b"not a vaa"), Some(digest.clone())),
+            ],
+            pagination: None,
+        };
+
+        verify_digests(&mut response);
+        let checks: Vec<_> = response.data.iter().map(|doc| doc.digest_check).collect();
+        assert_eq!(checks, vec![
+            Some(DigestCheck::Verified),
+            Some(DigestCheck::Computed),
+            Some(DigestCheck::Mismatch),
+            Some(DigestCheck::Unparseable),
+        ]);
+        assert_eq!(response.data[1].digest, Some(digest));
+        assert_eq!(response.data[2].digest, Some("00".repeat(32)));
+    }
+}
@@ -0,0 +1,193 @@
+// Sequence tracking per emitter, shared by the Scan and Spy gap analysis
+
+use std::collections::BTreeSet;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use super::models::{SequenceGap, VaaMetadata};
+
+/// How an observed sequence relates to the ones seen before.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", content = "gap", rename_all = "snake_case")]
+pub enum SequenceObservation {
+    /// First sequence seen, or the one right after the highest or right before the lowest
+    InOrder,
+    /// Skipped ahead of the highest sequence or below the lowest, opening a gap
+    Gap(SequenceGap),
+    /// First sequence after an ingestion outage, the gap may only have been missed by us
+    OutageGap(SequenceGap),
+    /// Arrived late and (partially) closed a gap
+    FilledGap,
+    /// Sequence already seen
+    Duplicate,
+}
+
+/// Tracks the sequences of a single emitter incrementally. Only gaps and
+/// duplicated sequences are kept, so memory doesn't grow with every VAA.
+#[derive(Debug, Clone, Default)]
+pub struct SequenceTracker {
+    total_items: usize,
+    lowest: Option<u64>,
+    highest: Option<u64>,
+    // Sorted, non-overlapping missing ranges between lowest and highest
+    gaps: Vec<SequenceGap>,
+    // Missing ranges that opened across an ingestion outage
+    outage_gaps: Vec<SequenceGap>,
+    // High-water mark when the last outage started, until the next sequence arrives
+    resume_from: Option<u64>,
+    duplicated_sequences: BTreeSet<u64>,
+}
+
+impl SequenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn highest(&self) -> Option<u64> {
+        self.highest
+    }
+
+    pub fn lowest(&self) -> Option<u64> {
+        self.lowest
+    }
+
+    pub fn gaps(&self) -> &[SequenceGap] {
+        &self.gaps
+    }
+
+    pub fn outage_gaps(&self) -> &[SequenceGap] {
+        &self.outage_gaps
+    }
+
+    /// Records that ingestion stopped. A gap opened by the next sequence above
+    /// the current high-water mark is then reported as an outage gap.
+    pub fn mark_outage(&mut self) {
+        if self.resume_from.is_none() {
+            self.resume_from = self.highest;
+        }
+    }
+
+    pub fn observe(&mut self, sequence: u64) -> SequenceObservation {
+        self.total_items += 1;
+
+        let (Some(lowest), Some(highest)) = (self.lowest, self.highest) else {
+            self.lowest = Some(sequence);
+            self.highest = Some(sequence);
+            return SequenceObservation::InOrder;
+        };
+
+        if sequence > highest {
+            self.highest = Some(sequence);
+            let resumed = self.resume_from.take().is_some();
+            if sequence == highest + 1 {
+                return SequenceObservation::InOrder;
+            }
+            let gap = gap(highest + 1, sequence - 1);
+            if resumed {
+                self.outage_gaps.push(gap.clone());
+                return SequenceObservation::OutageGap(gap);
+            }
+            self.gaps.push(gap.clone());
+            return SequenceObservation::Gap(gap);
+        }
+
+        if sequence < lowest {
+            self.lowest = Some(sequence);
+            if sequence + 1 == lowest {
+                return SequenceObservation::InOrder;
+            }
+            let gap = gap(sequence + 1, lowest - 1);
+            self.gaps.insert(0, gap.clone());
+            return SequenceObservation::Gap(gap);
+        }
+
+        if fill(&mut self.gaps, sequence) || fill(&mut self.outage_gaps, sequence) {
+            return SequenceObservation::FilledGap;
+        }
+        self.duplicated_sequences.insert(sequence);
+        SequenceObservation::Duplicate
+    }
+
+    /// Summarizes the tracked sequences in the same shape as `analyze_sequences`.
+    pub fn metadata(&self) -> VaaMetadata {
+        VaaMetadata {
+            total_items: self.total_items,
+            total_duplicates: self.duplicated_sequences.len(),
+            duplicated_sequences: self.duplicated_sequences.iter().copied().collect(),
+            lowest_sequence: self.lowest,
+            highest_sequence: self.highest,
+            sequence_gaps: self.gaps.clone(),
+            total_gaps: self.gaps.len(),
+        }
+    }
+}
+
+fn gap(from: u64, to: u64) -> SequenceGap {
+    SequenceGap { from, to, size: to - from + 1, governed: Vec::new() }
+}
+
+// Removes `sequence` from the gap containing it, splitting the gap if needed
+fn fill(gaps: &mut Vec<SequenceGap>, sequence: u64) -> bool {
+    let Some(position) = gaps.iter().position(|gap| gap.from <= sequence && sequence <= gap.to) else {
+        return false;
+    };
+    let filled = gaps.remove(position);
+    if sequence < filled.to {
+        gaps.insert(position, gap(sequence + 1, filled.to));
+    }
+    if sequence > filled.from {
+        gaps.insert(position, gap(filled.from, sequence - 1));
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaps_open_and_close() {
+        let mut tracker = SequenceTracker::new();
+        assert_eq!(tracker.observe(10), SequenceObservation::InOrder);
+        assert_eq!(tracker.observe(11), SequenceObservation::InOrder);
+        assert_eq!(
+            tracker.observe(16),
+            SequenceObservation::Gap(gap(12, 15))
+        );
+        // A late arrival splits the gap in two
+        assert_eq!(tracker.observe(13), SequenceObservation::FilledGap);
+        assert_eq!(tracker.observe(11), SequenceObservation::Duplicate);
+        // Extending below the lowest sequence opens a gap underneath
+        assert_eq!(tracker.observe(7), SequenceObservation::Gap(gap(8, 9)));
+        assert_eq!(tracker.observe(6), SequenceObservation::InOrder);
+
+        let metadata = tracker.metadata();
+        let gaps: Vec<(u64, u64)> = metadata.sequence_gaps.iter().map(|gap| (gap.from, gap.to)).collect();
+        assert_eq!(gaps, vec![(8, 9), (12, 12), (14, 15)]);
+        assert_eq!(metadata.total_items, 7);
+        assert_eq!(metadata.duplicated_sequences, vec![11]);
+        assert_eq!(metadata.lowest_sequence, Some(6));
+        assert_eq!(metadata.highest_sequence, Some(16));
+    }
+
+    #[test]
+    fn test_outage_gaps_are_kept_apart() {
+        let mut tracker = SequenceTracker::new();
+        tracker.observe(1);
+        tracker.observe(3);
+        tracker.mark_outage();
+        // Late arrivals below the high-water mark don't end the outage
+        tracker.observe(2);
+        assert_eq!(
+            tracker.observe(8),
+            SequenceObservation::OutageGap(gap(4, 7))
+        );
+        // Once resumed, skipped sequences are network gaps again
+        assert!(matches!(tracker.observe(10), SequenceObservation::Gap(_)));
+        assert_eq!(tracker.observe(5), SequenceObservation::FilledGap);
+
+        let ranges = |gaps: &[SequenceGap]| gaps.iter().map(|gap| (gap.from, gap.to)).collect::<Vec<_>>();
+        assert_eq!(ranges(tracker.gaps()), vec![(9, 9)]);
+        assert_eq!(ranges(tracker.outage_gaps()), vec![(4, 4), (6, 7)]);
+    }
+}
@@ -0,0 +1,81 @@
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use sha3::{Digest, Keccak256};
+
+use crate::library::errors::Error;
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// The digest guardians sign: keccak256(keccak256(body)).
+pub fn vaa_digest(body: &[u8]) -> [u8; 32] {
+    keccak256(&keccak256(body))
+}
+
+/// Recovers the Ethereum-style address (last 20 bytes of the keccak256 of the
+/// uncompressed public key) that produced a 65-byte `r || s || v` signature.
+pub fn recover_address(digest: &[u8; 32], signature: &[u8; 65]) -> Result<[u8; 20], Error> {
+    let mut sig = Signature::from_slice(&signature[..64])
+        .map_err(|e| Error::Parsing(format!("Invalid signature: {}", e)))?;
+    let mut recovery_byte = signature[64];
+    // Tolerate legacy Ethereum-style `v` values
+    if recovery_byte >= 27 {
+        recovery_byte -= 27;
+    }
+    let mut recovery_id = RecoveryId::from_byte(recovery_byte)
+        .ok_or_else(|| Error::Parsing(format!("Invalid recovery id: {}", signature[64])))?;
+
+    // k256 only accepts low-S signatures, flip the parity if we had to normalize
+    if let Some(normalized) = sig.normalize_s() {
+        sig = normalized;
+        recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+    }
+
+    let key = VerifyingKey::recover_from_prehash(digest, &sig, recovery_id)
+        .map_err(|e| Error::Parsing(format!("Failed to recover signer: {}", e)))?;
+
+    Ok(public_key_address(&key))
+}
+
+/// Signs a digest the way guardians do, as a 65-byte `r || s || v` signature.
+pub fn sign_digest(key: &SigningKey, digest: &[u8; 32]) -> Result<[u8; 65], Error> {
+    let (signature, recovery_id) = key
+        .sign_prehash_recoverable(digest)
+        .map_err(|e| Error::Parsing(format!("Failed to sign digest: {}", e)))?;
+    let mut bytes = [0u8; 65];
+    bytes[..64].copy_from_slice(&signature.to_bytes());
+    bytes[64] = recovery_id.to_byte();
+    Ok(bytes)
+}
+
+pub fn public_key_address(key: &VerifyingKey) -> [u8; 20] {
+    let point = key.to_encoded_point(false);
+    let hash = keccak256(&point.as_bytes()[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::wormhole::builder::DEVNET_GUARDIAN_KEYS;
+
+    const DEVNET_GUARDIAN_ADDRESS: &str = "befa429d57cd18b7f8a4d91a2da9ab4af05d0fbe";
+
+    #[test]
+    fn test_recover_devnet_guardian() {
+        let key = SigningKey::from_slice(&hex::decode(DEVNET_GUARDIAN_KEYS[0]).unwrap()).unwrap();
+        assert_eq!(hex::encode(public_key_address(key.verifying_key())), DEVNET_GUARDIAN_ADDRESS);
+
+        let digest = vaa_digest(b"some vaa body");
+        let signature = sign_digest(&key, &digest).unwrap();
+        let signer = recover_address(&digest, &signature).unwrap();
+        assert_eq!(hex::encode(signer), DEVNET_GUARDIAN_ADDRESS);
+
+        // A signature over another body must not recover the same guardian
+        let other = vaa_digest(b"another vaa body");
+        let signer = recover_address(&other, &signature).unwrap();
+        assert_ne!(hex::encode(signer), DEVNET_GUARDIAN_ADDRESS);
+    }
+}
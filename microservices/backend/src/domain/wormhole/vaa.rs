@@ -0,0 +1,587 @@
+// Shared VAA model, parsed once and used by both the REST (Scan) and gRPC (Spy) paths
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use crate::library::errors::Error;
+use super::signatures::{keccak256, vaa_digest};
+
+/// Size of the fixed part of the header (version, guardian set index, signature count).
+pub const HEADER_LEN: usize = 6;
+/// Size of each signature entry (guardian index and 65-byte signature).
+pub const SIGNATURE_LEN: usize = 66;
+/// Version of the VAAs carrying a single message.
+pub const VAA_VERSION: u8 = 1;
+/// Version of batch VAAs, signing the hashes of several observations at once.
+pub const BATCH_VAA_VERSION: u8 = 2;
+/// Version of the headless VAAs, without header, a batch VAA carries its observations as.
+pub const HEADLESS_VAA_VERSION: u8 = 3;
+
+/// Why a VAA failed to decode, located down to the byte where possible.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VaaDecodeError {
+    Empty,
+    /// Not valid in the encoding it was given or detected in
+    InvalidEncoding { message: String },
+    /// The VAA ends in the middle of `field`
+    Truncated { field: String, offset: usize, expected: usize, actual: usize },
+    /// Strict mode: a version other than 1 or 2, or other than 3 for an observation
+    UnsupportedVersion { field: String, offset: usize, version: u8, expected: u8 },
+    /// Strict mode: no signature at all
+    NoSignatures { field: String, offset: usize },
+    /// Strict mode: more signatures than guardians in the set
+    TooManySignatures { field: String, offset: usize, count: usize, guardian_set_size: usize },
+    /// Strict mode: a guardian index past the end of the set
+    GuardianIndexOutOfRange { field: String, offset: usize, index: u8, guardian_set_size: usize },
+    /// Strict mode: a guardian signing twice
+    DuplicateGuardianIndex { field: String, offset: usize, index: u8 },
+    /// Strict mode: guardian indexes not in increasing order
+    UnorderedGuardianIndex { field: String, offset: usize, index: u8, previous: u8 },
+    /// Strict mode: a batch observation whose hash is not the one the batch lists at its index
+    ObservationHashMismatch { field: String, offset: usize, index: u8 },
+}
+
+impl VaaDecodeError {
+    /// Offset of the offending byte in the decoded VAA
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::Empty | Self::InvalidEncoding { .. } => None,
+            Self::Truncated { offset, .. }
+            | Self::UnsupportedVersion { offset, .. }
+            | Self::NoSignatures { offset, .. }
+            | Self::TooManySignatures { offset, .. }
+            | Self::GuardianIndexOutOfRange { offset, .. }
+            | Self::DuplicateGuardianIndex { offset, .. }
+            | Self::UnorderedGuardianIndex { offset, .. }
+            | Self::ObservationHashMismatch { offset, .. } => Some(*offset),
+        }
+    }
+
+    /// Name of the offending field, e.g. `signatures[2].guardian_index`
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            Self::Empty | Self::InvalidEncoding { .. } => None,
+            Self::Truncated { field, .. }
+            | Self::UnsupportedVersion { field, .. }
+            | Self::NoSignatures { field, .. }
+            | Self::TooManySignatures { field, .. }
+            | Self::GuardianIndexOutOfRange { field, .. }
+            | Self::DuplicateGuardianIndex { field, .. }
+            | Self::UnorderedGuardianIndex { field, .. }
+            | Self::ObservationHashMismatch { field, .. } => Some(field),
+        }
+    }
+}
+
+impl fmt::Display for VaaDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Empty VAA"),
+            Self::InvalidEncoding { message } => write!(f, "{}", message),
+            Self::Truncated { field, offset, expected, actual } => write!(
+                f, "Malformed VAA: {} at byte {} takes {} bytes, only {} left", field, offset, expected, actual
+            ),
+            Self::UnsupportedVersion { field, version, expected, .. } => write!(
+                f, "Unsupported {} {}, expected {}", field, version, expected
+            ),
+            Self::NoSignatures { .. } => write!(f, "VAA without signatures"),
+            Self::TooManySignatures { count, guardian_set_size, .. } => write!(
+                f, "{} signatures for a guardian set of {}", count, guardian_set_size
+            ),
+            Self::GuardianIndexOutOfRange { field, offset, index, guardian_set_size } => write!(
+                f, "{} at byte {} is guardian {}, outside a guardian set of {}", field, offset, index, guardian_set_size
+            ),
+            Self::DuplicateGuardianIndex { field, offset, index } => write!(
+                f, "{} at byte {} repeats guardian {}", field, offset, index
+            ),
+            Self::UnorderedGuardianIndex { field, offset, index, previous } => write!(
+                f, "{} at byte {} is guardian {}, after guardian {}", field, offset, index, previous
+            ),
+            Self::ObservationHashMismatch { field, offset, index } => write!(
+                f, "{} at byte {} does not hash to the batch's hash {}", field, offset, index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VaaDecodeError {}
+
+impl From<VaaDecodeError> for Error {
+    fn from(e: VaaDecodeError) -> Self {
+        Error::Parsing(e.to_string())
+    }
+}
+
+// Reads the VAA field by field, keeping track of the offset for errors. Batch
+// observations are read on their own, `base` and `prefix` locating them in the batch.
+struct VaaReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    base: usize,
+    prefix: &'a str,
+}
+
+impl<'a> VaaReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0, base: 0, prefix: "" }
+    }
+
+    fn take(&mut self, field: impl FnOnce() -> String, len: usize) -> Result<&'a [u8], VaaDecodeError> {
+        let remaining = &self.bytes[self.offset..];
+        if remaining.len() < len {
+            return Err(VaaDecodeError::Truncated {
+                field: format!("{}{}", self.prefix, field()),
+                offset: self.base + self.offset,
+                expected: len,
+                actual: remaining.len(),
+            });
+        }
+        self.offset += len;
+        Ok(&remaining[..len])
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.bytes[self.offset..];
+        self.offset = self.bytes.len();
+        rest
+    }
+
+    fn array<const N: usize>(&mut self, field: impl FnOnce() -> String) -> Result<[u8; N], VaaDecodeError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(field, N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self, field: &str) -> Result<u8, VaaDecodeError> {
+        Ok(self.take(|| field.to_string(), 1)?[0])
+    }
+
+    fn signatures(&mut self) -> Result<Vec<VaaSignature>, VaaDecodeError> {
+        let num_signatures = self.u8("num_signatures")?;
+        let mut signatures = Vec::with_capacity(num_signatures as usize);
+        for i in 0..num_signatures {
+            let [guardian_index] = self.array(|| format!("signatures[{}].guardian_index", i))?;
+            let signature = self.array(|| format!("signatures[{}].signature", i))?;
+            signatures.push(VaaSignature { guardian_index, signature });
+        }
+        Ok(signatures)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VaaSignature {
+    pub guardian_index: u8,
+    pub signature: [u8; 65],
+}
+
+#[derive(Debug, Clone)]
+pub struct Vaa {
+    // Header
+    pub version: u8,
+    pub guardian_set_index: u32,
+    pub signatures: Vec<VaaSignature>,
+    // Body (Envelope)
+    pub timestamp: u32,
+    pub nonce: u32,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub sequence: u64,
+    pub consistency_level: u8,
+    pub payload: Vec<u8>,
+    // Raw body, the signed part of the VAA
+    body: Vec<u8>,
+}
+
+/// Canonical `chain/emitter/sequence` identifier of a message, as used by Wormholescan.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct MessageId {
+    pub emitter_chain: u16,
+    /// Hex-encoded (no leading 0x) 32-byte emitter address
+    pub emitter_address: String,
+    pub sequence: u64,
+}
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.emitter_chain, self.emitter_address, self.sequence)
+    }
+}
+
+impl Vaa {
+    /// Parses a version 1 VAA that is long enough for its fields, see
+    /// `check_strict` for the checks the Core Bridge would add. Other versions,
+    /// e.g. batches read as a single VAA, are rejected: `ParsedVaa` reads them.
+    pub fn parse(bytes: &[u8]) -> Result<Self, VaaDecodeError> {
+        let vaa = Self::parse_any_version(bytes)?;
+        check_version(VAA_VERSION, vaa.version, "version", 0)?;
+        Ok(vaa)
+    }
+
+    // Single VAA layout whatever the version, for the decode endpoint to show
+    // unknown versions as they are
+    fn parse_any_version(bytes: &[u8]) -> Result<Self, VaaDecodeError> {
+        let mut reader = VaaReader::new(bytes);
+
+        // 1. Parse Header
+        let version = reader.u8("version")?;
+        let guardian_set_index = u32::from_be_bytes(reader.array(|| "guardian_set_index".to_string())?);
+
+        // 2. Parse Signatures
+        let signatures = reader.signatures()?;
+
+        Self::parse_body(reader, version, guardian_set_index, signatures)
+    }
+
+    // A headless VAA: its version then its body, signed as part of a batch
+    fn parse_headless(reader: VaaReader<'_>, guardian_set_index: u32) -> Result<Self, VaaDecodeError> {
+        let mut reader = reader;
+        let version = reader.u8("version")?;
+        Self::parse_body(reader, version, guardian_set_index, Vec::new())
+    }
+
+    fn parse_body(
+        mut reader: VaaReader<'_>,
+        version: u8,
+        guardian_set_index: u32,
+        signatures: Vec<VaaSignature>,
+    ) -> Result<Self, VaaDecodeError> {
+        // 3. Parse Body (Envelope)
+        let body = reader.bytes[reader.offset..].to_vec();
+        let timestamp = u32::from_be_bytes(reader.array(|| "timestamp".to_string())?);
+        let nonce = u32::from_be_bytes(reader.array(|| "nonce".to_string())?);
+        let emitter_chain = u16::from_be_bytes(reader.array(|| "emitter_chain".to_string())?);
+        let emitter_address = reader.array(|| "emitter_address".to_string())?;
+        let sequence = u64::from_be_bytes(reader.array(|| "sequence".to_string())?);
+        let consistency_level = reader.u8("consistency_level")?;
+
+        // 4. Remaining bytes are the payload
+        let payload = reader.rest().to_vec();
+
+        Ok(Self {
+            version,
+            guardian_set_index,
+            signatures,
+            timestamp,
+            nonce,
+            emitter_chain,
+            emitter_address,
+            sequence,
+            consistency_level,
+            payload,
+            body,
+        })
+    }
+
+    /// Rejects VAAs that parse but that the Core Bridge would refuse: another
+    /// version, no signatures, or guardian indexes that are repeated, out of
+    /// order or, when the guardian set is known, outside of it.
+    pub fn check_strict(&self, guardian_set_size: Option<usize>) -> Result<(), VaaDecodeError> {
+        check_version(VAA_VERSION, self.version, "version", 0)?;
+        check_signatures(&self.signatures, guardian_set_size)
+    }
+
+    /// Serializes the VAA back to its wire format, the inverse of `parse`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.signatures.len() * SIGNATURE_LEN + self.body.len());
+        bytes.push(self.version);
+        bytes.extend_from_slice(&self.guardian_set_index.to_be_bytes());
+        bytes.push(self.signatures.len() as u8);
+        for signature in &self.signatures {
+            bytes.push(signature.guardian_index);
+            bytes.extend_from_slice(&signature.signature);
+        }
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// keccak256(body)
+    pub fn body_hash(&self) -> [u8; 32] {
+        keccak256(&self.body)
+    }
+
+    /// keccak256(keccak256(body)), the hash guardians sign and Wormholescan reports as `digest`.
+    pub fn digest(&self) -> [u8; 32] {
+        vaa_digest(&self.body)
+    }
+
+    pub fn message_id(&self) -> MessageId {
+        MessageId {
+            emitter_chain: self.emitter_chain,
+            emitter_address: hex::encode(self.emitter_address),
+            sequence: self.sequence,
+        }
+    }
+}
+
+/// An observation of a batch VAA, at `index` in the batch's hashes.
+#[derive(Debug, Clone)]
+pub struct IndexedObservation {
+    pub index: u8,
+    /// Headless VAA (version 3), without signatures of its own
+    pub observation: Vaa,
+    // Offset of the observation in the batch, for errors
+    offset: usize,
+}
+
+/// Batch VAA (version 2): guardians sign the hashes of several observations at
+/// once, each observation being the body of a message as a headless VAA.
+#[derive(Debug, Clone)]
+pub struct BatchVaa {
+    pub version: u8,
+    pub guardian_set_index: u32,
+    pub signatures: Vec<VaaSignature>,
+    /// Digest of every observation of the batch, in order
+    pub hashes: Vec<[u8; 32]>,
+    /// Observations carried along, possibly only some of those hashed
+    pub observations: Vec<IndexedObservation>,
+}
+
+impl BatchVaa {
+    pub fn parse(bytes: &[u8]) -> Result<Self, VaaDecodeError> {
+        let mut reader = VaaReader::new(bytes);
+
+        // 1. Parse Header and Signatures, as for a single VAA
+        let version = reader.u8("version")?;
+        let guardian_set_index = u32::from_be_bytes(reader.array(|| "guardian_set_index".to_string())?);
+        let signatures = reader.signatures()?;
+
+        // 2. Parse the observation hashes, the part guardians sign
+        let num_hashes = reader.u8("num_hashes")?;
+        let mut hashes = Vec::with_capacity(num_hashes as usize);
+        for i in 0..num_hashes {
+            hashes.push(reader.array(|| format!("hashes[{}]", i))?);
+        }
+
+        // 3. Parse the observations, each prefixed with its index and length
+        let num_observations = reader.u8("num_observations")?;
+        let mut observations = Vec::with_capacity(num_observations as usize);
+        for i in 0..num_observations {
+            let [index] = reader.array(|| format!("observations[{}].index", i))?;
+            let len = u32::from_be_bytes(reader.array(|| format!("observations[{}].length", i))?);
+            let offset = reader.offset;
+            let bytes = reader.take(|| format!("observations[{}].observation", i), len as usize)?;
+            let prefix = format!("observations[{}].", i);
+            let observation = Vaa::parse_headless(
+                VaaReader { bytes, offset: 0, base: offset, prefix: &prefix },
+                guardian_set_index,
+            )?;
+            observations.push(IndexedObservation { index, observation, offset });
+        }
+
+        Ok(Self { version, guardian_set_index, signatures, hashes, observations })
+    }
+
+    /// The observation hashes, concatenated, the part guardians sign.
+    pub fn body(&self) -> Vec<u8> {
+        self.hashes.concat()
+    }
+
+    /// keccak256(body)
+    pub fn body_hash(&self) -> [u8; 32] {
+        keccak256(&self.body())
+    }
+
+    /// keccak256(keccak256(body)), the batch hash guardians sign.
+    pub fn digest(&self) -> [u8; 32] {
+        vaa_digest(&self.body())
+    }
+
+    /// Whether the batch lists the observation's digest at its index.
+    pub fn is_listed(&self, observation: &IndexedObservation) -> bool {
+        self.hashes
+            .get(usize::from(observation.index))
+            .is_some_and(|hash| *hash == observation.observation.digest())
+    }
+
+    /// `Vaa::check_strict` for batches, which also rejects observations that
+    /// are not headless VAAs or whose hashes the batch does not list.
+    pub fn check_strict(&self, guardian_set_size: Option<usize>) -> Result<(), VaaDecodeError> {
+        check_version(BATCH_VAA_VERSION, self.version, "version", 0)?;
+        check_signatures(&self.signatures, guardian_set_size)?;
+        for (i, observation) in self.observations.iter().enumerate() {
+            let field = format!("observations[{}].version", i);
+            check_version(HEADLESS_VAA_VERSION, observation.observation.version, &field, observation.offset)?;
+            if !self.is_listed(observation) {
+                return Err(VaaDecodeError::ObservationHashMismatch {
+                    field: format!("observations[{}].observation", i),
+                    offset: observation.offset,
+                    index: observation.index,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A VAA of any version, as found on the wire.
+#[derive(Debug, Clone)]
+pub enum ParsedVaa {
+    Single(Vaa),
+    Batch(BatchVaa),
+}
+
+impl ParsedVaa {
+    /// Parses a batch VAA or a single one, going by the version byte.
+    pub fn parse(bytes: &[u8]) -> Result<Self, VaaDecodeError> {
+        match bytes.first() {
+            Some(&BATCH_VAA_VERSION) => Ok(Self::Batch(BatchVaa::parse(bytes)?)),
+            _ => Ok(Self::Single(Vaa::parse_any_version(bytes)?)),
+        }
+    }
+
+    pub fn check_strict(&self, guardian_set_size: Option<usize>) -> Result<(), VaaDecodeError> {
+        match self {
+            Self::Single(vaa) => vaa.check_strict(guardian_set_size),
+            Self::Batch(batch) => batch.check_strict(guardian_set_size),
+        }
+    }
+
+    pub fn guardian_set_index(&self) -> u32 {
+        match self {
+            Self::Single(vaa) => vaa.guardian_set_index,
+            Self::Batch(batch) => batch.guardian_set_index,
+        }
+    }
+}
+
+fn check_version(expected: u8, version: u8, field: &str, offset: usize) -> Result<(), VaaDecodeError> {
+    if version == expected {
+        return Ok(());
+    }
+    Err(VaaDecodeError::UnsupportedVersion { field: field.to_string(), offset, version, expected })
+}
+
+// Signatures the Core Bridge would accept: at least one, at most one per
+// guardian of the set, with increasing guardian indexes within the set
+fn check_signatures(signatures: &[VaaSignature], guardian_set_size: Option<usize>) -> Result<(), VaaDecodeError> {
+    if signatures.is_empty() {
+        return Err(VaaDecodeError::NoSignatures { field: "num_signatures".to_string(), offset: 5 });
+    }
+    if let Some(guardian_set_size) = guardian_set_size {
+        if signatures.len() > guardian_set_size {
+            return Err(VaaDecodeError::TooManySignatures {
+                field: "num_signatures".to_string(),
+                offset: 5,
+                count: signatures.len(),
+                guardian_set_size,
+            });
+        }
+    }
+
+    let mut seen = [false; 256];
+    let mut previous: Option<u8> = None;
+    for (i, signature) in signatures.iter().enumerate() {
+        let field = format!("signatures[{}].guardian_index", i);
+        let offset = HEADER_LEN + i * SIGNATURE_LEN;
+        let index = signature.guardian_index;
+        if let Some(guardian_set_size) = guardian_set_size.filter(|size| usize::from(index) >= *size) {
+            return Err(VaaDecodeError::GuardianIndexOutOfRange { field, offset, index, guardian_set_size });
+        }
+        if seen[usize::from(index)] {
+            return Err(VaaDecodeError::DuplicateGuardianIndex { field, offset, index });
+        }
+        if let Some(previous) = previous.filter(|previous| index < *previous) {
+            return Err(VaaDecodeError::UnorderedGuardianIndex { field, offset, index, previous });
+        }
+        seen[usize::from(index)] = true;
+        previous = Some(index);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_covers_body_only() {
+        let mut body = Vec::new();
+        body.extend_from_slice(&1_700_000_000u32.to_be_bytes());
+        body.extend_from_slice(&7u32.to_be_bytes());
+        body.extend_from_slice(&30u16.to_be_bytes());
+        body.extend_from_slice(&[0xab; 32]);
+        body.extend_from_slice(&155_060u64.to_be_bytes());
+        body.push(1);
+        body.extend_from_slice(b"payload");
+
+        let encode = |signature_byte: u8| {
+            let mut bytes = vec![1, 0, 0, 0, 4, 1, 0];
+            bytes.extend_from_slice(&[signature_byte; 65]);
+            bytes.extend_from_slice(&body);
+            bytes
+        };
+
+        let vaa = Vaa::parse(&encode(0x11)).unwrap();
+        let resigned = Vaa::parse(&encode(0x22)).unwrap();
+
+        assert_eq!(vaa.body(), body.as_slice());
+        assert_eq!(vaa.payload, b"payload");
+        assert_eq!(vaa.body_hash(), keccak256(&body));
+        assert_eq!(vaa.digest(), keccak256(&keccak256(&body)));
+        // Different signatures over the same body share the digest
+        assert_eq!(vaa.digest(), resigned.digest());
+        assert_eq!(vaa.message_id().to_string(), format!("30/{}/155060", "ab".repeat(32)));
+        assert_eq!(vaa.to_bytes(), encode(0x11));
+    }
+
+    fn encode_with(version: u8, guardian_indexes: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![version, 0, 0, 0, 0, guardian_indexes.len() as u8];
+        for index in guardian_indexes {
+            bytes.push(*index);
+            bytes.extend_from_slice(&[0x11; 65]);
+        }
+        bytes.extend_from_slice(&[0u8; 51]);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_reports_truncated_field() {
+        let bytes = encode_with(1, &[0, 1]);
+
+        let e = Vaa::parse(&bytes[..100]).unwrap_err();
+        assert_eq!(e, VaaDecodeError::Truncated {
+            field: "signatures[1].signature".to_string(),
+            offset: 73,
+            expected: 65,
+            actual: 27,
+        });
+        assert_eq!((e.offset(), e.field()), (Some(73), Some("signatures[1].signature")));
+
+        let e = Vaa::parse(&bytes[..bytes.len() - 5]).unwrap_err();
+        assert_eq!(e.field(), Some("sequence"));
+        assert_eq!(e.offset(), Some(bytes.len() - 9));
+
+        // Batches and other versions are not read with the version 1 layout
+        let e = Vaa::parse(&encode_with(BATCH_VAA_VERSION, &[0])).unwrap_err();
+        assert!(matches!(e, VaaDecodeError::UnsupportedVersion { version: 2, expected: 1, .. }));
+        assert!(matches!(ParsedVaa::parse(&encode_with(4, &[0])), Ok(ParsedVaa::Single(_))));
+    }
+
+    #[test]
+    fn test_check_strict() {
+        let check = |version, indexes: &[u8], guardian_set_size| {
+            Vaa::parse_any_version(&encode_with(version, indexes)).unwrap().check_strict(guardian_set_size)
+        };
+
+        assert_eq!(check(1, &[0, 2, 5], Some(19)), Ok(()));
+        assert_eq!(check(1, &[0, 2, 5], None), Ok(()));
+        assert!(matches!(check(2, &[0], None), Err(VaaDecodeError::UnsupportedVersion { version: 2, .. })));
+        assert!(matches!(check(1, &[], None), Err(VaaDecodeError::NoSignatures { offset: 5, .. })));
+        assert!(matches!(check(1, &[0, 1, 2], Some(2)), Err(VaaDecodeError::TooManySignatures { count: 3, .. })));
+        assert!(matches!(check(1, &[0, 19], Some(19)), Err(VaaDecodeError::GuardianIndexOutOfRange { index: 19, .. })));
+        assert!(matches!(
+            check(1, &[0, 3, 3], None),
+            Err(VaaDecodeError::DuplicateGuardianIndex { index: 3, offset: 138, .. })
+        ));
+        assert!(matches!(
+            check(1, &[0, 3, 2], None),
+            Err(VaaDecodeError::UnorderedGuardianIndex { index: 2, previous: 3, .. })
+        ));
+    }
+}
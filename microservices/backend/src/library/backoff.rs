@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use super::config::Config;
+
+/// Exponential backoff with random jitter, used to space out reconnect attempts.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    min_delay: Duration,
+    max_delay: Duration,
+    // Fraction (0..=1) of each delay that is randomized
+    jitter: f64,
+    attempt: u32,
+    // Attempts after which the caller gives up, unbounded if unset
+    max_attempts: Option<u32>,
+}
+
+impl Backoff {
+    pub fn new(min_delay: Duration, max_delay: Duration, jitter: f64) -> Self {
+        Self {
+            min_delay,
+            max_delay: max_delay.max(min_delay),
+            jitter: jitter.clamp(0.0, 1.0),
+            attempt: 0,
+            max_attempts: None,
+        }
+    }
+
+    /// Bounds the number of consecutive attempts, see `is_exhausted`.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Backoff used to reconnect to the Spy, as configured through `WORMHOLE_SPY_RECONNECT_*`.
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            Duration::from_secs(config.wormhole_spy_reconnect_min_delay),
+            Duration::from_secs(config.wormhole_spy_reconnect_max_delay),
+            config.wormhole_spy_reconnect_jitter,
+        )
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Whether every attempt allowed since the last `reset` was made.
+    pub fn is_exhausted(&self) -> bool {
+        self.max_attempts.is_some_and(|max_attempts| self.attempt >= max_attempts)
+    }
+
+    /// Delay to wait before the next attempt: doubles on every call up to the
+    /// maximum, then a random share of it (up to `jitter`) is taken off.
+    pub fn next_delay(&mut self) -> Duration {
+        let factor = 2u32.saturating_pow(self.attempt.min(31));
+        let delay = self.min_delay.saturating_mul(factor).min(self.max_delay);
+        self.attempt = self.attempt.saturating_add(1);
+
+        delay.mul_f64(1.0 - self.jitter * fastrand::f64())
+    }
+
+    /// Starts over from the minimum delay, once a connection succeeded.
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delays_grow_up_to_max_with_jitter() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(10), 0.5);
+
+        for expected in [1, 2, 4, 8, 10, 10] {
+            let delay = backoff.next_delay();
+            let expected = Duration::from_secs(expected);
+            assert!(delay <= expected && delay >= expected / 2, "{:?} out of range for {:?}", delay, expected);
+        }
+
+        backoff.reset();
+        assert_eq!(backoff.attempt(), 0);
+        assert!(backoff.next_delay() <= Duration::from_secs(1));
+        assert!(!backoff.is_exhausted());
+    }
+
+    #[test]
+    fn test_max_attempts() {
+        let mut backoff = Backoff::new(Duration::ZERO, Duration::ZERO, 0.0).with_max_attempts(2);
+        backoff.next_delay();
+        assert!(!backoff.is_exhausted());
+        backoff.next_delay();
+        assert!(backoff.is_exhausted());
+
+        backoff.reset();
+        assert!(!backoff.is_exhausted());
+    }
+}
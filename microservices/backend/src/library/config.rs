@@ -10,10 +10,34 @@ pub struct Config {
     pub wormholescan_base_url: String,
     pub wormhole_spy_addr: Option<String>,
     pub wormhole_spy_timeout: u64,
+    pub wormhole_spy_ingest: bool,
+    pub wormhole_spy_filters: String,
+    pub wormhole_connect_timeout: u64,
+    pub wormhole_spy_reconnect_min_delay: u64,
+    pub wormhole_spy_reconnect_max_delay: u64,
+    pub wormhole_spy_reconnect_jitter: f64,
+    pub wormhole_network: String,
+    pub wormhole_guardian_rpc_addrs: Vec<String>,
+    pub guardian_set_sync_interval: u64,
+    pub gap_backfill_interval: u64,
+    pub governor_sync_interval: u64,
+    pub heartbeat_poll_interval: u64,
+    pub heartbeat_stale_after: u64,
+    pub heartbeat_max_lag: i64,
+    pub storage_url: Option<String>,
+    pub redis_url: Option<String>,
+    pub redis_ttl: u64,
+    pub redis_channel: String,
 }
 
 static CONFIG: OnceLock<Config> = OnceLock::new();
 
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Config {
     pub fn new() -> Self {
         // Load .env file if it exists
@@ -37,11 +61,91 @@ impl Config {
                 .unwrap_or_else(|_| "30".to_string())
                 .parse()
                 .expect("WORMHOLE_SPY_TIMEOUT must be a number"),
+            wormhole_spy_ingest: env::var("WORMHOLE_SPY_INGEST")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .expect("WORMHOLE_SPY_INGEST must be true or false"),
+            wormhole_spy_filters: env::var("WORMHOLE_SPY_FILTERS")
+                .unwrap_or_default(),
+            wormhole_connect_timeout: env::var("WORMHOLE_CONNECT_TIMEOUT")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("WORMHOLE_CONNECT_TIMEOUT must be a number"),
+            wormhole_spy_reconnect_min_delay: env::var("WORMHOLE_SPY_RECONNECT_MIN_DELAY")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse::<u64>()
+                .ok()
+                .filter(|delay| *delay > 0)
+                .expect("WORMHOLE_SPY_RECONNECT_MIN_DELAY must be a positive number"),
+            wormhole_spy_reconnect_max_delay: env::var("WORMHOLE_SPY_RECONNECT_MAX_DELAY")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse::<u64>()
+                .ok()
+                .filter(|delay| *delay > 0)
+                .expect("WORMHOLE_SPY_RECONNECT_MAX_DELAY must be a positive number"),
+            wormhole_spy_reconnect_jitter: env::var("WORMHOLE_SPY_RECONNECT_JITTER")
+                .unwrap_or_else(|_| "0.2".to_string())
+                .parse()
+                .expect("WORMHOLE_SPY_RECONNECT_JITTER must be a number between 0 and 1"),
+            wormhole_network: env::var("WORMHOLE_NETWORK")
+                .unwrap_or_else(|_| "mainnet".to_string()),
+            wormhole_guardian_rpc_addrs: env::var("WORMHOLE_GUARDIAN_RPC_ADDRS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|addr| addr.trim().to_string())
+                .filter(|addr| !addr.is_empty())
+                .collect(),
+            guardian_set_sync_interval: env::var("GUARDIAN_SET_SYNC_INTERVAL")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse::<u64>()
+                .ok()
+                .filter(|interval| *interval > 0)
+                .expect("GUARDIAN_SET_SYNC_INTERVAL must be a positive number"),
+            gap_backfill_interval: env::var("GAP_BACKFILL_INTERVAL")
+                .unwrap_or_else(|_| "600".to_string())
+                .parse::<u64>()
+                .ok()
+                .filter(|interval| *interval > 0)
+                .expect("GAP_BACKFILL_INTERVAL must be a positive number"),
+            governor_sync_interval: env::var("GOVERNOR_SYNC_INTERVAL")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse::<u64>()
+                .ok()
+                .filter(|interval| *interval > 0)
+                .expect("GOVERNOR_SYNC_INTERVAL must be a positive number"),
+            heartbeat_poll_interval: env::var("HEARTBEAT_POLL_INTERVAL")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse::<u64>()
+                .ok()
+                .filter(|interval| *interval > 0)
+                .expect("HEARTBEAT_POLL_INTERVAL must be a positive number"),
+            heartbeat_stale_after: env::var("HEARTBEAT_STALE_AFTER")
+                .unwrap_or_else(|_| "120".to_string())
+                .parse()
+                .expect("HEARTBEAT_STALE_AFTER must be a number"),
+            heartbeat_max_lag: env::var("HEARTBEAT_MAX_LAG")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()
+                .expect("HEARTBEAT_MAX_LAG must be a number"),
+            // In-memory storage when unset
+            storage_url: env::var("STORAGE_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            // No cache nor fan-out when unset
+            redis_url: env::var("REDIS_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            redis_ttl: env::var("REDIS_TTL")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .expect("REDIS_TTL must be a number"),
+            redis_channel: env::var("REDIS_CHANNEL")
+                .unwrap_or_else(|_| "wormhole:vaas".to_string()),
         }
     }
 }
 
 // Helper function to get config singleton
 pub fn get_config() -> &'static Config {
-    CONFIG.get_or_init(|| Config::new())
+    CONFIG.get_or_init(Config::new)
 } 
\ No newline at end of file
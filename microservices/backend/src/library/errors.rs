@@ -53,6 +53,7 @@ pub enum Error {
     External(String),
     Parsing(String),
     Subscription(String),
+    Storage(String),
 }
 
 impl std::fmt::Display for Error {
@@ -63,6 +64,7 @@ impl std::fmt::Display for Error {
             Error::External(msg) => write!(f, "External error: {}", msg),
             Error::Parsing(msg) => write!(f, "Parsing error: {}", msg),
             Error::Subscription(msg) => write!(f, "Subscription error: {}", msg),
+            Error::Storage(msg) => write!(f, "Storage error: {}", msg),
         }
     }
 }
@@ -1,3 +1,4 @@
 pub mod docs;
 pub mod errors;
 pub mod config;
+pub mod backoff;
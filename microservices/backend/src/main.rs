@@ -7,11 +7,29 @@ use axum::Extension;
 use state::{AppState, Repositories};
 use domain::{
   health::health_routes,
-  wormhole::{scan_routes, spy_routes}
+  wormhole::{scan_routes, spy_routes, guardian_routes, governor_routes, observer_routes}
+};
+use domain::wormhole::backfill::spawn_gap_backfill;
+use domain::wormhole::governor::sync::spawn_governor_sync;
+use domain::wormhole::guardians::{GuardianSetRegistry, sync::{spawn_guardian_set_sync, spawn_heartbeat_poller}};
+use domain::wormhole::grpc::{
+    fanout::{spawn_fanout_publisher, spawn_fanout_subscriber},
+    filters::parse_filters,
+    ingest::{restore_ingestion, spawn_spy_ingestion},
 };
 use library::docs::docs_routes;
 use library::config::get_config;
-use crate::storage::{Repository, memory::MemoryRepository};
+use library::backoff::Backoff;
+use crate::storage::{
+    Repository,
+    VaaRepository,
+    database::StorageBackend,
+    events::{EventStore, MemoryEventStore},
+    memory::MemoryRepository,
+    postgres::PostgresRepository,
+    redis::RedisRepository,
+    sqlite::SqliteRepository,
+};
 
 pub mod domain;
 pub mod library;
@@ -34,10 +52,84 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     aide::generate::extract_schemas(true);
 
+    let config = get_config();
+
     let wormhole_repository = Repository::new(MemoryRepository::new());
-    let vaas_repository = Repository::new(MemoryRepository::new());
-    let repositories = Repositories::new(wormhole_repository, vaas_repository);
-    let state = AppState::new(repositories).await?;
+    // The event log lives next to the VAA records
+    let (vaas_repository, events) = match StorageBackend::from_url(config.storage_url.as_deref())? {
+        StorageBackend::Memory => (VaaRepository::new(MemoryRepository::new()), EventStore::new(MemoryEventStore::new())),
+        StorageBackend::Postgres(url) => {
+            let postgres = PostgresRepository::connect(&url).await?;
+            (VaaRepository::new(postgres.clone()), EventStore::new(postgres))
+        }
+        StorageBackend::Sqlite(url) => {
+            let sqlite = SqliteRepository::connect(&url).await?;
+            (VaaRepository::new(sqlite.clone()), EventStore::new(sqlite))
+        }
+    };
+    let repositories = Repositories::new(wormhole_repository, vaas_repository, events);
+
+    let guardian_sets = Arc::new(GuardianSetRegistry::bundled(&config.wormhole_network)?);
+    let mut state = AppState::new(repositories, guardian_sets).await?;
+    let cache = match &config.redis_url {
+        Some(url) => Some(RedisRepository::connect(url, config.redis_ttl).await?),
+        None => None,
+    };
+    if let Some(cache) = cache.clone() {
+        state = state.with_cache(cache);
+    }
+
+    // Live projections start from whatever the event log already holds
+    state.projections().rebuild(state.events()).await?;
+
+    if !config.wormhole_guardian_rpc_addrs.is_empty() {
+        spawn_guardian_set_sync(
+            Arc::new(state.clone()),
+            config.wormhole_guardian_rpc_addrs.clone(),
+            std::time::Duration::from_secs(config.guardian_set_sync_interval),
+        );
+        // Tells the sequences held by the Governor apart from missing ones
+        spawn_governor_sync(
+            Arc::new(state.clone()),
+            config.wormhole_guardian_rpc_addrs.clone(),
+            std::time::Duration::from_secs(config.governor_sync_interval),
+        );
+        // Keeps track of the guardians lagging behind on some chain
+        spawn_heartbeat_poller(
+            Arc::new(state.clone()),
+            config.wormhole_guardian_rpc_addrs.clone(),
+            std::time::Duration::from_secs(config.heartbeat_poll_interval),
+        );
+    }
+
+    // Pick up the gap reports of previous runs from persistent storage
+    restore_ingestion(&state).await;
+
+    // Ask the guardians for the VAAs missing from the open gaps
+    if !config.wormhole_guardian_rpc_addrs.is_empty() {
+        spawn_gap_backfill(
+            Arc::new(state.clone()),
+            config.wormhole_guardian_rpc_addrs.clone(),
+            std::time::Duration::from_secs(config.gap_backfill_interval),
+        );
+    }
+
+    // Keep a permanent Spy subscription feeding the repositories
+    let ingesting = config.wormhole_spy_ingest && config.wormhole_spy_addr.is_some();
+    if let (true, Some(spy_addr)) = (config.wormhole_spy_ingest, config.wormhole_spy_addr.clone()) {
+        let filters = parse_filters(&config.wormhole_spy_filters)?;
+        spawn_spy_ingestion(Arc::new(state.clone()), spy_addr, filters, Backoff::from_config(config));
+    }
+
+    // Replicas without an ingestion worker get the live VAAs over Redis
+    if let Some(cache) = cache {
+        let channel = config.redis_channel.clone();
+        if ingesting {
+            spawn_fanout_publisher(Arc::new(state.clone()), cache, channel);
+        } else {
+            spawn_fanout_subscriber(Arc::new(state.clone()), cache, channel, Backoff::from_config(config));
+        }
+    }
 
     let mut api = OpenApi::default();
 
@@ -45,13 +137,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .merge(health_routes(Arc::new(state.clone())))
         .merge(scan_routes(Arc::new(state.clone())))
         .merge(spy_routes(Arc::new(state.clone())))
+        .merge(guardian_routes(Arc::new(state.clone())))
+        .merge(governor_routes(Arc::new(state.clone())))
+        .merge(observer_routes(Arc::new(state.clone())))
         .nest_api_service("/docs", docs_routes(Arc::new(state)))
         .finish_api_with(&mut api, library::docs::configure_api_docs)
         .layer(Extension(Arc::new(api)))
         .with_state(());
 
-    let config = get_config();
-    
     println!("Scalar Docs are now available at http://{}:{}/docs", config.host, config.port);
 
     let listener = TcpListener::bind(format!("{}:{}", config.host, config.port))
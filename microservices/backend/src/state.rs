@@ -1,49 +1,123 @@
 use std::sync::Arc;
-use crate::storage::Repository;
-use crate::domain::wormhole::models::{VaaRequest, VaaResponse, VaaRecord, VaaRecordView};
+use crate::storage::{events::EventStore, redis::RedisRepository, Repository, VaaRepository};
+use crate::domain::wormhole::models::{VaaRequest, VaaResponse};
+use crate::domain::wormhole::backfill::Backfills;
+use crate::domain::wormhole::governor::GovernorQueue;
+use crate::domain::wormhole::guardians::{GuardianSetRegistry, Heartbeats};
+use crate::domain::wormhole::grpc::ingest::SpyIngestion;
+use crate::domain::wormhole::events::ObserverEvent;
+use crate::domain::wormhole::projections::Projections;
+use crate::domain::wormhole::rest::crawl::Crawls;
 // use crate::library::config::get_config;
 use crate::library::errors::Error;
 
 #[derive(Clone)]
 pub struct Repositories {
     wormhole: Repository<VaaRequest, VaaResponse>,
-    vaas: Repository<VaaRecord, VaaRecordView>,
+    vaas: VaaRepository,
+    events: EventStore<ObserverEvent>,
 }
 
 impl Repositories {
     pub fn new(
         wormhole: Repository<VaaRequest, VaaResponse>,
-        vaas: Repository<VaaRecord, VaaRecordView>,
+        vaas: VaaRepository,
+        events: EventStore<ObserverEvent>,
     ) -> Self {
-        Self { wormhole, vaas }
+        Self { wormhole, vaas, events }
     }
 
     pub fn wormhole(&self) -> &Repository<VaaRequest, VaaResponse> {
         &self.wormhole
     }
 
-    pub fn vaas(&self) -> &Repository<VaaRecord, VaaRecordView> {
+    pub fn vaas(&self) -> &VaaRepository {
         &self.vaas
     }
+
+    pub fn events(&self) -> &EventStore<ObserverEvent> {
+        &self.events
+    }
 }
 
 #[derive(Clone)]
 pub struct AppState {
     repositories: Arc<Repositories>,
+    guardian_sets: Arc<GuardianSetRegistry>,
+    spy_ingestion: Arc<SpyIngestion>,
+    projections: Arc<Projections>,
+    crawls: Arc<Crawls>,
+    backfills: Arc<Backfills>,
+    governor: Arc<GovernorQueue>,
+    heartbeats: Arc<Heartbeats>,
+    cache: Option<RedisRepository>,
 }
 
 impl AppState {
-    pub async fn new(repositories: Repositories) -> Result<Self, Error> {
+    pub async fn new(
+        repositories: Repositories,
+        guardian_sets: Arc<GuardianSetRegistry>,
+    ) -> Result<Self, Error> {
         Ok(Self {
             repositories: Arc::new(repositories),
+            guardian_sets,
+            spy_ingestion: Arc::new(SpyIngestion::new()),
+            projections: Arc::new(Projections::new()),
+            crawls: Arc::new(Crawls::new()),
+            backfills: Arc::new(Backfills::new()),
+            governor: Arc::new(GovernorQueue::new()),
+            heartbeats: Arc::new(Heartbeats::new()),
+            cache: None,
         })
     }
 
+    /// Writes ingested VAAs through to a Redis cache as well
+    pub fn with_cache(mut self, cache: RedisRepository) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     pub fn wormhole_repository(&self) -> &Repository<VaaRequest, VaaResponse> {
         self.repositories.wormhole()
     }
 
-    pub fn vaas_repository(&self) -> &Repository<VaaRecord, VaaRecordView> {
+    pub fn vaas_repository(&self) -> &VaaRepository {
       self.repositories.vaas()
     }
+
+    pub fn events(&self) -> &EventStore<ObserverEvent> {
+        self.repositories.events()
+    }
+
+    pub fn guardian_sets(&self) -> &Arc<GuardianSetRegistry> {
+        &self.guardian_sets
+    }
+
+    pub fn spy_ingestion(&self) -> &Arc<SpyIngestion> {
+        &self.spy_ingestion
+    }
+
+    pub fn projections(&self) -> &Arc<Projections> {
+        &self.projections
+    }
+
+    pub fn crawls(&self) -> &Arc<Crawls> {
+        &self.crawls
+    }
+
+    pub fn backfills(&self) -> &Arc<Backfills> {
+        &self.backfills
+    }
+
+    pub fn governor(&self) -> &Arc<GovernorQueue> {
+        &self.governor
+    }
+
+    pub fn heartbeats(&self) -> &Arc<Heartbeats> {
+        &self.heartbeats
+    }
+
+    pub fn cache(&self) -> Option<&RedisRepository> {
+        self.cache.as_ref()
+    }
 }
\ No newline at end of file
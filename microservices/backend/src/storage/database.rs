@@ -0,0 +1,27 @@
+use crate::library::errors::Error;
+
+/// Storage backend selected through `STORAGE_URL`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageBackend {
+    /// Nothing survives a restart
+    Memory,
+    Postgres(String),
+    /// Embedded database file, or `sqlite::memory:`
+    Sqlite(String),
+}
+
+impl StorageBackend {
+    pub fn from_url(url: Option<&str>) -> Result<Self, Error> {
+        match url {
+            None => Ok(StorageBackend::Memory),
+            Some(url) if url.starts_with("postgres://") || url.starts_with("postgresql://") => {
+                Ok(StorageBackend::Postgres(url.to_string()))
+            }
+            Some(url) if url.starts_with("sqlite:") => Ok(StorageBackend::Sqlite(url.to_string())),
+            Some(url) => Err(Error::Storage(format!(
+                "Unsupported STORAGE_URL scheme: {}",
+                url.split("://").next().unwrap_or(url)
+            ))),
+        }
+    }
+}
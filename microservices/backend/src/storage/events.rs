@@ -0,0 +1,230 @@
+// Append-only event log, the source the read models are projected from
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use schemars::JsonSchema;
+use tokio::sync::RwLock;
+
+use crate::library::errors::Error;
+use super::query::SqlValue;
+
+pub const DEFAULT_EVENT_LIMIT: usize = 100;
+pub const MAX_EVENT_LIMIT: usize = 1_000;
+
+/// Events kept in an `EventStore`.
+pub trait Event: Serialize + DeserializeOwned + Clone + Send + Sync + 'static {
+    /// Short name of the event type, stored alongside it
+    fn kind(&self) -> &'static str;
+    /// Stream the event belongs to, so the history of a single entity can be read back
+    fn stream_id(&self) -> String;
+}
+
+/// An event as recorded in the log. Positions increase with every append.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StoredEvent<E> {
+    pub position: i64,
+    /// When the observer recorded the event
+    #[schemars(with = "String")]
+    pub recorded_at: DateTime<Utc>,
+    pub stream_id: String,
+    pub event: E,
+}
+
+/// Range of the log to read, oldest events first.
+#[derive(Debug, Clone, Default)]
+pub struct EventQuery {
+    /// Only events recorded after this position
+    pub after: i64,
+    /// Only events of any of these streams, every stream when empty
+    pub stream_ids: Vec<String>,
+    /// Only events of this kind, e.g. `vaa_observed`
+    pub kind: Option<&'static str>,
+    /// At most this many events, `DEFAULT_EVENT_LIMIT` when unset
+    pub limit: Option<usize>,
+}
+
+impl EventQuery {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_EVENT_LIMIT).clamp(1, MAX_EVENT_LIMIT)
+    }
+
+    fn matches<E: Event>(&self, event: &StoredEvent<E>) -> bool {
+        event.position > self.after
+            && self.kind.is_none_or(|kind| event.event.kind() == kind)
+            && (self.stream_ids.is_empty() || self.stream_ids.contains(&event.stream_id))
+    }
+
+    /// `WHERE`, `ORDER BY` and `LIMIT` clauses over the `events` table. Bind
+    /// parameters are numbered from `$1`.
+    pub fn to_sql(&self) -> (String, Vec<SqlValue>) {
+        let mut values = vec![SqlValue::Int(self.after)];
+        let mut conditions = vec!["position > $1".to_string()];
+        if let Some(kind) = self.kind {
+            values.push(SqlValue::Text(kind.to_string()));
+            conditions.push(format!("kind = ${}", values.len()));
+        }
+        if !self.stream_ids.is_empty() {
+            let mut placeholders = Vec::new();
+            for stream_id in &self.stream_ids {
+                values.push(SqlValue::Text(stream_id.clone()));
+                placeholders.push(format!("${}", values.len()));
+            }
+            conditions.push(format!("stream_id IN ({})", placeholders.join(", ")));
+        }
+
+        let sql = format!("WHERE {} ORDER BY position LIMIT {}", conditions.join(" AND "), self.limit());
+        (sql, values)
+    }
+}
+
+/// Events can only be appended and read, never updated or deleted.
+#[async_trait]
+pub trait EventStoreImpl<E: Event>: Send + Sync + 'static {
+    async fn append(&self, event: E) -> Result<StoredEvent<E>, Error>;
+    async fn read(&self, query: &EventQuery) -> Result<Vec<StoredEvent<E>>, Error>;
+}
+
+/// Facade over the event store backends, like `Repository` for read models.
+pub struct EventStore<E> {
+    inner: Arc<dyn EventStoreImpl<E>>,
+}
+
+impl<E> Clone for EventStore<E> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<E: Event> EventStore<E> {
+    pub fn new(impl_: impl EventStoreImpl<E> + 'static) -> Self {
+        Self {
+            inner: Arc::new(impl_),
+        }
+    }
+
+    pub async fn append(&self, event: E) -> Result<StoredEvent<E>, Error> {
+        self.inner.append(event).await
+    }
+
+    pub async fn read(&self, query: &EventQuery) -> Result<Vec<StoredEvent<E>>, Error> {
+        self.inner.read(query).await
+    }
+
+    /// Reads the whole log from `after` on, page by page, handing each event to `apply`.
+    /// Returns the position of the last event read.
+    pub async fn replay(&self, after: i64, mut apply: impl FnMut(&StoredEvent<E>)) -> Result<i64, Error> {
+        let mut query = EventQuery {
+            after,
+            limit: Some(MAX_EVENT_LIMIT),
+            ..Default::default()
+        };
+        loop {
+            let events = self.read(&query).await?;
+            let Some(last) = events.last() else {
+                return Ok(query.after);
+            };
+            query.after = last.position;
+            events.iter().for_each(&mut apply);
+        }
+    }
+}
+
+/// Event log kept in memory, lost on restart.
+pub struct MemoryEventStore<E> {
+    events: RwLock<Vec<StoredEvent<E>>>,
+}
+
+impl<E> MemoryEventStore<E> {
+    pub fn new() -> Self {
+        Self {
+            events: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+impl<E> Default for MemoryEventStore<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<E: Event> EventStoreImpl<E> for MemoryEventStore<E> {
+    async fn append(&self, event: E) -> Result<StoredEvent<E>, Error> {
+        let mut events = self.events.write().await;
+        let stored = StoredEvent {
+            position: events.len() as i64 + 1,
+            recorded_at: Utc::now(),
+            stream_id: event.stream_id(),
+            event,
+        };
+        events.push(stored.clone());
+        Ok(stored)
+    }
+
+    async fn read(&self, query: &EventQuery) -> Result<Vec<StoredEvent<E>>, Error> {
+        let events = self.events.read().await;
+        // Positions start at 1 and have no holes
+        let start = usize::try_from(query.after.max(0)).unwrap_or(usize::MAX).min(events.len());
+        Ok(events[start..]
+            .iter()
+            .filter(|event| query.matches(event))
+            .take(query.limit())
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Seen(String);
+
+    impl Event for Seen {
+        fn kind(&self) -> &'static str {
+            "seen"
+        }
+
+        fn stream_id(&self) -> String {
+            self.0.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_event_store() {
+        let store = EventStore::new(MemoryEventStore::new());
+        for stream in ["a", "b", "a"] {
+            store.append(Seen(stream.to_string())).await.unwrap();
+        }
+
+        let query = EventQuery { after: 1, stream_ids: vec!["a".to_string()], ..Default::default() };
+        let positions: Vec<i64> = store.read(&query).await.unwrap().iter().map(|event| event.position).collect();
+        assert_eq!(positions, vec![3]);
+        let query = EventQuery { stream_ids: vec!["a".to_string(), "b".to_string()], kind: Some("seen"), ..Default::default() };
+        assert_eq!(store.read(&query).await.unwrap().len(), 3);
+        let query = EventQuery { kind: Some("other"), ..Default::default() };
+        assert!(store.read(&query).await.unwrap().is_empty());
+
+        let mut replayed = Vec::new();
+        assert_eq!(store.replay(0, |event| replayed.push(event.event.clone())).await.unwrap(), 3);
+        assert_eq!(replayed.len(), 3);
+    }
+
+    #[test]
+    fn test_to_sql() {
+        let query = EventQuery {
+            after: 7,
+            stream_ids: vec!["a".to_string(), "b".to_string()],
+            kind: Some("seen"),
+            limit: Some(10),
+        };
+        let (sql, values) = query.to_sql();
+        assert_eq!(sql, "WHERE position > $1 AND kind = $2 AND stream_id IN ($3, $4) ORDER BY position LIMIT 10");
+        assert_eq!(values.len(), 4);
+    }
+}
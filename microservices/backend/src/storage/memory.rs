@@ -3,9 +3,11 @@ use tokio::sync::Mutex;
 use uuid::Uuid;
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Serialize};
-use tracing::info;
 
-use super::{RepositoryImpl, ReadModel, HasId};
+use crate::library::errors::Error;
+use crate::domain::wormhole::models::{VaaRecord, VaaRecordView};
+use super::{RepositoryImpl, ReadModel, HasId, VaaRepositoryImpl};
+use super::query::{Page, Queryable, VaaQuery};
 
 pub struct MemoryRepository<W>
 where
@@ -25,6 +27,15 @@ where
     }
 }
 
+impl<W> Default for MemoryRepository<W>
+where
+    W: HasId,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl<W, R> RepositoryImpl<W, R> for MemoryRepository<W>
 where
@@ -38,11 +49,7 @@ where
     }
 
     async fn get(&self, id: Uuid) -> Option<R> {
-        let items = self.items.lock().await;
-        info!("Looking for item with id: {}", id);
-        info!("Available items: {:?}", items);
-        
-        items.get(&id).map(|item| R::from_write_model(item))
+        self.items.lock().await.get(&id).map(|item| R::from_write_model(item))
     }
 
     async fn list(&self) -> Vec<R> {
@@ -58,11 +65,29 @@ where
 
     async fn update(&self, id: Uuid, item: W) -> Option<W> {
         let mut items = self.items.lock().await;
-        if items.contains_key(&id) {
-            items.insert(id, item.clone());
-            Some(item)
-        } else {
-            None
+        match items.get_mut(&id) {
+            Some(existing) => {
+                *existing = item.clone();
+                Some(item)
+            }
+            None => None,
         }
     }
-} 
\ No newline at end of file
+}
+
+#[async_trait]
+impl VaaRepositoryImpl for MemoryRepository<VaaRecord> {
+    async fn query(&self, query: &VaaQuery) -> Result<Page<VaaRecordView>, Error> {
+        let mut matching = Vec::new();
+        for item in self.items.lock().await.values() {
+            if let Some(fields) = item.query_fields() {
+                if query.matches(&fields, item.id())? {
+                    matching.push((fields, item.clone()));
+                }
+            }
+        }
+
+        let items = query.sort(matching)?;
+        Ok(query.paginate(items)?.map(|item| VaaRecordView::from_write_model(&item)))
+    }
+}
@@ -1,8 +1,13 @@
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Serialize};
 use uuid::Uuid;
+use std::ops::Deref;
 use std::sync::Arc;
 
+use crate::domain::wormhole::models::{VaaRecord, VaaRecordView};
+use crate::library::errors::Error;
+use query::{Page, VaaQuery};
+
 // Trait for converting between write and read models
 pub trait ReadModel: Sized {
     type WriteModel;
@@ -26,6 +31,13 @@ where
     async fn update(&self, id: Uuid, item: W) -> Option<W>;
 }
 
+/// Repositories of VAA records, which also answer VAA queries.
+#[async_trait]
+pub trait VaaRepositoryImpl: RepositoryImpl<VaaRecord, VaaRecordView> {
+    /// Filtered, ordered page of the stored VAAs
+    async fn query(&self, query: &VaaQuery) -> Result<Page<VaaRecordView>, Error>;
+}
+
 // ToDo: Explain facade pattern
 #[derive(Clone)]
 pub struct Repository<W, R> {
@@ -64,5 +76,40 @@ where
     }
 }
 
+/// `Repository` of VAA records, with VAA queries on top.
+#[derive(Clone)]
+pub struct VaaRepository {
+    records: Repository<VaaRecord, VaaRecordView>,
+    inner: Arc<dyn VaaRepositoryImpl>,
+}
+
+impl VaaRepository {
+    pub fn new(impl_: impl VaaRepositoryImpl + 'static) -> Self {
+        let inner: Arc<dyn VaaRepositoryImpl> = Arc::new(impl_);
+        Self {
+            records: Repository { inner: inner.clone() },
+            inner,
+        }
+    }
+
+    pub async fn query(&self, query: &VaaQuery) -> Result<Page<VaaRecordView>, Error> {
+        self.inner.query(query).await
+    }
+}
+
+impl Deref for VaaRepository {
+    type Target = Repository<VaaRecord, VaaRecordView>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.records
+    }
+}
+
+pub mod database;
+pub mod events;
 pub mod memory;
-// pub mod postgres; 
\ No newline at end of file
+pub mod postgres;
+pub mod query;
+pub mod redis;
+pub mod sql;
+pub mod sqlite; 
\ No newline at end of file
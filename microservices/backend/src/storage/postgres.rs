@@ -0,0 +1,89 @@
+use sqlx::postgres::{PgPool, PgPoolOptions, Postgres};
+use tracing::info;
+
+use crate::library::errors::Error;
+use super::sql::SqlRepository;
+
+const MAX_CONNECTIONS: u32 = 10;
+
+/// Postgres-backed storage for VAA records, see `migrations/postgres`.
+pub type PostgresRepository = SqlRepository<Postgres>;
+
+impl SqlRepository<Postgres> {
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        let pool = PgPoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .connect(url)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to connect to Postgres: {}", e)))?;
+        Self::from_pool(pool).await
+    }
+
+    /// Wraps an existing pool, applying pending migrations first.
+    pub async fn from_pool(pool: PgPool) -> Result<Self, Error> {
+        sqlx::migrate!("./migrations/postgres")
+            .run(&pool)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to run Postgres migrations: {}", e)))?;
+        info!("Postgres storage ready");
+        Ok(Self::migrated(pool))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use sqlx::{postgres::PgConnectOptions, Executor};
+    use uuid::Uuid;
+    use super::*;
+    use crate::storage::sql::tests as suite;
+
+    // Throwaway database on the server at TEST_POSTGRES_URL, dropped by `cleanup`
+    async fn test_repository() -> (PostgresRepository, PgPool, String) {
+        let url = std::env::var("TEST_POSTGRES_URL").expect("TEST_POSTGRES_URL not set");
+        let admin = PgPool::connect(&url).await.unwrap();
+        let database = format!("vaa_observer_test_{}", Uuid::new_v4().simple());
+        admin.execute(format!("CREATE DATABASE {}", database).as_str()).await.unwrap();
+
+        let options = PgConnectOptions::from_str(&url).unwrap().database(&database);
+        let pool = PgPoolOptions::new().connect_with(options).await.unwrap();
+        (PostgresRepository::from_pool(pool).await.unwrap(), admin, database)
+    }
+
+    async fn cleanup(repository: PostgresRepository, admin: PgPool, database: String) {
+        repository.pool().close().await;
+        admin.execute(format!("DROP DATABASE {} WITH (FORCE)", database).as_str()).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Postgres server (TEST_POSTGRES_URL)"]
+    async fn test_postgres_repository() {
+        let (repository, admin, database) = test_repository().await;
+        suite::stores_each_digest_once(&repository).await;
+        cleanup(repository, admin, database).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Postgres server (TEST_POSTGRES_URL)"]
+    async fn test_stores_decoded_fields() {
+        let (repository, admin, database) = test_repository().await;
+        suite::stores_decoded_fields(&repository).await;
+        cleanup(repository, admin, database).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Postgres server (TEST_POSTGRES_URL)"]
+    async fn test_query_pages_like_memory() {
+        let (repository, admin, database) = test_repository().await;
+        suite::query_pages_like_memory(&repository).await;
+        cleanup(repository, admin, database).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Postgres server (TEST_POSTGRES_URL)"]
+    async fn test_postgres_event_store() {
+        let (repository, admin, database) = test_repository().await;
+        suite::event_store_is_append_only(&repository).await;
+        cleanup(repository, admin, database).await;
+    }
+}
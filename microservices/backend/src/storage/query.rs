@@ -0,0 +1,349 @@
+// Typed queries over stored VAAs, answered by every storage backend
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use uuid::Uuid;
+
+use crate::library::errors::Error;
+use super::HasId;
+
+pub const DEFAULT_QUERY_LIMIT: usize = 100;
+pub const MAX_QUERY_LIMIT: usize = 1_000;
+
+/// Field results are sorted by, ties are broken by record ID.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryOrder {
+    /// When the observer stored the VAA
+    #[default]
+    ObservedAt,
+    Sequence,
+    /// Timestamp of the VAA body
+    Timestamp,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Filters, ordering and page of a VAA query. Unset filters match everything.
+#[derive(Debug, Clone, Default)]
+pub struct VaaQuery {
+    pub emitter_chain: Option<u16>,
+    /// Hex-encoded 32-byte emitter address, lowercase and without leading 0x
+    pub emitter_address: Option<String>,
+    /// Lowest sequence, inclusive
+    pub sequence_from: Option<u64>,
+    /// Highest sequence, inclusive
+    pub sequence_to: Option<u64>,
+    /// Earliest VAA timestamp, inclusive
+    pub time_from: Option<DateTime<Utc>>,
+    /// Latest VAA timestamp, exclusive
+    pub time_to: Option<DateTime<Utc>>,
+    pub guardian_set_index: Option<u32>,
+    pub order: QueryOrder,
+    pub direction: Direction,
+    /// Page size, `DEFAULT_QUERY_LIMIT` when unset and at most `MAX_QUERY_LIMIT`
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+}
+
+/// A page of query results.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Page<R> {
+    pub items: Vec<R>,
+    /// Pass as `cursor` to get the next page, unset on the last one
+    pub next_cursor: Option<String>,
+}
+
+impl<R> Page<R> {
+    pub fn map<T>(self, f: impl FnMut(R) -> T) -> Page<T> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+/// Indexed attributes of a stored item, matched against `VaaQuery` filters.
+#[derive(Debug, Clone)]
+pub struct QueryFields {
+    pub emitter_chain: u16,
+    pub emitter_address: String,
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    pub guardian_set_index: u32,
+    pub observed_at: DateTime<Utc>,
+}
+
+/// Write models that can be queried. Items without fields match no query.
+pub trait Queryable {
+    fn query_fields(&self) -> Option<QueryFields>;
+}
+
+// Position of the last item of a page, in the order it was queried with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Cursor {
+    order: QueryOrder,
+    direction: Direction,
+    key: i64,
+    id: Uuid,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str) -> Result<Self, Error> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| Error::Request(format!("Invalid cursor {}", cursor)))
+    }
+}
+
+impl QueryFields {
+    // Value of the ordering field, as stored in cursors
+    fn sort_key(&self, order: QueryOrder) -> Result<i64, Error> {
+        Ok(match order {
+            QueryOrder::ObservedAt => self.observed_at.timestamp_nanos_opt().unwrap_or(i64::MAX),
+            QueryOrder::Sequence => sequence_to_i64(self.sequence)?,
+            QueryOrder::Timestamp => self.timestamp.timestamp(),
+        })
+    }
+}
+
+/// Sequence as stored by the SQL backends and in cursors, which only have
+/// signed 64-bit integers. Larger sequences, e.g. from a malformed VAA, are
+/// rejected rather than wrapped.
+pub fn sequence_to_i64(sequence: u64) -> Result<i64, Error> {
+    i64::try_from(sequence)
+        .map_err(|_| Error::Request(format!("Sequence {} is out of range, at most {}", sequence, i64::MAX)))
+}
+
+/// Bind parameter of the SQL run by the SQL backends, e.g. built by `VaaQuery::to_sql`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Bool(bool),
+    Int(i64),
+    Text(String),
+    OptionalText(Option<String>),
+    Bytes(Vec<u8>),
+    Time(DateTime<Utc>),
+    Id(Uuid),
+    Json(serde_json::Value),
+}
+
+impl VaaQuery {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_QUERY_LIMIT).clamp(1, MAX_QUERY_LIMIT)
+    }
+
+    // Decodes the cursor, which must come from a query with the same ordering
+    fn after(&self) -> Result<Option<Cursor>, Error> {
+        let Some(cursor) = &self.cursor else { return Ok(None) };
+        let cursor = Cursor::decode(cursor)?;
+        if (cursor.order, cursor.direction) != (self.order, self.direction) {
+            return Err(Error::Request("Cursor was issued for a different ordering".to_string()));
+        }
+        Ok(Some(cursor))
+    }
+
+    /// Whether an item with `fields` and `id` belongs to the results, cursor included.
+    pub fn matches(&self, fields: &QueryFields, id: Uuid) -> Result<bool, Error> {
+        let matches = self.emitter_chain.is_none_or(|chain| fields.emitter_chain == chain)
+            && self.emitter_address.as_ref().is_none_or(|emitter| fields.emitter_address == *emitter)
+            && self.sequence_from.is_none_or(|from| fields.sequence >= from)
+            && self.sequence_to.is_none_or(|to| fields.sequence <= to)
+            && self.time_from.is_none_or(|from| fields.timestamp >= from)
+            && self.time_to.is_none_or(|to| fields.timestamp < to)
+            && self.guardian_set_index.is_none_or(|index| fields.guardian_set_index == index);
+
+        Ok(matches && match self.after()? {
+            Some(cursor) => {
+                let position = (fields.sort_key(self.order)?, id).cmp(&(cursor.key, cursor.id));
+                match self.direction {
+                    Direction::Asc => position.is_gt(),
+                    Direction::Desc => position.is_lt(),
+                }
+            }
+            None => true,
+        })
+    }
+
+    /// Sorts matching items the way SQL backends return them, keeping the
+    /// `limit() + 1` first ones for `paginate`.
+    pub fn sort<W: HasId>(&self, items: Vec<(QueryFields, W)>) -> Result<Vec<W>, Error> {
+        let mut keyed = items
+            .into_iter()
+            .map(|(fields, item)| Ok(((fields.sort_key(self.order)?, item.id()), item)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        keyed.sort_by_key(|(key, _)| *key);
+        if self.direction == Direction::Desc {
+            keyed.reverse();
+        }
+        Ok(keyed.into_iter().take(self.limit() + 1).map(|(_, item)| item).collect())
+    }
+
+    /// Turns up to `limit() + 1` sorted results into a page.
+    pub fn paginate<W: Queryable + HasId>(&self, mut items: Vec<W>) -> Result<Page<W>, Error> {
+        let limit = self.limit();
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+
+        let last = items
+            .last()
+            .filter(|_| has_more)
+            .and_then(|item| item.query_fields().map(|fields| (item.id(), fields)));
+        let next_cursor = match last {
+            Some((id, fields)) => Some(Cursor {
+                order: self.order,
+                direction: self.direction,
+                key: fields.sort_key(self.order)?,
+                id,
+            }.encode()),
+            None => None,
+        };
+
+        Ok(Page { items, next_cursor })
+    }
+
+    /// `WHERE`, `ORDER BY` and `LIMIT` clauses over the `vaas` table, fetching
+    /// one row more than the page to tell whether another one follows. Bind
+    /// parameters are numbered from `$1`.
+    pub fn to_sql(&self) -> Result<(String, Vec<SqlValue>), Error> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        let mut condition = |sql: &str, value: SqlValue| {
+            values.push(value);
+            conditions.push(sql.replace('?', &format!("${}", values.len())));
+        };
+
+        if let Some(chain) = self.emitter_chain {
+            condition("emitter_chain = ?", SqlValue::Int(i64::from(chain)));
+        }
+        if let Some(emitter) = &self.emitter_address {
+            condition("emitter_address = ?", SqlValue::Text(emitter.clone()));
+        }
+        if let Some(from) = self.sequence_from {
+            condition("sequence >= ?", SqlValue::Int(sequence_to_i64(from)?));
+        }
+        if let Some(to) = self.sequence_to {
+            condition("sequence <= ?", SqlValue::Int(sequence_to_i64(to)?));
+        }
+        if let Some(from) = self.time_from {
+            condition("vaa_timestamp >= ?", SqlValue::Time(from));
+        }
+        if let Some(to) = self.time_to {
+            condition("vaa_timestamp < ?", SqlValue::Time(to));
+        }
+        if let Some(index) = self.guardian_set_index {
+            condition("guardian_set_index = ?", SqlValue::Int(i64::from(index)));
+        }
+
+        let column = match self.order {
+            QueryOrder::ObservedAt => "observed_at",
+            QueryOrder::Sequence => "sequence",
+            QueryOrder::Timestamp => "vaa_timestamp",
+        };
+        let (operator, direction) = match self.direction {
+            Direction::Asc => (">", "ASC"),
+            Direction::Desc => ("<", "DESC"),
+        };
+        if let Some(cursor) = self.after()? {
+            let key = match self.order {
+                QueryOrder::ObservedAt => SqlValue::Time(DateTime::from_timestamp_nanos(cursor.key)),
+                QueryOrder::Sequence => SqlValue::Int(cursor.key),
+                QueryOrder::Timestamp => SqlValue::Time(DateTime::from_timestamp(cursor.key, 0).unwrap_or_default()),
+            };
+            values.push(key);
+            values.push(SqlValue::Id(cursor.id));
+            conditions.push(format!("({}, id) {} (${}, ${})", column, operator, values.len() - 1, values.len()));
+        }
+
+        let mut sql = String::new();
+        if !conditions.is_empty() {
+            sql.push_str(&format!("WHERE {} ", conditions.join(" AND ")));
+        }
+        sql.push_str(&format!(
+            "ORDER BY {column} {direction}, id {direction} LIMIT {}",
+            self.limit() + 1
+        ));
+        Ok((sql, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(sequence: u64) -> QueryFields {
+        QueryFields {
+            emitter_chain: 2,
+            emitter_address: "11".repeat(32),
+            sequence,
+            timestamp: DateTime::from_timestamp(1_700_000_000 + sequence as i64, 0).unwrap(),
+            guardian_set_index: 4,
+            observed_at: DateTime::from_timestamp(1_800_000_000, sequence as u32).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor { order: QueryOrder::Sequence, direction: Direction::Desc, key: 42, id: Uuid::new_v4() };
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(Cursor::decode("not a cursor").is_err());
+
+        let query = VaaQuery { cursor: Some(cursor.encode()), ..Default::default() };
+        assert!(query.to_sql().is_err());
+    }
+
+    #[test]
+    fn test_matches_filters_and_cursor() {
+        let query = VaaQuery {
+            emitter_chain: Some(2),
+            sequence_from: Some(2),
+            sequence_to: Some(5),
+            order: QueryOrder::Sequence,
+            ..Default::default()
+        };
+        let id = Uuid::nil();
+        assert!(!query.matches(&fields(1), id).unwrap());
+        assert!(query.matches(&fields(3), id).unwrap());
+        assert!(!query.matches(&fields(6), id).unwrap());
+
+        let cursor = Cursor { order: QueryOrder::Sequence, direction: Direction::Asc, key: 3, id };
+        let after = VaaQuery { cursor: Some(cursor.encode()), ..query };
+        assert!(!after.matches(&fields(3), id).unwrap());
+        assert!(after.matches(&fields(4), id).unwrap());
+    }
+
+    #[test]
+    fn test_to_sql() {
+        let query = VaaQuery {
+            emitter_chain: Some(2),
+            guardian_set_index: Some(4),
+            direction: Direction::Desc,
+            limit: Some(10),
+            ..Default::default()
+        };
+        let (sql, values) = query.to_sql().unwrap();
+        assert_eq!(sql, "WHERE emitter_chain = $1 AND guardian_set_index = $2 ORDER BY observed_at DESC, id DESC LIMIT 11");
+        assert_eq!(values, vec![SqlValue::Int(2), SqlValue::Int(4)]);
+
+        // Sequences SQL can't store are rejected, not wrapped
+        let query = VaaQuery { sequence_from: Some(u64::MAX), ..Default::default() };
+        assert!(query.to_sql().is_err());
+        assert_eq!(sequence_to_i64(42).unwrap(), 42);
+        assert!(sequence_to_i64(u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+// Redis cache for hot data, also carrying the pub/sub fan-out of ingested VAAs
+
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::Utc;
+use redis::{aio::ConnectionManager, AsyncCommands, Client, RedisError};
+use serde::Serialize;
+use schemars::JsonSchema;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::library::errors::Error;
+use crate::domain::wormhole::chains;
+use crate::domain::wormhole::models::{VaaRecord, VaaRecordView};
+use super::{RepositoryImpl, ReadModel, VaaRepositoryImpl};
+use super::query::{Page, Queryable, VaaQuery};
+
+const VAA_KEY_PREFIX: &str = "wormhole:vaa:";
+// Record IDs scored by observation time in milliseconds
+const RECENT_KEY: &str = "wormhole:vaas:recent";
+// `chain/emitter` members scored by their highest sequence
+const LATEST_KEY: &str = "wormhole:emitters:latest";
+
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Highest sequence seen for an emitter, shared by every replica.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct LatestSequence {
+    pub emitter_chain: u16,
+    pub emitter_chain_name: Option<String>,
+    pub emitter_address: String,
+    pub sequence: u64,
+}
+
+/// Redis-backed storage for recent VAA records, which expire `ttl` seconds
+/// after being written. Only hot data lives here, not the full history.
+#[derive(Clone)]
+pub struct RedisRepository {
+    client: Client,
+    conn: ConnectionManager,
+    ttl: u64,
+}
+
+impl RedisRepository {
+    pub async fn connect(url: &str, ttl: u64) -> Result<Self, Error> {
+        let client = Client::open(url)
+            .map_err(|e| Error::Storage(format!("Invalid Redis URL: {}", e)))?;
+        let conn = ConnectionManager::new(client.clone())
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to connect to Redis: {}", e)))?;
+        info!("Redis cache ready");
+        Ok(Self { client, conn, ttl: ttl.max(1) })
+    }
+
+    /// Client to open dedicated connections with, e.g. for subscriptions
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub async fn ping(&self) -> Result<(), Error> {
+        let mut conn = self.conn.clone();
+        match tokio::time::timeout(PING_TIMEOUT, redis::cmd("PING").query_async::<String>(&mut conn)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(cache_error(e)),
+            Err(_) => Err(Error::Storage("Redis did not answer PING in time".to_string())),
+        }
+    }
+
+    pub async fn publish(&self, channel: &str, payload: String) -> Result<(), Error> {
+        let mut conn = self.conn.clone();
+        conn.publish::<_, _, ()>(channel, payload).await.map_err(cache_error)
+    }
+
+    /// Highest sequence of every emitter seen, by chain and emitter address
+    pub async fn latest_sequences(&self) -> Result<Vec<LatestSequence>, Error> {
+        let mut conn = self.conn.clone();
+        let scores: Vec<(String, f64)> = conn.zrange_withscores(LATEST_KEY, 0, -1).await.map_err(cache_error)?;
+
+        let mut latest: Vec<LatestSequence> = scores
+            .into_iter()
+            .filter_map(|(emitter, sequence)| {
+                let (chain, address) = emitter.split_once('/')?;
+                let emitter_chain = chain.parse().ok()?;
+                Some(LatestSequence {
+                    emitter_chain,
+                    emitter_chain_name: chains::name(emitter_chain),
+                    emitter_address: address.to_string(),
+                    sequence: sequence as u64,
+                })
+            })
+            .collect();
+        latest.sort_by(|a, b| (a.emitter_chain, &a.emitter_address).cmp(&(b.emitter_chain, &b.emitter_address)));
+        Ok(latest)
+    }
+
+    // Writes the record with a fresh TTL, indexes it as recent and raises its
+    // emitter's latest sequence. Index entries past the TTL are dropped.
+    async fn insert(&self, item: &VaaRecord) -> Result<(), Error> {
+        let json = serde_json::to_string(item).map_err(|e| Error::Parsing(e.to_string()))?;
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .set_ex(vaa_key(item.id), json, self.ttl).ignore()
+            .zadd(RECENT_KEY, item.id.to_string(), item.observed_at.timestamp_millis()).ignore()
+            .zrembyscore(RECENT_KEY, "-inf", format!("({}", self.expired_before())).ignore();
+        if let Some(fields) = item.query_fields() {
+            // GT only ever raises the score, late VAAs leave it alone
+            pipe.cmd("ZADD")
+                .arg(LATEST_KEY)
+                .arg("GT")
+                .arg(fields.sequence as f64)
+                .arg(format!("{}/{}", fields.emitter_chain, fields.emitter_address))
+                .ignore();
+        }
+
+        let mut conn = self.conn.clone();
+        pipe.query_async::<()>(&mut conn).await.map_err(cache_error)
+    }
+
+    async fn fetch(&self, id: Uuid) -> Result<Option<VaaRecord>, Error> {
+        let mut conn = self.conn.clone();
+        let json: Option<String> = conn.get(vaa_key(id)).await.map_err(cache_error)?;
+        json.map(|json| parse_record(&json)).transpose()
+    }
+
+    // Records still cached, oldest observation first
+    async fn recent(&self) -> Result<Vec<VaaRecord>, Error> {
+        let mut conn = self.conn.clone();
+        let ids: Vec<String> = conn
+            .zrangebyscore(RECENT_KEY, self.expired_before(), "+inf")
+            .await
+            .map_err(cache_error)?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let keys: Vec<String> = ids.iter().map(|id| format!("{}{}", VAA_KEY_PREFIX, id)).collect();
+        let records: Vec<Option<String>> = redis::cmd("MGET").arg(keys).query_async(&mut conn).await.map_err(cache_error)?;
+        // Records expire on their own, their index entries on the next write
+        records.into_iter().flatten().map(|json| parse_record(&json)).collect()
+    }
+
+    fn expired_before(&self) -> i64 {
+        Utc::now().timestamp_millis() - (self.ttl * 1000) as i64
+    }
+}
+
+fn vaa_key(id: Uuid) -> String {
+    format!("{}{}", VAA_KEY_PREFIX, id)
+}
+
+fn parse_record(json: &str) -> Result<VaaRecord, Error> {
+    serde_json::from_str(json).map_err(|e| Error::Parsing(format!("Invalid cached VAA record: {}", e)))
+}
+
+fn cache_error(e: RedisError) -> Error {
+    Error::Storage(format!("Redis: {}", e))
+}
+
+#[async_trait]
+impl RepositoryImpl<VaaRecord, VaaRecordView> for RedisRepository {
+    async fn create(&self, item: VaaRecord) -> VaaRecord {
+        if let Err(e) = self.insert(&item).await {
+            error!("Failed to cache VAA {}: {}", item.digest, e);
+        }
+        item
+    }
+
+    async fn get(&self, id: Uuid) -> Option<VaaRecordView> {
+        match self.fetch(id).await {
+            Ok(record) => record.as_ref().map(VaaRecordView::from_write_model),
+            Err(e) => {
+                error!("Failed to read cached VAA {}: {}", id, e);
+                None
+            }
+        }
+    }
+
+    async fn list(&self) -> Vec<VaaRecordView> {
+        match self.recent().await {
+            Ok(records) => records.iter().map(VaaRecordView::from_write_model).collect(),
+            Err(e) => {
+                error!("Failed to list cached VAAs: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn delete(&self, id: Uuid) -> Option<VaaRecord> {
+        let record = self.fetch(id).await.ok().flatten()?;
+        let mut conn = self.conn.clone();
+        let deleted = redis::pipe()
+            .atomic()
+            .del(vaa_key(id)).ignore()
+            .zrem(RECENT_KEY, id.to_string()).ignore()
+            .query_async::<()>(&mut conn)
+            .await;
+        match deleted {
+            Ok(()) => Some(record),
+            Err(e) => {
+                error!("Failed to delete cached VAA {}: {}", id, e);
+                None
+            }
+        }
+    }
+
+    async fn update(&self, id: Uuid, item: VaaRecord) -> Option<VaaRecord> {
+        let json = serde_json::to_string(&item).ok()?;
+        let mut conn = self.conn.clone();
+        // Only cached records are replaced, keeping their expiry
+        let updated: Result<Option<String>, RedisError> = redis::cmd("SET")
+            .arg(vaa_key(id))
+            .arg(json)
+            .arg("XX")
+            .arg("KEEPTTL")
+            .query_async(&mut conn)
+            .await;
+        match updated {
+            Ok(Some(_)) => Some(item),
+            Ok(None) => None,
+            Err(e) => {
+                error!("Failed to update cached VAA {}: {}", id, e);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl VaaRepositoryImpl for RedisRepository {
+    async fn query(&self, query: &VaaQuery) -> Result<Page<VaaRecordView>, Error> {
+        let mut matching = Vec::new();
+        for record in self.recent().await? {
+            if let Some(fields) = record.query_fields() {
+                if query.matches(&fields, record.id)? {
+                    matching.push((fields, record));
+                }
+            }
+        }
+
+        let records = query.sort(matching)?;
+        Ok(query.paginate(records)?.map(|record| VaaRecordView::from_write_model(&record)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::wormhole::builder::test_vaa_bytes;
+
+    // Needs a Redis server to flush, e.g. `TEST_REDIS_URL=redis://127.0.0.1:6379/15`
+    #[tokio::test]
+    #[ignore = "requires a Redis server (TEST_REDIS_URL)"]
+    async fn test_redis_repository() {
+        let url = std::env::var("TEST_REDIS_URL").expect("TEST_REDIS_URL must be set");
+        let repository = RedisRepository::connect(&url, 60).await.unwrap();
+        let mut conn = repository.conn.clone();
+        redis::cmd("FLUSHDB").query_async::<()>(&mut conn).await.unwrap();
+        repository.ping().await.unwrap();
+
+        for sequence in [3, 1, 2] {
+            repository.create(VaaRecord::from_bytes(test_vaa_bytes(sequence), "mainnet").unwrap()).await;
+        }
+        let record = VaaRecord::from_bytes(test_vaa_bytes(1), "mainnet").unwrap();
+        assert_eq!(repository.get(record.id).await.unwrap().digest, record.digest);
+        assert_eq!(repository.list().await.len(), 3);
+
+        // Late sequences don't lower the latest one
+        let latest = repository.latest_sequences().await.unwrap();
+        assert_eq!((latest.len(), latest[0].emitter_chain, latest[0].sequence), (1, 2, 3));
+
+        let query = VaaQuery { order: crate::storage::query::QueryOrder::Sequence, limit: Some(2), ..Default::default() };
+        let page = repository.query(&query).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert!(page.next_cursor.is_some());
+
+        assert!(repository.delete(record.id).await.is_some());
+        assert!(repository.get(record.id).await.is_none());
+        assert!(repository.update(record.id, record.clone()).await.is_none());
+        assert_eq!(repository.list().await.len(), 2);
+    }
+}
@@ -0,0 +1,493 @@
+// VAA repository and event store shared by the SQL backends, which only differ
+// in how they connect and migrate, see `postgres.rs` and `sqlite.rs`
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    query::Query, types::Json, ColumnIndex, Database, Decode, Encode, Executor, IntoArguments, Pool, Row, Type,
+};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::library::errors::Error;
+use crate::domain::wormhole::models::{VaaRecord, VaaRecordView};
+use super::{RepositoryImpl, ReadModel, VaaRepositoryImpl};
+use super::events::{Event, EventQuery, EventStoreImpl, StoredEvent};
+use super::query::{sequence_to_i64, Page, SqlValue, VaaQuery};
+
+const VAA_COLUMNS: &str = "id, digest, message_id, emitter_chain, emitter_address, sequence, guardian_set_index, \
+    consistency_level, signature_count, payload_type, vaa_timestamp, observed_at, raw_bytes";
+
+const EVENT_COLUMNS: &str = "position, recorded_at, stream_id, payload";
+
+/// Databases with the `vaas` and `events` tables of `migrations`. The blanket
+/// implementation is the only place spelling out the sqlx bounds.
+#[async_trait]
+pub trait SqlDatabase: Database {
+    /// Runs `sql`, returning how many rows it returned, e.g. with `RETURNING id`
+    async fn execute(pool: &Pool<Self>, sql: &str, values: Vec<SqlValue>) -> Result<usize, Error>;
+    /// Runs `sql`, selecting `VAA_COLUMNS`
+    async fn fetch_records(pool: &Pool<Self>, sql: &str, values: Vec<SqlValue>) -> Result<Vec<VaaRecord>, Error>;
+    /// Runs `sql`, selecting `EVENT_COLUMNS`
+    async fn fetch_events(
+        pool: &Pool<Self>,
+        sql: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<Vec<StoredEvent<serde_json::Value>>, Error>;
+}
+
+#[async_trait]
+impl<DB> SqlDatabase for DB
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> i32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> bool: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> Option<String>: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> Vec<u8>: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> Uuid: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> DateTime<Utc>: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> Json<serde_json::Value>: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'a> &'a str: ColumnIndex<DB::Row>,
+{
+    async fn execute(pool: &Pool<Self>, sql: &str, values: Vec<SqlValue>) -> Result<usize, Error> {
+        let rows = bind_values(sqlx::query(sql), values)
+            .fetch_all(pool)
+            .await
+            .map_err(storage_error)?;
+        Ok(rows.len())
+    }
+
+    async fn fetch_records(pool: &Pool<Self>, sql: &str, values: Vec<SqlValue>) -> Result<Vec<VaaRecord>, Error> {
+        let rows = bind_values(sqlx::query(sql), values)
+            .fetch_all(pool)
+            .await
+            .map_err(storage_error)?;
+        rows.iter().map(record_from_row).collect()
+    }
+
+    async fn fetch_events(
+        pool: &Pool<Self>,
+        sql: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<Vec<StoredEvent<serde_json::Value>>, Error> {
+        let rows = bind_values(sqlx::query(sql), values)
+            .fetch_all(pool)
+            .await
+            .map_err(storage_error)?;
+        rows.iter().map(event_from_row).collect()
+    }
+}
+
+fn storage_error(e: sqlx::Error) -> Error {
+    Error::Storage(e.to_string())
+}
+
+fn bind_values<'q, DB>(
+    mut statement: Query<'q, DB, DB::Arguments<'q>>,
+    values: Vec<SqlValue>,
+) -> Query<'q, DB, DB::Arguments<'q>>
+where
+    DB: Database,
+    bool: Encode<'q, DB> + Type<DB>,
+    i64: Encode<'q, DB> + Type<DB>,
+    String: Encode<'q, DB> + Type<DB>,
+    Option<String>: Encode<'q, DB> + Type<DB>,
+    Vec<u8>: Encode<'q, DB> + Type<DB>,
+    Uuid: Encode<'q, DB> + Type<DB>,
+    DateTime<Utc>: Encode<'q, DB> + Type<DB>,
+    Json<serde_json::Value>: Encode<'q, DB> + Type<DB>,
+{
+    for value in values {
+        statement = match value {
+            SqlValue::Bool(value) => statement.bind(value),
+            SqlValue::Int(value) => statement.bind(value),
+            SqlValue::Text(value) => statement.bind(value),
+            SqlValue::OptionalText(value) => statement.bind(value),
+            SqlValue::Bytes(value) => statement.bind(value),
+            SqlValue::Time(value) => statement.bind(value),
+            SqlValue::Id(value) => statement.bind(value),
+            SqlValue::Json(value) => statement.bind(Json(value)),
+        };
+    }
+    statement
+}
+
+fn record_from_row<R>(row: &R) -> Result<VaaRecord, Error>
+where
+    R: Row,
+    for<'a> &'a str: ColumnIndex<R>,
+    for<'q> i32: Decode<'q, R::Database> + Type<R::Database>,
+    for<'q> i64: Decode<'q, R::Database> + Type<R::Database>,
+    for<'q> String: Decode<'q, R::Database> + Type<R::Database>,
+    for<'q> Option<String>: Decode<'q, R::Database> + Type<R::Database>,
+    for<'q> Vec<u8>: Decode<'q, R::Database> + Type<R::Database>,
+    for<'q> Uuid: Decode<'q, R::Database> + Type<R::Database>,
+    for<'q> DateTime<Utc>: Decode<'q, R::Database> + Type<R::Database>,
+{
+    Ok(VaaRecord {
+        id: row.try_get("id").map_err(storage_error)?,
+        digest: row.try_get("digest").map_err(storage_error)?,
+        message_id: row.try_get("message_id").map_err(storage_error)?,
+        emitter_chain: row.try_get::<i32, _>("emitter_chain").map_err(storage_error)? as u16,
+        emitter_address: row.try_get("emitter_address").map_err(storage_error)?,
+        sequence: row.try_get::<i64, _>("sequence").map_err(storage_error)? as u64,
+        guardian_set_index: row.try_get::<i64, _>("guardian_set_index").map_err(storage_error)? as u32,
+        consistency_level: row.try_get::<i32, _>("consistency_level").map_err(storage_error)? as u8,
+        signature_count: row.try_get::<i32, _>("signature_count").map_err(storage_error)? as u8,
+        payload_type: row.try_get("payload_type").map_err(storage_error)?,
+        vaa_timestamp: row.try_get("vaa_timestamp").map_err(storage_error)?,
+        observed_at: row.try_get("observed_at").map_err(storage_error)?,
+        bytes: row.try_get("raw_bytes").map_err(storage_error)?,
+    })
+}
+
+fn event_from_row<R>(row: &R) -> Result<StoredEvent<serde_json::Value>, Error>
+where
+    R: Row,
+    for<'a> &'a str: ColumnIndex<R>,
+    for<'q> i64: Decode<'q, R::Database> + Type<R::Database>,
+    for<'q> String: Decode<'q, R::Database> + Type<R::Database>,
+    for<'q> DateTime<Utc>: Decode<'q, R::Database> + Type<R::Database>,
+    for<'q> Json<serde_json::Value>: Decode<'q, R::Database> + Type<R::Database>,
+{
+    let Json(event) = row.try_get("payload").map_err(storage_error)?;
+    Ok(StoredEvent {
+        position: row.try_get("position").map_err(storage_error)?,
+        recorded_at: row.try_get("recorded_at").map_err(storage_error)?,
+        stream_id: row.try_get("stream_id").map_err(storage_error)?,
+        event,
+    })
+}
+
+/// Storage for VAA records and events in a SQL database, see `PostgresRepository`
+/// and `SqliteRepository`.
+pub struct SqlRepository<DB: Database> {
+    pool: Pool<DB>,
+}
+
+impl<DB: Database> Clone for SqlRepository<DB> {
+    fn clone(&self) -> Self {
+        Self { pool: self.pool.clone() }
+    }
+}
+
+impl<DB: SqlDatabase> SqlRepository<DB> {
+    /// Wraps a pool whose migrations were applied.
+    pub(super) fn migrated(pool: Pool<DB>) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &Pool<DB> {
+        &self.pool
+    }
+
+    // Inserts the record unless its digest is already stored. A new digest for a
+    // stored message ID is kept, flagged as conflicting.
+    async fn insert(&self, item: &VaaRecord) -> Result<(), Error> {
+        for conflicting in [false, true] {
+            let values = vec![
+                SqlValue::Id(item.id),
+                SqlValue::Text(item.digest.clone()),
+                SqlValue::Text(item.message_id.clone()),
+                SqlValue::Int(i64::from(item.emitter_chain)),
+                SqlValue::Text(item.emitter_address.clone()),
+                SqlValue::Int(sequence_to_i64(item.sequence)?),
+                SqlValue::Int(i64::from(item.guardian_set_index)),
+                SqlValue::Int(i64::from(item.consistency_level)),
+                SqlValue::Int(i64::from(item.signature_count)),
+                SqlValue::OptionalText(item.payload_type.clone()),
+                SqlValue::Time(item.vaa_timestamp),
+                SqlValue::Time(item.observed_at),
+                SqlValue::Bytes(item.bytes.clone()),
+                SqlValue::Bool(conflicting),
+            ];
+            let sql = format!(
+                "INSERT INTO vaas ({}, conflicting)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                ON CONFLICT DO NOTHING
+                RETURNING id",
+                VAA_COLUMNS,
+            );
+            let inserted = DB::execute(&self.pool, &sql, values).await? > 0;
+
+            if inserted || self.fetch(item.id).await?.is_some() {
+                if inserted && conflicting {
+                    warn!("VAA {} conflicts with the one stored for message ID {}", item.digest, item.message_id);
+                }
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    async fn fetch(&self, id: Uuid) -> Result<Option<VaaRecord>, Error> {
+        let sql = format!("SELECT {} FROM vaas WHERE id = $1", VAA_COLUMNS);
+        Ok(DB::fetch_records(&self.pool, &sql, vec![SqlValue::Id(id)]).await?.pop())
+    }
+
+    async fn select(&self, query: &VaaQuery) -> Result<Vec<VaaRecord>, Error> {
+        let (clauses, values) = query.to_sql()?;
+        DB::fetch_records(&self.pool, &format!("SELECT {} FROM vaas {}", VAA_COLUMNS, clauses), values).await
+    }
+}
+
+#[async_trait]
+impl<DB: SqlDatabase> RepositoryImpl<VaaRecord, VaaRecordView> for SqlRepository<DB> {
+    async fn create(&self, item: VaaRecord) -> VaaRecord {
+        if let Err(e) = self.insert(&item).await {
+            error!("Failed to store VAA {}: {}", item.digest, e);
+        }
+        item
+    }
+
+    async fn get(&self, id: Uuid) -> Option<VaaRecordView> {
+        match self.fetch(id).await {
+            Ok(record) => record.map(|record| VaaRecordView::from_write_model(&record)),
+            Err(e) => {
+                error!("Failed to get VAA {}: {}", id, e);
+                None
+            }
+        }
+    }
+
+    async fn list(&self) -> Vec<VaaRecordView> {
+        let sql = format!("SELECT {} FROM vaas ORDER BY observed_at, id", VAA_COLUMNS);
+        match DB::fetch_records(&self.pool, &sql, Vec::new()).await {
+            Ok(records) => records.iter().map(VaaRecordView::from_write_model).collect(),
+            Err(e) => {
+                error!("Failed to list VAAs: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn delete(&self, id: Uuid) -> Option<VaaRecord> {
+        let sql = format!("DELETE FROM vaas WHERE id = $1 RETURNING {}", VAA_COLUMNS);
+        match DB::fetch_records(&self.pool, &sql, vec![SqlValue::Id(id)]).await {
+            Ok(mut deleted) => deleted.pop(),
+            Err(e) => {
+                error!("Failed to delete VAA {}: {}", id, e);
+                None
+            }
+        }
+    }
+
+    async fn update(&self, id: Uuid, item: VaaRecord) -> Option<VaaRecord> {
+        let values = vec![
+            SqlValue::Id(id),
+            SqlValue::Bytes(item.bytes.clone()),
+            SqlValue::Time(item.observed_at),
+            SqlValue::Int(i64::from(item.consistency_level)),
+            SqlValue::Int(i64::from(item.signature_count)),
+            SqlValue::OptionalText(item.payload_type.clone()),
+        ];
+        let updated = DB::execute(
+            &self.pool,
+            "UPDATE vaas SET raw_bytes = $2, observed_at = $3, consistency_level = $4, signature_count = $5,
+                payload_type = $6
+            WHERE id = $1
+            RETURNING id",
+            values,
+        )
+        .await;
+
+        match updated {
+            Ok(rows) if rows > 0 => Some(item),
+            Ok(_) => None,
+            Err(e) => {
+                error!("Failed to update VAA {}: {}", id, e);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<DB: SqlDatabase> VaaRepositoryImpl for SqlRepository<DB> {
+    async fn query(&self, query: &VaaQuery) -> Result<Page<VaaRecordView>, Error> {
+        let records = self.select(query).await?;
+        Ok(query.paginate(records)?.map(|record| VaaRecordView::from_write_model(&record)))
+    }
+}
+
+#[async_trait]
+impl<DB: SqlDatabase, E: Event> EventStoreImpl<E> for SqlRepository<DB> {
+    async fn append(&self, event: E) -> Result<StoredEvent<E>, Error> {
+        let payload = serde_json::to_value(&event).map_err(|e| Error::Parsing(e.to_string()))?;
+        let values = vec![
+            SqlValue::Time(Utc::now()),
+            SqlValue::Text(event.kind().to_string()),
+            SqlValue::Text(event.stream_id()),
+            SqlValue::Json(payload),
+        ];
+        let sql = format!(
+            "INSERT INTO events (recorded_at, kind, stream_id, payload)
+            VALUES ($1, $2, $3, $4)
+            RETURNING {}",
+            EVENT_COLUMNS,
+        );
+        let stored = DB::fetch_events(&self.pool, &sql, values)
+            .await?
+            .pop()
+            .ok_or_else(|| Error::Storage("Event was not recorded".to_string()))?;
+        Ok(with_event(stored, event))
+    }
+
+    async fn read(&self, query: &EventQuery) -> Result<Vec<StoredEvent<E>>, Error> {
+        let (clauses, values) = query.to_sql();
+        let sql = format!("SELECT {} FROM events {}", EVENT_COLUMNS, clauses);
+        DB::fetch_events(&self.pool, &sql, values)
+            .await?
+            .into_iter()
+            .map(|mut stored| {
+                let payload = std::mem::take(&mut stored.event);
+                let event = serde_json::from_value(payload).map_err(|e| Error::Parsing(e.to_string()))?;
+                Ok(with_event(stored, event))
+            })
+            .collect()
+    }
+}
+
+// Recorded event with its JSON payload swapped for the typed event
+fn with_event<E>(stored: StoredEvent<serde_json::Value>, event: E) -> StoredEvent<E> {
+    StoredEvent {
+        position: stored.position,
+        recorded_at: stored.recorded_at,
+        stream_id: stored.stream_id,
+        event,
+    }
+}
+
+/// Cases every SQL backend passes, each run on a fresh database by the backend's tests.
+#[cfg(test)]
+pub mod tests {
+    use serde::{Deserialize, Serialize};
+    use super::*;
+    use crate::domain::wormhole::builder::{test_vaa, test_vaa_bytes, VaaBuilder};
+    use crate::storage::events::EventStore;
+    use crate::storage::memory::MemoryRepository;
+    use crate::storage::query::{Direction, QueryOrder};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Seen(String);
+
+    impl Event for Seen {
+        fn kind(&self) -> &'static str {
+            "seen"
+        }
+
+        fn stream_id(&self) -> String {
+            self.0.clone()
+        }
+    }
+
+    pub async fn stores_each_digest_once<DB: SqlDatabase>(repository: &SqlRepository<DB>) {
+        let record = VaaRecord::from_bytes(test_vaa(7).payload(b"first").to_bytes().unwrap(), "mainnet").unwrap();
+        repository.create(record.clone()).await;
+        // Same digest again is a no-op
+        repository.create(record.clone()).await;
+        // Different body for the same message ID is kept as a conflict
+        let conflicting = VaaRecord::from_bytes(test_vaa(7).payload(b"second").to_bytes().unwrap(), "mainnet").unwrap();
+        repository.create(conflicting.clone()).await;
+
+        let stored = RepositoryImpl::<VaaRecord, VaaRecordView>::get(repository, record.id).await.unwrap();
+        assert_eq!(stored.digest, record.digest);
+        assert_eq!(stored.message_id, record.message_id);
+        let listed = RepositoryImpl::<VaaRecord, VaaRecordView>::list(repository).await;
+        assert_eq!(listed.len(), 2);
+
+        let sql = format!("SELECT {} FROM vaas WHERE conflicting", VAA_COLUMNS);
+        let flagged = DB::fetch_records(repository.pool(), &sql, Vec::new()).await.unwrap();
+        assert_eq!(flagged.iter().map(|record| &record.digest).collect::<Vec<_>>(), vec![&conflicting.digest]);
+
+        assert!(repository.delete(conflicting.id).await.is_some());
+        assert!(repository.delete(conflicting.id).await.is_none());
+        assert!(repository.update(record.id, record.clone()).await.is_some());
+    }
+
+    pub async fn stores_decoded_fields<DB: SqlDatabase>(repository: &SqlRepository<DB>) {
+        // Token Bridge transfer from the Ethereum emitter
+        let mut emitter = [0u8; 32];
+        emitter[12..].copy_from_slice(&hex::decode("3ee18b2214aff97000d974cf647e7c347e8fa585").unwrap());
+        let mut payload = vec![1];
+        payload.extend_from_slice(&[0u8; 32]);
+        payload.extend_from_slice(&[0xaa; 32]);
+        payload.extend_from_slice(&2u16.to_be_bytes());
+        payload.extend_from_slice(&[0xbb; 32]);
+        payload.extend_from_slice(&1u16.to_be_bytes());
+        payload.extend_from_slice(&[0u8; 32]);
+        let bytes = VaaBuilder::new(2, emitter, 3)
+            .timestamp(1_700_000_000)
+            .consistency_level(15)
+            .payload(payload)
+            .devnet_signers()
+            .to_bytes()
+            .unwrap();
+
+        let record = VaaRecord::from_bytes(bytes, "mainnet").unwrap();
+        assert_eq!(record.payload_type.as_deref(), Some("token_transfer"));
+        repository.create(record.clone()).await;
+        let stored = RepositoryImpl::<VaaRecord, VaaRecordView>::get(repository, record.id).await.unwrap();
+        assert_eq!(stored.emitter_address, hex::encode(emitter));
+        assert_eq!((stored.sequence, stored.consistency_level, stored.signature_count), (3, 15, 1));
+        assert_eq!(stored.payload_type.as_deref(), Some("token_transfer"));
+        assert_eq!(stored.vaa_timestamp.timestamp(), 1_700_000_000);
+    }
+
+    pub async fn query_pages_like_memory<DB: SqlDatabase>(repository: &SqlRepository<DB>) {
+        let memory = MemoryRepository::<VaaRecord>::new();
+        for sequence in 1..=7 {
+            let record = VaaRecord::from_bytes(test_vaa_bytes(sequence), "mainnet").unwrap();
+            repository.create(record.clone()).await;
+            RepositoryImpl::<VaaRecord, VaaRecordView>::create(&memory, record).await;
+        }
+
+        for direction in [Direction::Asc, Direction::Desc] {
+            let mut query = VaaQuery {
+                emitter_chain: Some(2),
+                sequence_from: Some(2),
+                order: QueryOrder::Sequence,
+                direction,
+                limit: Some(4),
+                ..Default::default()
+            };
+            let mut pages = Vec::new();
+            loop {
+                let page = repository.query(&query).await.unwrap();
+                let expected = VaaRepositoryImpl::query(&memory, &query).await.unwrap();
+                let digests = |page: &Page<VaaRecordView>| page.items.iter().map(|vaa| vaa.digest.clone()).collect::<Vec<_>>();
+                assert_eq!(digests(&page), digests(&expected));
+                assert_eq!(page.next_cursor, expected.next_cursor);
+
+                pages.push(page.items.len());
+                match page.next_cursor {
+                    Some(cursor) => query.cursor = Some(cursor),
+                    None => break,
+                }
+            }
+            assert_eq!(pages, vec![4, 2]);
+        }
+
+        let other_chain = VaaQuery { emitter_chain: Some(1), ..Default::default() };
+        assert!(repository.query(&other_chain).await.unwrap().items.is_empty());
+    }
+
+    pub async fn event_store_is_append_only<DB: SqlDatabase>(repository: &SqlRepository<DB>) {
+        let store = EventStore::new(repository.clone());
+        for stream in ["a", "b", "a"] {
+            store.append(Seen(stream.to_string())).await.unwrap();
+        }
+
+        let query = EventQuery { after: 1, stream_ids: vec!["a".to_string()], ..Default::default() };
+        let events = store.read(&query).await.unwrap();
+        assert_eq!((events.len(), events[0].position), (1, 3));
+        assert_eq!(events[0].event, Seen("a".to_string()));
+
+        // Recorded events can't be rewritten
+        assert!(DB::execute(repository.pool(), "DELETE FROM events", Vec::new()).await.is_err());
+    }
+}
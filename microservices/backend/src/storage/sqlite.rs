@@ -0,0 +1,102 @@
+use std::str::FromStr;
+use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use tracing::info;
+
+use crate::library::errors::Error;
+use super::sql::SqlRepository;
+
+const MAX_CONNECTIONS: u32 = 4;
+
+/// Embedded SQLite storage for VAA records, see `migrations/sqlite`. Same
+/// schema as the Postgres backend, for deployments without a database server.
+pub type SqliteRepository = SqlRepository<Sqlite>;
+
+impl SqlRepository<Sqlite> {
+    /// Opens the database at `url` (`sqlite://path/to/file.db` or `sqlite::memory:`),
+    /// creating the file if needed.
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        let options = SqliteConnectOptions::from_str(url)
+            .map_err(|e| Error::Storage(format!("Invalid SQLite URL {}: {}", url, e)))?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal);
+
+        // Every connection to an in-memory database opens a new, empty one
+        let pool = if url.contains(":memory:") || url.contains("mode=memory") {
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            SqlitePoolOptions::new().max_connections(MAX_CONNECTIONS)
+        }
+        .connect_with(options)
+        .await
+        .map_err(|e| Error::Storage(format!("Failed to open SQLite database: {}", e)))?;
+
+        Self::from_pool(pool).await
+    }
+
+    /// Wraps an existing pool, applying pending migrations first.
+    pub async fn from_pool(pool: SqlitePool) -> Result<Self, Error> {
+        sqlx::migrate!("./migrations/sqlite")
+            .run(&pool)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to run SQLite migrations: {}", e)))?;
+        info!("SQLite storage ready");
+        Ok(Self::migrated(pool))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use super::*;
+    use crate::domain::wormhole::builder::test_vaa;
+    use crate::domain::wormhole::models::{VaaRecord, VaaRecordView};
+    use crate::storage::sql::tests as suite;
+    use crate::storage::RepositoryImpl;
+
+    async fn in_memory() -> SqliteRepository {
+        SqliteRepository::connect("sqlite::memory:").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_repository() {
+        suite::stores_each_digest_once(&in_memory().await).await;
+    }
+
+    #[tokio::test]
+    async fn test_stores_decoded_fields() {
+        suite::stores_decoded_fields(&in_memory().await).await;
+    }
+
+    #[tokio::test]
+    async fn test_query_pages_like_memory() {
+        suite::query_pages_like_memory(&in_memory().await).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_event_store() {
+        suite::event_store_is_append_only(&in_memory().await).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_survives_restart() {
+        let path = std::env::temp_dir().join(format!("vaa_observer_test_{}.db", Uuid::new_v4().simple()));
+        let url = format!("sqlite://{}", path.display());
+
+        let record = VaaRecord::from_bytes(test_vaa(1).payload(b"kept").to_bytes().unwrap(), "mainnet").unwrap();
+        let repository = SqliteRepository::connect(&url).await.unwrap();
+        repository.create(record.clone()).await;
+        repository.pool().close().await;
+
+        let reopened = SqliteRepository::connect(&url).await.unwrap();
+        let stored = RepositoryImpl::<VaaRecord, VaaRecordView>::get(&reopened, record.id).await;
+        assert_eq!(stored.map(|stored| stored.digest), Some(record.digest));
+        reopened.pool().close().await;
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}